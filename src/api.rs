@@ -1,99 +1,756 @@
-use std::collections::{BTreeMap, HashMap};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::{SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
 use serde_json::Value;
+use tracing::{debug, warn};
 
 use crate::{
+    core::{
+        http,
+        utils::{parse_modstring, write_atomic_with},
+    },
     error::ThermiteError,
-    model::{Mod, ModVersion},
+    model::{IndexDiff, Mod, ModVersion, NorthstarRelease, PackageIndex},
 };
 
+/// A package exactly as returned by Thunderstore's v1 package list
+///
+/// Most consumers should use [`Mod`] instead, this is exposed for fields it doesn't include.
+/// Any fields not listed here are kept in `_extra`.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct PackageListing {
+    pub name: String,
+    pub owner: String,
+    ///Versions of the package, newest first
+    pub versions: Vec<PackageVersion>,
+    #[serde(default)]
+    pub rating_score: i64,
+    #[serde(default)]
+    pub is_deprecated: bool,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub date_created: String,
+    #[serde(default)]
+    pub date_updated: String,
+    #[serde(flatten)]
+    pub _extra: HashMap<String, Value>,
+}
+
+/// A version of a package exactly as returned by Thunderstore's v1 package list
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct PackageVersion {
+    pub dependencies: Vec<String>,
+    pub description: String,
+    pub download_url: String,
+    pub file_size: u64,
+    pub version_number: String,
+    pub full_name: String,
+    #[serde(default)]
+    pub downloads: u64,
+    #[serde(default)]
+    pub date_created: String,
+
+    #[serde(flatten)]
+    pub _extra: HashMap<String, Value>,
+}
+
+impl From<&PackageListing> for Mod {
+    fn from(value: &PackageListing) -> Self {
+        map_listing(value)
+    }
+}
+
+impl From<PackageListing> for Mod {
+    fn from(value: PackageListing) -> Self {
+        map_listing(&value)
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
-struct PackageListing {
+struct ExperimentalPackage {
+    namespace: String,
     name: String,
-    owner: String,
-    versions: Vec<PackageVersion>,
+    latest: ExperimentalVersion,
+    #[serde(default)]
+    rating_score: i64,
+    #[serde(default)]
+    is_deprecated: bool,
+    #[serde(default)]
+    date_created: String,
+    #[serde(default)]
+    date_updated: String,
     #[serde(flatten)]
     _extra: HashMap<String, Value>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
-struct PackageVersion {
+struct ExperimentalVersion {
+    name: String,
     dependencies: Vec<String>,
     description: String,
     download_url: String,
-    file_size: u64,
     version_number: String,
     full_name: String,
+    #[serde(default)]
+    downloads: u64,
+    #[serde(default)]
+    date_created: String,
 
     #[serde(flatten)]
     _extra: HashMap<String, Value>,
 }
 
+#[derive(Deserialize, Serialize, Clone, Debug)]
+struct Markdown {
+    markdown: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct InitiatedUpload {
+    user_media: UserMedia,
+    upload_urls: Vec<UploadPart>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct UserMedia {
+    uuid: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct UploadPart {
+    part_number: u32,
+    url: String,
+    offset: u64,
+    length: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct CompletedPart {
+    #[serde(rename = "ETag")]
+    etag: String,
+    #[serde(rename = "PartNumber")]
+    part_number: u32,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct SubmissionMetadata<'a> {
+    author_name: &'a str,
+    categories: &'a [String],
+    communities: &'a [String],
+    community_categories: HashMap<&'a str, &'a [String]>,
+    has_nsfw_content: bool,
+    upload_uuid: &'a str,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct Submission {
+    package_version: ExperimentalVersion,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+struct IndexCache {
+    fetched_at: u64,
+    index: PackageIndex,
+}
+
+/// An index returned by [`get_package_index_cached`] or [`load_cached_index`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedIndex {
+    pub index: PackageIndex,
+    /// When the index was fetched from Thunderstore
+    pub fetched_at: SystemTime,
+    /// `true` if the index was loaded from the cache because Thunderstore couldn't be reached
+    pub stale: bool,
+}
+
+/// Options for [`publish`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PublishOptions {
+    /// Service account token used to authenticate with Thunderstore
+    pub token: String,
+    /// Team the package will be published under
+    pub author: String,
+    /// Communities the package will be listed in, e.g. `northstar`
+    pub communities: Vec<String>,
+    /// Categories the package will be listed under in every community
+    pub categories: Vec<String>,
+    pub nsfw: bool,
+}
+
+const BASE_URL: &str = "https://northstar.thunderstore.io";
+const CACHE_FILE: &str = "package_index.json";
+const GITHUB_RELEASES_URL: &str = "https://api.github.com/repos/R2Northstar/Northstar/releases";
+
 /// Get a list of available packges from `northstar.thunderstore.io`
 ///
 /// # Errors
 /// * IO Errors
 /// * Unexpected response format from Thunderstore
-pub fn get_package_index() -> Result<Vec<Mod>, ThermiteError> {
-    let raw = ureq::get("https://northstar.thunderstore.io/c/northstar/api/v1/package/")
+pub fn get_package_index() -> Result<PackageIndex, ThermiteError> {
+    fetch_index(&format!("{BASE_URL}/c/northstar/api/v1/package/"))
+}
+
+/// Get Northstar's releases from GitHub, newest first. Release candidates are included and marked as `prerelease`
+///
+/// # Errors
+/// * IO Errors
+/// * Unexpected response format from GitHub
+pub fn northstar_releases() -> Result<Vec<NorthstarRelease>, ThermiteError> {
+    let raw = http::agent()
+        .get(&format!("{GITHUB_RELEASES_URL}?per_page=100"))
+        .set("accept", "application/vnd.github+json")
+        .call()?;
+
+    Ok(serde_json::from_str(&raw.into_string()?)?)
+}
+
+/// Get the release notes for a version of Northstar from GitHub, rendered as markdown
+///
+/// Returns `None` if there is no release for the version
+///
+/// # Errors
+/// * IO Errors
+/// * Unexpected response format from GitHub
+pub fn northstar_release_notes(version: impl AsRef<str>) -> Result<Option<String>, ThermiteError> {
+    let tag = format!("v{}", version.as_ref().trim_start_matches('v'));
+    let raw = match http::agent()
+        .get(&format!("{GITHUB_RELEASES_URL}/tags/{tag}"))
+        .set("accept", "application/vnd.github+json")
+        .call()
+    {
+        Ok(res) => res,
+        Err(ureq::Error::Status(404, _)) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let release: NorthstarRelease = serde_json::from_str(&raw.into_string()?)?;
+
+    Ok(release.body)
+}
+
+/// Get the raw package list from `northstar.thunderstore.io`, without converting it to `Mod`s
+///
+/// # Errors
+/// * IO Errors
+/// * Unexpected response format from Thunderstore
+pub fn get_package_listings() -> Result<Vec<PackageListing>, ThermiteError> {
+    let raw = http::agent()
+        .get(&format!("{BASE_URL}/c/northstar/api/v1/package/"))
         .set("accept", "application/json")
         .call()?;
-    let parsed: Vec<PackageListing> = serde_json::from_str(&raw.into_string()?)?;
-    let index = map_response(&parsed);
+
+    let mut listings = vec![];
+    stream_listings(raw.into_reader(), |listing| listings.push(listing))?;
+
+    Ok(listings)
+}
+
+/// Get the package index like [`get_package_index`], falling back to the last index fetched
+/// by this function if Thunderstore can't be reached.
+///
+/// Every successful fetch is written to `cache_dir`; if that fails the fresh index is still
+/// returned. The returned `CachedIndex` is marked as `stale` when it was loaded from the cache
+/// instead, which also happens when the connection drops while the index is being read.
+///
+/// # Errors
+/// * Thunderstore can't be reached and there is no cached index
+/// * IO Errors
+/// * Unexpected response format from Thunderstore
+pub fn get_package_index_cached(cache_dir: impl AsRef<Path>) -> Result<CachedIndex, ThermiteError> {
+    with_cache(get_package_index(), cache_dir.as_ref())
+}
+
+/// Load the index cached by [`get_package_index_cached`] without making any network requests
+///
+/// # Errors
+/// * There is no cached index in `cache_dir`
+/// * The cache file is malformed
+pub fn load_cached_index(cache_dir: impl AsRef<Path>) -> Result<CachedIndex, ThermiteError> {
+    let path = cache_dir.as_ref().join(CACHE_FILE);
+    if !path.try_exists()? {
+        return Err(ThermiteError::MissingFile(Box::new(path)));
+    }
+
+    let cache: IndexCache = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    Ok(CachedIndex {
+        index: cache.index,
+        fetched_at: UNIX_EPOCH + Duration::from_secs(cache.fetched_at),
+        stale: true,
+    })
+}
+
+fn with_cache(
+    fetched: Result<PackageIndex, ThermiteError>,
+    cache_dir: &Path,
+) -> Result<CachedIndex, ThermiteError> {
+    match fetched {
+        Ok(index) => {
+            let fetched_at = SystemTime::now();
+            let cache = IndexCache {
+                fetched_at: fetched_at
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default(),
+                index,
+            };
+
+            let written = serde_json::to_vec(&cache)
+                .map_err(ThermiteError::from)
+                .and_then(|data| write_atomic_with(cache_dir.join(CACHE_FILE), data, false));
+            if let Err(e) = written {
+                warn!("Unable to cache the package index: {e}");
+            }
+
+            Ok(CachedIndex {
+                index: cache.index,
+                fetched_at,
+                stale: false,
+            })
+        }
+        Err(
+            e @ (ThermiteError::NetworkError(_)
+            | ThermiteError::Timeout
+            | ThermiteError::IoError(_)),
+        ) => {
+            warn!("Unable to fetch package index, using cache: {e}");
+            load_cached_index(cache_dir).map_err(|_| e)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Get a list of available packages from any community on `thunderstore.io`, e.g. `"northstar"`
+///
+/// # Errors
+/// * IO Errors
+/// * Unexpected response format from Thunderstore
+pub fn get_community_index(community: impl AsRef<str>) -> Result<PackageIndex, ThermiteError> {
+    fetch_index(&format!(
+        "https://thunderstore.io/c/{}/api/v1/package/",
+        community.as_ref()
+    ))
+}
+
+/// Get the indexes of several communities and merge them into one, see [`PackageIndex::merge`]
+///
+/// # Errors
+/// * IO Errors
+/// * Unexpected response format from Thunderstore
+pub fn get_merged_index(communities: &[impl AsRef<str>]) -> Result<PackageIndex, ThermiteError> {
+    let mut index = PackageIndex::default();
+    for community in communities {
+        index.merge(get_community_index(community)?);
+    }
 
     Ok(index)
 }
 
-fn map_response(res: &[PackageListing]) -> Vec<Mod> {
-    res.iter()
-        .map(|e| {
-            let versions = &e.versions;
-            let latest = versions[0].clone();
-            let mut urls = BTreeMap::new();
-
-            for v in versions {
-                urls.insert(
-                    v.version_number.clone(),
-                    ModVersion {
-                        name: e.name.clone(),
-                        full_name: v.full_name.clone(),
-                        version: v.version_number.clone(),
-                        desc: v.description.clone(),
-                        file_size: v.file_size,
-                        deps: v
-                            .dependencies
-                            .iter()
-                            .filter(|e| !e.contains("northstar-Northstar"))
-                            .cloned()
-                            .collect::<Vec<String>>(),
-                        installed: false,
-                        global: false,
-                        url: v.download_url.clone(),
-                    },
-                );
+fn fetch_index(url: &str) -> Result<PackageIndex, ThermiteError> {
+    let raw = http::agent()
+        .get(url)
+        .set("accept", "application/json")
+        .call()?;
+
+    parse_index(raw.into_reader())
+}
+
+/// Fetch the index again and update `existing` in place, only rebuilding packages that have
+/// changed since it was fetched.
+///
+/// A package counts as changed if its `date_updated` differs from the one in `existing`, or if
+/// it was updated after `since` (the time `existing` was fetched, in the same ISO 8601 format
+/// Thunderstore uses). Packages are identified by `author-name`.
+///
+/// # Errors
+/// * IO Errors
+/// * Unexpected response format from Thunderstore
+pub fn refresh_index(
+    existing: &mut PackageIndex,
+    since: Option<&str>,
+) -> Result<IndexDiff, ThermiteError> {
+    let raw = http::agent()
+        .get(&format!("{BASE_URL}/c/northstar/api/v1/package/"))
+        .set("accept", "application/json")
+        .call()?;
+
+    refresh_from_reader(existing, since, raw.into_reader())
+}
+
+fn refresh_from_reader(
+    existing: &mut PackageIndex,
+    since: Option<&str>,
+    reader: impl Read,
+) -> Result<IndexDiff, ThermiteError> {
+    let mut old: HashMap<String, Mod> = std::mem::take(existing)
+        .into_iter()
+        .map(|m| (format!("{}-{}", m.author, m.name), m))
+        .collect();
+    let mut diff = IndexDiff::default();
+    let mut mods = vec![];
+
+    stream_listings(reader, |listing| {
+        let key = format!("{}-{}", listing.owner, listing.name);
+        match old.remove(&key) {
+            Some(m)
+                if m.date_updated == listing.date_updated
+                    && since.map_or(true, |s| listing.date_updated.as_str() <= s) =>
+            {
+                mods.push(m);
+            }
+            Some(_) => {
+                mods.push(map_listing(&listing));
+                diff.updated.push(key);
             }
+            None => {
+                mods.push(map_listing(&listing));
+                diff.added.push(key);
+            }
+        }
+    })?;
 
-            Mod {
-                name: e.name.clone(),
-                author: e.owner.clone(),
-                latest: latest.version_number,
-                versions: urls,
-                installed: false,
-                global: false,
-                upgradable: false,
+    diff.removed = old.into_keys().collect();
+    diff.removed.sort();
+    *existing = mods.into();
+
+    Ok(diff)
+}
+
+/// Deserializes the index straight from the reader, converting each listing as it's parsed
+/// so that neither the raw response nor the full list of `PackageListing`s is held in memory
+fn parse_index(reader: impl Read) -> Result<PackageIndex, ThermiteError> {
+    let mut mods = vec![];
+    stream_listings(reader, |listing| mods.push(map_listing(&listing)))?;
+
+    Ok(mods.into())
+}
+
+fn stream_listings(reader: impl Read, f: impl FnMut(PackageListing)) -> Result<(), ThermiteError> {
+    struct ListingVisitor<F>(F);
+
+    impl<'de, F: FnMut(PackageListing)> Visitor<'de> for ListingVisitor<F> {
+        type Value = ();
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a list of packages")
+        }
+
+        fn visit_seq<A>(mut self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            while let Some(listing) = seq.next_element::<PackageListing>()? {
+                (self.0)(listing);
+            }
+
+            Ok(())
+        }
+    }
+
+    let mut de = serde_json::Deserializer::from_reader(BufReader::new(reader));
+    de.deserialize_seq(ListingVisitor(f))
+        .and_then(|()| de.end())
+        .map_err(|e| {
+            // a connection dropped partway through the body is a network problem, not bad JSON
+            if e.is_io() {
+                ThermiteError::IoError(e.into())
+            } else {
+                e.into()
             }
         })
+}
+
+/// Get a single package from `northstar.thunderstore.io` without fetching the whole index
+///
+/// The experimental API only includes the latest version of the package, so the returned
+/// `Mod` will only contain that version and its `file_size` will be 0
+///
+/// # Errors
+/// * IO Errors
+/// * The package doesn't exist
+/// * Unexpected response format from Thunderstore
+pub fn get_package(
+    namespace: impl AsRef<str>,
+    name: impl AsRef<str>,
+) -> Result<Mod, ThermiteError> {
+    let url = format!(
+        "{BASE_URL}/api/experimental/package/{}/{}/",
+        namespace.as_ref(),
+        name.as_ref()
+    );
+    let raw = http::agent()
+        .get(&url)
+        .set("accept", "application/json")
+        .call()?;
+    let parsed: ExperimentalPackage = serde_json::from_str(&raw.into_string()?)?;
+
+    Ok(map_experimental(&parsed))
+}
+
+/// Get the README of a specific version of a package, rendered as markdown
+///
+/// # Errors
+/// * IO Errors
+/// * The version's `full_name` isn't a valid modstring
+/// * Unexpected response format from Thunderstore
+pub fn get_readme(version: &ModVersion) -> Result<String, ThermiteError> {
+    Ok(get_markdown(version, "readme")?.unwrap_or_default())
+}
+
+/// Get the changelog of a specific version of a package, rendered as markdown
+///
+/// Returns `None` if the package doesn't have a changelog
+///
+/// # Errors
+/// * IO Errors
+/// * The version's `full_name` isn't a valid modstring
+/// * Unexpected response format from Thunderstore
+pub fn get_changelog(version: &ModVersion) -> Result<Option<String>, ThermiteError> {
+    get_markdown(version, "changelog")
+}
+
+/// Upload a packaged mod to Thunderstore and submit it to the configured communities
+///
+/// The zip should be laid out the same way `tcli build` produces, with `manifest.json`,
+/// `icon.png`, and `README.md` at its root.
+///
+/// # Errors
+/// * IO Errors
+/// * Thunderstore rejects the token or the package
+/// * Unexpected response format from Thunderstore
+pub fn publish(
+    zip_path: impl AsRef<Path>,
+    options: &PublishOptions,
+) -> Result<ModVersion, ThermiteError> {
+    let path = zip_path.as_ref();
+    let filename = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or(ThermiteError::UTF8Error)?;
+    let mut file = File::open(path)?;
+    let auth = format!("Bearer {}", options.token);
+
+    let res = post_json(
+        &format!("{BASE_URL}/api/experimental/usermedia/initiate-upload/"),
+        &auth,
+        &serde_json::json!({
+            "filename": filename,
+            "file_size_bytes": file.metadata()?.len(),
+        }),
+    )?;
+    let upload: InitiatedUpload = serde_json::from_reader(res.into_reader())?;
+    let uuid = &upload.user_media.uuid;
+
+    let parts = match upload_parts(&mut file, &upload.upload_urls) {
+        Ok(parts) => parts,
+        Err(e) => {
+            if let Err(abort) = http::agent()
+                .post(&format!(
+                    "{BASE_URL}/api/experimental/usermedia/{uuid}/abort-upload/"
+                ))
+                .set("authorization", &auth)
+                .call()
+            {
+                warn!("Failed to abort upload {uuid}: {abort}");
+            }
+            return Err(e);
+        }
+    };
+
+    post_json(
+        &format!("{BASE_URL}/api/experimental/usermedia/{uuid}/finish-upload/"),
+        &auth,
+        &serde_json::json!({ "parts": parts }),
+    )?;
+
+    let res = post_json(
+        &format!("{BASE_URL}/api/experimental/submission/submit/"),
+        &auth,
+        &submission_metadata(options, uuid),
+    )?;
+    let submission: Submission = serde_json::from_reader(res.into_reader())?;
+
+    Ok(map_experimental_version(&submission.package_version))
+}
+
+fn post_json(
+    url: &str,
+    auth: &str,
+    body: &impl Serialize,
+) -> Result<ureq::Response, ThermiteError> {
+    Ok(http::agent()
+        .post(url)
+        .set("authorization", auth)
+        .set("content-type", "application/json")
+        .send_string(&serde_json::to_string(body)?)?)
+}
+
+fn upload_parts(
+    file: &mut File,
+    parts: &[UploadPart],
+) -> Result<Vec<CompletedPart>, ThermiteError> {
+    let mut completed = Vec::with_capacity(parts.len());
+    for part in parts {
+        debug!("Uploading part {} of {}", part.part_number, parts.len());
+        file.seek(SeekFrom::Start(part.offset))?;
+        let mut chunk = Vec::with_capacity(usize::try_from(part.length)?);
+        file.by_ref().take(part.length).read_to_end(&mut chunk)?;
+
+        let res = http::agent().put(&part.url).send_bytes(&chunk)?;
+        let etag = res
+            .header("ETag")
+            .ok_or_else(|| ThermiteError::UnknownError("Upload response missing ETag".into()))?;
+        completed.push(CompletedPart {
+            etag: etag.to_owned(),
+            part_number: part.part_number,
+        });
+    }
+
+    Ok(completed)
+}
+
+fn submission_metadata<'a>(options: &'a PublishOptions, uuid: &'a str) -> SubmissionMetadata<'a> {
+    SubmissionMetadata {
+        author_name: &options.author,
+        categories: &options.categories,
+        communities: &options.communities,
+        community_categories: options
+            .communities
+            .iter()
+            .map(|c| (c.as_str(), options.categories.as_slice()))
+            .collect(),
+        has_nsfw_content: options.nsfw,
+        upload_uuid: uuid,
+    }
+}
+
+fn get_markdown(version: &ModVersion, kind: &str) -> Result<Option<String>, ThermiteError> {
+    let (author, name, version) = parse_modstring(&version.full_name)?;
+    let url = format!("{BASE_URL}/api/experimental/package/{author}/{name}/{version}/{kind}/");
+    let raw = match http::agent()
+        .get(&url)
+        .set("accept", "application/json")
+        .call()
+    {
+        Ok(res) => res,
+        Err(ureq::Error::Status(404, _)) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let parsed: Markdown = serde_json::from_str(&raw.into_string()?)?;
+
+    Ok(parsed.markdown)
+}
+
+fn filter_deps(deps: &[String]) -> Vec<String> {
+    deps.iter()
+        .filter(|e| !e.contains("northstar-Northstar"))
+        .cloned()
         .collect()
 }
 
+fn map_experimental_version(v: &ExperimentalVersion) -> ModVersion {
+    ModVersion {
+        name: v.name.clone(),
+        full_name: v.full_name.clone(),
+        version: v.version_number.as_str().into(),
+        desc: v.description.clone(),
+        file_size: 0,
+        downloads: v.downloads,
+        deps: filter_deps(&v.dependencies),
+        installed: false,
+        global: false,
+        url: v.download_url.clone(),
+        date_created: v.date_created.clone(),
+    }
+}
+
+fn map_experimental(res: &ExperimentalPackage) -> Mod {
+    let latest = &res.latest;
+    let version = map_experimental_version(latest);
+
+    Mod {
+        name: res.name.clone(),
+        author: res.namespace.clone(),
+        latest: version.version.clone(),
+        versions: BTreeMap::from([(version.version.clone(), version)]),
+        rating_score: res.rating_score,
+        deprecated: res.is_deprecated,
+        categories: vec![],
+        date_created: res.date_created.clone(),
+        date_updated: res.date_updated.clone(),
+        ..Default::default()
+    }
+}
+
+fn map_listing(e: &PackageListing) -> Mod {
+    let versions = &e.versions;
+    let mut urls = BTreeMap::new();
+
+    for v in versions {
+        urls.insert(
+            v.version_number.as_str().into(),
+            ModVersion {
+                name: e.name.clone(),
+                full_name: v.full_name.clone(),
+                version: v.version_number.as_str().into(),
+                desc: v.description.clone(),
+                file_size: v.file_size,
+                downloads: v.downloads,
+                deps: filter_deps(&v.dependencies),
+                installed: false,
+                global: false,
+                url: v.download_url.clone(),
+                date_created: v.date_created.clone(),
+            },
+        );
+    }
+
+    Mod {
+        name: e.name.clone(),
+        author: e.owner.clone(),
+        latest: versions
+            .first()
+            .map(|v| v.version_number.as_str().into())
+            .unwrap_or_default(),
+        versions: urls,
+        rating_score: e.rating_score,
+        deprecated: e.is_deprecated,
+        categories: e.categories.clone(),
+        date_created: e.date_created.clone(),
+        date_updated: e.date_updated.clone(),
+        ..Default::default()
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use std::collections::{BTreeMap, HashMap};
+    use std::{
+        collections::{BTreeMap, HashMap},
+        fs,
+        io::{self, Read},
+        time::{Duration, UNIX_EPOCH},
+    };
 
-    use crate::model::{Mod, ModVersion};
+    use crate::{
+        core::utils::TempDir,
+        error::ThermiteError,
+        model::{IndexDiff, Mod, ModVersion},
+    };
 
-    use super::{get_package_index, map_response, PackageListing, PackageVersion};
+    use super::{
+        get_changelog, get_package, get_package_index, get_readme, map_experimental, map_listing,
+        parse_index, refresh_from_reader, submission_metadata, with_cache, ExperimentalPackage,
+        Markdown, PackageListing, PackageVersion, PublishOptions,
+    };
 
     #[test]
     fn get_packages_from_tstore() {
@@ -124,8 +781,15 @@ mod test {
                 file_size: 420,
                 version_number: "0.1.0".into(),
                 full_name: "Bar-Foo-0.1.0".into(),
+                downloads: 69,
+                date_created: "2023-01-01T00:00:00Z".into(),
                 _extra: HashMap::new(),
             }],
+            rating_score: 3,
+            is_deprecated: false,
+            categories: vec!["Mods".into()],
+            date_created: "2023-01-01T00:00:00Z".into(),
+            date_updated: "2023-01-01T00:00:00Z".into(),
             _extra: HashMap::new(),
         }];
 
@@ -133,9 +797,6 @@ mod test {
             name: "Foo".into(),
             author: "Bar".into(),
             latest: "0.1.0".into(),
-            installed: false,
-            upgradable: false,
-            global: false,
             versions: BTreeMap::from([(
                 "0.1.0".into(),
                 ModVersion {
@@ -148,12 +809,344 @@ mod test {
                     installed: false,
                     global: false,
                     file_size: 420,
+                    downloads: 69,
+                    date_created: "2023-01-01T00:00:00Z".into(),
                 },
             )]),
+            rating_score: 3,
+            deprecated: false,
+            categories: vec!["Mods".into()],
+            date_created: "2023-01-01T00:00:00Z".into(),
+            date_updated: "2023-01-01T00:00:00Z".into(),
+            ..Default::default()
         }];
 
-        let res = map_response(&test_data);
-        assert!(!res.is_empty());
-        assert_eq!(res[0], expected[0]);
+        let res = map_listing(&test_data[0]);
+        assert_eq!(res, expected[0]);
+    }
+
+    #[test]
+    #[ignore = "needs access to thunderstore.io"]
+    fn get_single_package_from_tstore() {
+        let res = get_package("S2Mods", "VanillaPlus");
+        let package = res.expect("get package");
+        assert_eq!(package.name, "VanillaPlus");
+        assert!(package.get_latest().is_some());
+    }
+
+    const EXPERIMENTAL_PACKAGE: &str = r#"{
+        "namespace": "Bar",
+        "name": "Foo",
+        "full_name": "Bar-Foo",
+        "owner": "Bar",
+        "is_deprecated": false,
+        "latest": {
+            "namespace": "Bar",
+            "name": "Foo",
+            "version_number": "0.2.0",
+            "full_name": "Bar-Foo-0.2.0",
+            "description": "Test",
+            "dependencies": ["northstar-Northstar-1.0.0", "Baz-Qux-1.0.0"],
+            "download_url": "localhost",
+            "downloads": 5,
+            "is_active": true
+        }
+    }"#;
+
+    #[test]
+    fn map_experimental_response() {
+        let parsed: ExperimentalPackage =
+            serde_json::from_str(EXPERIMENTAL_PACKAGE).expect("parse experimental package");
+        let res = map_experimental(&parsed);
+
+        assert_eq!(res.name, "Foo");
+        assert_eq!(res.author, "Bar");
+        assert_eq!(res.latest, "0.2.0");
+        assert_eq!(res.versions.len(), 1);
+
+        let latest = res.get_latest().expect("latest version");
+        assert_eq!(latest.full_name, "Bar-Foo-0.2.0");
+        assert_eq!(latest.deps, vec!["Baz-Qux-1.0.0".to_string()]);
+        assert_eq!(latest.downloads, 5);
+    }
+
+    #[test]
+    #[ignore = "needs access to thunderstore.io"]
+    fn get_markdown_from_tstore() {
+        let package = get_package("S2Mods", "VanillaPlus").expect("get package");
+        let latest = package.get_latest().expect("latest version");
+
+        let readme = get_readme(latest).expect("get readme");
+        assert!(!readme.is_empty());
+        assert!(get_changelog(latest).is_ok());
+    }
+
+    #[test]
+    fn fail_markdown_bad_name() {
+        let version = ModVersion {
+            name: "Foo".into(),
+            full_name: "invalid".into(),
+            version: "0.1.0".into(),
+            url: "localhost".into(),
+            desc: "Test".into(),
+            deps: vec![],
+            installed: false,
+            global: false,
+            file_size: 0,
+            downloads: 0,
+            date_created: String::new(),
+        };
+
+        assert!(matches!(
+            get_readme(&version),
+            Err(ThermiteError::NameError(_))
+        ));
+    }
+
+    #[test]
+    fn deserialize_missing_markdown() {
+        let parsed: Markdown =
+            serde_json::from_str(r#"{"markdown": null}"#).expect("parse markdown");
+        assert!(parsed.markdown.is_none());
+    }
+
+    const INDEX: &str = r#"[
+        {
+            "name": "Foo",
+            "owner": "Bar",
+            "rating_score": 2,
+            "versions": [
+                {
+                    "dependencies": ["northstar-Northstar-1.0.0"],
+                    "description": "Test",
+                    "download_url": "localhost",
+                    "file_size": 420,
+                    "version_number": "0.2.0",
+                    "full_name": "Bar-Foo-0.2.0",
+                    "downloads": 10
+                },
+                {
+                    "dependencies": [],
+                    "description": "Test",
+                    "download_url": "localhost",
+                    "file_size": 400,
+                    "version_number": "0.1.0",
+                    "full_name": "Bar-Foo-0.1.0",
+                    "downloads": 5
+                }
+            ]
+        }
+    ]"#;
+
+    #[test]
+    fn stream_parse_index() {
+        let index = parse_index(INDEX.as_bytes()).expect("parse index");
+
+        assert_eq!(index.len(), 1);
+        assert_eq!(index[0].name, "Foo");
+        assert_eq!(index[0].latest, "0.2.0");
+        assert_eq!(index[0].versions.len(), 2);
+        assert_eq!(index[0].total_downloads(), 15);
+        assert!(index[0].get_latest().expect("latest").deps.is_empty());
+    }
+
+    #[test]
+    fn fail_stream_parse_trailing_data() {
+        let res = parse_index(format!("{INDEX} []").as_bytes());
+
+        assert!(matches!(res, Err(ThermiteError::JsonError(_))));
+    }
+
+    fn listing_json(owner: &str, name: &str, date_updated: &str) -> String {
+        format!(
+            r#"{{
+                "name": "{name}",
+                "owner": "{owner}",
+                "date_updated": "{date_updated}",
+                "versions": [{{
+                    "dependencies": [],
+                    "description": "Test",
+                    "download_url": "localhost",
+                    "file_size": 0,
+                    "version_number": "0.1.0",
+                    "full_name": "{owner}-{name}-0.1.0"
+                }}]
+            }}"#
+        )
+    }
+
+    #[test]
+    fn refresh_index_diff() {
+        let before = format!(
+            "[{}, {}, {}]",
+            listing_json("Bar", "Same", "2023-01-01T00:00:00Z"),
+            listing_json("Bar", "Changed", "2023-01-01T00:00:00Z"),
+            listing_json("Bar", "Removed", "2023-01-01T00:00:00Z"),
+        );
+        let after = format!(
+            "[{}, {}, {}]",
+            listing_json("Bar", "Same", "2023-01-01T00:00:00Z"),
+            listing_json("Bar", "Changed", "2023-02-01T00:00:00Z"),
+            listing_json("Bar", "Added", "2023-02-01T00:00:00Z"),
+        );
+
+        let mut index = parse_index(before.as_bytes()).expect("parse index");
+        let diff = refresh_from_reader(&mut index, None, after.as_bytes()).expect("refresh");
+
+        assert_eq!(diff.added, ["Bar-Added"]);
+        assert_eq!(diff.updated, ["Bar-Changed"]);
+        assert_eq!(diff.removed, ["Bar-Removed"]);
+        assert_eq!(index.len(), 3);
+        assert!(index
+            .iter()
+            .any(|m| m.name == "Changed" && m.date_updated == "2023-02-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn refresh_index_since() {
+        let data = format!("[{}]", listing_json("Bar", "Foo", "2023-02-01T00:00:00Z"));
+
+        let mut index = parse_index(data.as_bytes()).expect("parse index");
+        let diff = refresh_from_reader(&mut index, Some("2023-03-01T00:00:00Z"), data.as_bytes())
+            .expect("refresh");
+        assert_eq!(diff, IndexDiff::default());
+
+        let diff = refresh_from_reader(&mut index, Some("2023-01-01T00:00:00Z"), data.as_bytes())
+            .expect("refresh");
+        assert_eq!(diff.updated, ["Bar-Foo"]);
+    }
+
+    #[test]
+    fn serialize_submission_metadata() {
+        let options = PublishOptions {
+            token: "secret".into(),
+            author: "Bar".into(),
+            communities: vec!["northstar".into()],
+            categories: vec!["mods".into(), "server-side".into()],
+            nsfw: false,
+        };
+
+        let value = serde_json::to_value(submission_metadata(&options, "uuid"))
+            .expect("serialize metadata");
+
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "author_name": "Bar",
+                "categories": ["mods", "server-side"],
+                "communities": ["northstar"],
+                "community_categories": { "northstar": ["mods", "server-side"] },
+                "has_nsfw_content": false,
+                "upload_uuid": "uuid",
+            })
+        );
+    }
+
+    #[test]
+    fn fall_back_to_cached_index() {
+        let dir = TempDir::create("./test_cached_index").expect("temp dir");
+        let index = parse_index(INDEX.as_bytes()).expect("parse index");
+
+        let fresh = with_cache(Ok(index.clone()), &dir).expect("write cache");
+        assert!(!fresh.stale);
+
+        let offline = ureq::Error::Status(
+            503,
+            ureq::Response::new(503, "Service Unavailable", "").expect("response"),
+        );
+        let cached = with_cache(Err(offline.into()), &dir).expect("load cache");
+        assert!(cached.stale);
+        assert_eq!(cached.index, index);
+        assert_eq!(
+            cached.fetched_at,
+            UNIX_EPOCH
+                + Duration::from_secs(
+                    fresh
+                        .fetched_at
+                        .duration_since(UNIX_EPOCH)
+                        .expect("duration")
+                        .as_secs()
+                )
+        );
+    }
+
+    #[test]
+    fn fail_without_cached_index() {
+        let dir = TempDir::create("./test_missing_cached_index").expect("temp dir");
+        let offline = ureq::Error::Status(
+            503,
+            ureq::Response::new(503, "Service Unavailable", "").expect("response"),
+        );
+
+        let res = with_cache(Err(offline.into()), &dir);
+        assert!(matches!(res, Err(ThermiteError::NetworkError(_))));
+    }
+
+    #[test]
+    fn keep_fresh_index_when_cache_write_fails() {
+        let dir = TempDir::create("./test_unwritable_cached_index").expect("temp dir");
+        let not_a_dir = dir.join("cache");
+        fs::write(&not_a_dir, "").expect("write file");
+        let index = parse_index(INDEX.as_bytes()).expect("parse index");
+
+        let fresh = with_cache(Ok(index.clone()), &not_a_dir).expect("fresh index");
+        assert!(!fresh.stale);
+        assert_eq!(fresh.index, index);
+    }
+
+    #[test]
+    fn fall_back_to_cached_index_when_body_fails() {
+        struct Dropped;
+
+        impl Read for Dropped {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::ConnectionReset.into())
+            }
+        }
+
+        let dir = TempDir::create("./test_cached_index_broken_body").expect("temp dir");
+        let index = parse_index(INDEX.as_bytes()).expect("parse index");
+        with_cache(Ok(index.clone()), &dir).expect("write cache");
+
+        let broken = parse_index(INDEX.as_bytes()[..INDEX.len() / 2].chain(Dropped));
+        assert!(matches!(broken, Err(ThermiteError::IoError(_))));
+
+        let cached = with_cache(broken, &dir).expect("load cache");
+        assert!(cached.stale);
+        assert_eq!(cached.index, index);
+    }
+
+    #[test]
+    fn fall_back_to_cached_index_on_timeout() {
+        let dir = TempDir::create("./test_cached_index_timeout").expect("temp dir");
+        let index = parse_index(INDEX.as_bytes()).expect("parse index");
+
+        let res = with_cache(Err(ThermiteError::Timeout), &dir);
+        assert!(matches!(res, Err(ThermiteError::Timeout)));
+
+        with_cache(Ok(index.clone()), &dir).expect("write cache");
+        let cached = with_cache(Err(ThermiteError::Timeout), &dir).expect("load cache");
+        assert!(cached.stale);
+        assert_eq!(cached.index, index);
+    }
+
+    #[test]
+    fn convert_listing_without_versions() {
+        let listing = PackageListing {
+            name: "Foo".into(),
+            owner: "Bar".into(),
+            versions: vec![],
+            rating_score: 0,
+            is_deprecated: false,
+            categories: vec![],
+            date_created: String::new(),
+            date_updated: String::new(),
+            _extra: HashMap::new(),
+        };
+
+        let converted = Mod::from(listing);
+        assert_eq!(converted.name, "Foo");
+        assert!(converted.latest.is_empty());
+        assert!(converted.get_latest().is_none());
     }
 }
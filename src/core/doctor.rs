@@ -0,0 +1,337 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use tracing::debug;
+
+use crate::{
+    core::utils::write_atomic,
+    error::{Result, ThermiteError},
+};
+
+/// DLLs from the MSVC redistributable that `NorthstarLauncher.exe` links against
+pub const REQUIRED_REDIST: [&str; 3] = ["vcruntime140.dll", "vcruntime140_1.dll", "msvcp140.dll"];
+/// DLLs that have to be overridden to `native,builtin` for Northstar to load under Wine
+pub const REQUIRED_OVERRIDES: [&str; 1] = ["wsock32"];
+
+/// A problem that will stop Northstar from starting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Finding {
+    /// A file from the Northstar release is missing from the game directory
+    MissingNorthstarFile(PathBuf),
+    /// A DLL from the MSVC redistributable isn't installed
+    MissingRedist(String),
+    /// Wine isn't configured to load Northstar's version of a DLL
+    MissingDllOverride(String),
+}
+
+impl Finding {
+    /// What the user can do to fix the problem
+    #[must_use]
+    pub fn fix(&self) -> String {
+        match self {
+            Self::MissingNorthstarFile(_) => "Reinstall Northstar".into(),
+            Self::MissingRedist(_) => {
+                "Install the latest Microsoft Visual C++ Redistributable (x64)".into()
+            }
+            Self::MissingDllOverride(dll) => {
+                format!("Add WINEDLLOVERRIDES=\"{dll}=n,b\" %command% to the game's launch options")
+            }
+        }
+    }
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingNorthstarFile(path) => write!(f, "{} is missing", path.display()),
+            Self::MissingRedist(dll) => write!(f, "{dll} from the MSVC redistributable is missing"),
+            Self::MissingDllOverride(dll) => write!(f, "{dll} isn't overridden to native,builtin"),
+        }
+    }
+}
+
+/// Check that Northstar and everything it needs to run is installed
+///
+/// On Windows this also checks the MSVC redistributable in `%SystemRoot%\System32`.
+/// Use [`check_wine_prefix`] to check the prefix when running under Wine or Proton.
+///
+/// # Returns
+/// * every problem found, or an empty list if Northstar should start
+#[must_use]
+pub fn check_prerequisites(game_path: impl AsRef<Path>) -> Vec<Finding> {
+    let game_path = game_path.as_ref();
+    let mut findings = vec![];
+    for file in ["NorthstarLauncher.exe", "Northstar.dll"] {
+        let path = game_path.join(file);
+        if !path.is_file() {
+            findings.push(Finding::MissingNorthstarFile(path));
+        }
+    }
+
+    #[cfg(windows)]
+    if let Some(root) = std::env::var_os("SystemRoot") {
+        findings.extend(check_redist(&Path::new(&root).join("System32")));
+    }
+
+    findings
+}
+
+/// Check a Wine or Proton prefix for the MSVC redistributable and the DLL overrides Northstar needs
+///
+/// Overrides set with `WINEDLLOVERRIDES` in the launch options can't be seen from here, so they're
+/// reported as missing unless they're also set in the prefix's registry
+///
+/// # Params
+/// * `prefix` - the Wine prefix, e.g. `steamapps/compatdata/1237970/pfx` for Proton
+#[must_use]
+pub fn check_wine_prefix(prefix: impl AsRef<Path>) -> Vec<Finding> {
+    let prefix = prefix.as_ref();
+    let mut findings = check_redist(&prefix.join("drive_c").join("windows").join("system32"));
+
+    let overrides = fs::read_to_string(prefix.join("user.reg"))
+        .map(|reg| dll_overrides(&reg))
+        .unwrap_or_default();
+    for dll in REQUIRED_OVERRIDES {
+        let native_first = overrides
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(dll))
+            .is_some_and(|(_, mode)| mode.starts_with('n'));
+        if !native_first {
+            findings.push(Finding::MissingDllOverride(dll.into()));
+        }
+    }
+
+    findings
+}
+
+/// Override every DLL in [`REQUIRED_OVERRIDES`] to `native,builtin` in a prefix's registry, so
+/// Northstar loads without `WINEDLLOVERRIDES` in the launch options
+///
+/// Wine writes the registry back when the prefix's `wineserver` exits, which would undo the change,
+/// so this refuses to run while it's running
+///
+/// # Params
+/// * `prefix` - the Wine prefix, e.g. `steamapps/compatdata/1237970/pfx` for Proton
+///
+/// # Returns
+/// * `true` if any override had to be changed
+///
+/// # Errors
+/// - The prefix hasn't been created yet, i.e. it has no `user.reg`
+/// - The prefix's `wineserver` is running
+/// - IO Errors
+pub fn set_dll_overrides(prefix: impl AsRef<Path>) -> Result<bool> {
+    let prefix = prefix.as_ref();
+    let path = prefix.join("user.reg");
+    if !path.is_file() {
+        return Err(ThermiteError::MissingFile(Box::new(path)));
+    }
+    if wineserver_running(prefix) {
+        return Err(ThermiteError::GameRunning(prefix.to_path_buf()));
+    }
+
+    let reg = fs::read_to_string(&path)?;
+    let updated = REQUIRED_OVERRIDES.iter().fold(reg.clone(), |reg, dll| {
+        set_override(&reg, dll, "native,builtin")
+    });
+    if updated == reg {
+        return Ok(false);
+    }
+
+    debug!("Setting DLL overrides in {}", path.display());
+    write_atomic(path, updated)?;
+    Ok(true)
+}
+
+/// `true` if the prefix's `wineserver` socket exists, which it only does while the server runs
+#[cfg(unix)]
+fn wineserver_running(prefix: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    let (Ok(prefix), Ok(me)) = (fs::metadata(prefix), fs::metadata("/proc/self")) else {
+        return false;
+    };
+    Path::new(&format!("/tmp/.wine-{}", me.uid()))
+        .join(format!("server-{:x}-{:x}", prefix.dev(), prefix.ino()))
+        .join("socket")
+        .exists()
+}
+
+#[cfg(not(unix))]
+fn wineserver_running(_prefix: &Path) -> bool {
+    false
+}
+
+/// Set a DLL's override in the `DllOverrides` section of a Wine registry file, adding the section
+/// if it's missing
+fn set_override(reg: &str, dll: &str, mode: &str) -> String {
+    let entry = format!("\"{dll}\"=\"{mode}\"");
+    let mut lines = reg.lines().map(str::to_owned).collect::<Vec<_>>();
+    let Some(section) = lines
+        .iter()
+        .position(|l| l.trim().starts_with(r"[Software\\Wine\\DllOverrides]"))
+    else {
+        let time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let mut reg = reg.trim_end().to_owned();
+        reg.push_str(&format!(
+            "\n\n[Software\\\\Wine\\\\DllOverrides] {time}\n{entry}\n"
+        ));
+        return reg;
+    };
+
+    let end = lines[section + 1..]
+        .iter()
+        .position(|l| l.trim().starts_with('['))
+        .map_or(lines.len(), |n| section + 1 + n);
+    let existing = (section + 1..end).find(|i| {
+        lines[*i].split_once('=').is_some_and(|(name, _)| {
+            name.trim_matches('"')
+                .trim_start_matches('*')
+                .eq_ignore_ascii_case(dll)
+        })
+    });
+    match existing {
+        Some(i) if lines[i] == entry => return reg.to_owned(),
+        Some(i) => lines[i] = entry,
+        None => {
+            // keep entries after the `#time=` line Wine puts at the top of each section
+            let at = if lines.get(section + 1).is_some_and(|l| l.starts_with('#')) {
+                section + 2
+            } else {
+                section + 1
+            };
+            lines.insert(at, entry);
+        }
+    }
+
+    let mut reg = lines.join("\n");
+    reg.push('\n');
+    reg
+}
+
+fn check_redist(system32: &Path) -> Vec<Finding> {
+    debug!(
+        "Checking for the MSVC redistributable in {}",
+        system32.display()
+    );
+    REQUIRED_REDIST
+        .iter()
+        .filter(|dll| !system32.join(dll).is_file())
+        .map(|dll| Finding::MissingRedist((*dll).into()))
+        .collect()
+}
+
+/// Read the `DllOverrides` section of a Wine registry file as `(dll, mode)` pairs
+fn dll_overrides(reg: &str) -> Vec<(String, String)> {
+    let mut overrides = vec![];
+    let mut in_section = false;
+    for line in reg.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_section = line.starts_with(r"[Software\\Wine\\DllOverrides]");
+        } else if in_section {
+            let Some((name, mode)) = line.split_once('=') else {
+                continue;
+            };
+            overrides.push((
+                name.trim_matches('"').trim_start_matches('*').to_owned(),
+                mode.trim_matches('"').to_owned(),
+            ));
+        }
+    }
+
+    overrides
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::core::utils::TempDir;
+
+    use super::{
+        check_prerequisites, check_wine_prefix, set_dll_overrides, Finding, REQUIRED_REDIST,
+    };
+
+    const USER_REG: &str = r#"WINE REGISTRY Version 2
+;; All keys relative to \\User\\S-1-5-21-0-0-0-1000
+
+[Software\\Wine\\DllOverrides] 1700000000
+#time=1da0000000000000
+"*d3d11"="native"
+"wsock32"="native,builtin"
+
+[Software\\Wine\\Fonts] 1700000000
+"wsock32"="builtin"
+"#;
+
+    #[test]
+    fn check_missing_northstar() {
+        let dir = TempDir::create("./doctor_missing_ns").expect("Unable to create temp dir");
+        let findings = check_prerequisites(&dir);
+
+        assert!(findings.contains(&Finding::MissingNorthstarFile(
+            dir.join("NorthstarLauncher.exe")
+        )));
+        assert!(findings.contains(&Finding::MissingNorthstarFile(dir.join("Northstar.dll"))));
+    }
+
+    #[test]
+    fn check_prefix() {
+        let dir = TempDir::create("./doctor_prefix").expect("Unable to create temp dir");
+        let system32 = dir.join("drive_c/windows/system32");
+        fs::create_dir_all(&system32).expect("create dir");
+
+        let findings = check_wine_prefix(&dir);
+        assert_eq!(findings.len(), REQUIRED_REDIST.len() + 1);
+        assert!(findings.contains(&Finding::MissingDllOverride("wsock32".into())));
+
+        for dll in REQUIRED_REDIST {
+            fs::write(system32.join(dll), "").expect("write dll");
+        }
+        fs::write(dir.join("user.reg"), USER_REG).expect("write registry");
+        assert!(check_wine_prefix(&dir).is_empty());
+    }
+
+    #[test]
+    fn fail_builtin_override() {
+        let dir = TempDir::create("./doctor_builtin").expect("Unable to create temp dir");
+        fs::write(
+            dir.join("user.reg"),
+            "[Software\\\\Wine\\\\DllOverrides]\n\"wsock32\"=\"builtin\"\n",
+        )
+        .expect("write registry");
+
+        assert!(check_wine_prefix(&dir).contains(&Finding::MissingDllOverride("wsock32".into())));
+    }
+
+    #[test]
+    fn fix_dll_overrides() {
+        let dir = TempDir::create("./doctor_set_overrides").expect("Unable to create temp dir");
+        assert!(set_dll_overrides(&dir).is_err());
+
+        let reg = "WINE REGISTRY Version 2\n\n[Software\\\\Wine\\\\DllOverrides] 1700000000\n#time=1da0000000000000\n\"*d3d11\"=\"native\"\n\"*wsock32\"=\"builtin\"\n\n[Software\\\\Wine\\\\Fonts] 1700000000\n\"wsock32\"=\"builtin\"\n";
+        fs::write(dir.join("user.reg"), reg).expect("write registry");
+        assert!(set_dll_overrides(&dir).expect("set overrides"));
+        assert_eq!(
+            fs::read_to_string(dir.join("user.reg")).expect("read registry"),
+            reg.replacen(
+                "\"*wsock32\"=\"builtin\"",
+                "\"wsock32\"=\"native,builtin\"",
+                1
+            )
+        );
+        assert!(!set_dll_overrides(&dir).expect("set overrides again"));
+        assert!(!check_wine_prefix(&dir).contains(&Finding::MissingDllOverride("wsock32".into())));
+
+        fs::write(dir.join("user.reg"), "WINE REGISTRY Version 2\n").expect("write registry");
+        assert!(set_dll_overrides(&dir).expect("add section"));
+        assert!(!check_wine_prefix(&dir).contains(&Finding::MissingDllOverride("wsock32".into())));
+    }
+}
@@ -0,0 +1,389 @@
+use std::{fs, ops::Range, path::Path};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    core::utils::write_atomic,
+    error::{Result, ThermiteError},
+    model::ModJSON,
+};
+
+/// A `mod.json` that can be edited without reformatting it
+///
+/// Only the values that are set or removed change, so comments, key order, indentation and unknown
+/// keys are all kept as they were
+///
+/// # Example
+/// ```no_run
+/// use thermite::core::edit::ModJsonDocument;
+///
+/// # fn main() -> thermite::error::Result<()> {
+/// let mut doc = ModJsonDocument::load("mods/Foo.Bar/mod.json")?;
+/// doc.set("LoadPriority", 2)?;
+/// doc.save("mods/Foo.Bar/mod.json")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModJsonDocument {
+    text: String,
+}
+
+/// A top-level key and its value, as byte offsets into the text
+#[derive(Debug)]
+struct Member {
+    key: String,
+    key_start: usize,
+    value: Range<usize>,
+    comma: Option<usize>,
+}
+
+impl ModJsonDocument {
+    /// Parse the text of a `mod.json`
+    ///
+    /// # Errors
+    /// - The text isn't a JSON5 object
+    pub fn parse(text: impl Into<String>) -> Result<Self> {
+        let doc = Self { text: text.into() };
+        doc.check()?;
+
+        Ok(doc)
+    }
+
+    /// Read and parse a `mod.json`
+    ///
+    /// # Errors
+    /// - IO Errors
+    /// - The file isn't a JSON5 object
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::parse(fs::read_to_string(path)?)
+    }
+
+    /// Write the document to `path`, replacing the file atomically
+    ///
+    /// # Errors
+    /// - IO Errors
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        write_atomic(path, &self.text)
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// The document as a [`ModJSON`]
+    ///
+    /// # Errors
+    /// - A required field is missing or has the wrong type
+    pub fn to_mod_json(&self) -> Result<ModJSON> {
+        Ok(json5::from_str(&self.text)?)
+    }
+
+    /// The current value of a top-level key
+    ///
+    /// # Errors
+    /// - The value can't be parsed
+    pub fn get(&self, key: &str) -> Result<Option<Value>> {
+        let Some(member) = self.members()?.0.into_iter().find(|m| m.key == key) else {
+            return Ok(None);
+        };
+
+        Ok(Some(json5::from_str(&self.text[member.value])?))
+    }
+
+    /// Set a top-level key, replacing its value in place or adding it after the last key
+    ///
+    /// Keys are case-sensitive, like they are to Northstar
+    ///
+    /// # Errors
+    /// - `value` can't be serialized
+    pub fn set(&mut self, key: &str, value: impl Serialize) -> Result<()> {
+        let value = serde_json::to_string(&value)?;
+        let (members, close) = self.members()?;
+
+        let mut text = self.text.clone();
+        if let Some(member) = members.iter().find(|m| m.key == key) {
+            text.replace_range(member.value.clone(), &value);
+        } else if let Some(last) = members.last() {
+            let entry = format!("{}: {value}", serde_json::to_string(key)?);
+            let indent = self.indent_of(last.key_start);
+            match last.comma {
+                Some(comma) => text.insert_str(comma + 1, &format!("\n{indent}{entry},")),
+                None => text.insert_str(last.value.end, &format!(",\n{indent}{entry}")),
+            }
+        } else {
+            let entry = format!("{}: {value}", serde_json::to_string(key)?);
+            let open = self.text[..close].rfind('{').unwrap_or(close);
+            text.replace_range(open + 1..close, &format!("\n    {entry}\n"));
+        }
+
+        self.replace(text)
+    }
+
+    /// Remove a top-level key, along with its line if it's the only thing on it
+    ///
+    /// # Returns
+    /// * `true` if the key was present
+    ///
+    /// # Errors
+    /// - The document can't be scanned
+    pub fn remove(&mut self, key: &str) -> Result<bool> {
+        let (members, _) = self.members()?;
+        let Some(i) = members.iter().position(|m| m.key == key) else {
+            return Ok(false);
+        };
+        let member = &members[i];
+
+        let mut start = member.key_start;
+        let mut end = member.comma.map_or(member.value.end, |c| c + 1);
+        let line_start = self.text[..start].rfind('\n').map_or(0, |n| n + 1);
+        if self.text[line_start..start].trim().is_empty() {
+            start = line_start;
+            let rest = &self.text[end..];
+            let line_end = rest.find('\n').map_or(rest.len(), |n| n + 1);
+            if rest[..line_end].trim().is_empty() {
+                end += line_end;
+            }
+        }
+
+        let mut text = self.text.clone();
+        text.replace_range(start..end, "");
+        // the key before the last one keeps its comma otherwise, leaving a trailing comma
+        if member.comma.is_none() {
+            if let Some(comma) = i.checked_sub(1).and_then(|p| members[p].comma) {
+                text.remove(comma);
+            }
+        }
+
+        self.replace(text)?;
+        Ok(true)
+    }
+
+    /// Swap in the edited text, refusing edits that would leave the document unparseable
+    fn replace(&mut self, text: String) -> Result<()> {
+        let previous = std::mem::replace(&mut self.text, text);
+        if let Err(e) = self.check() {
+            self.text = previous;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    fn check(&self) -> Result<()> {
+        let value: Value = json5::from_str(&self.text)?;
+        if !value.is_object() {
+            return Err(ThermiteError::UnknownError(
+                "mod.json isn't an object".into(),
+            ));
+        }
+        self.members()?;
+
+        Ok(())
+    }
+
+    /// The whitespace before `pos` on its line, or 4 spaces if there's something else before it
+    fn indent_of(&self, pos: usize) -> &str {
+        let line_start = self.text[..pos].rfind('\n').map_or(0, |n| n + 1);
+        let indent = &self.text[line_start..pos];
+        if indent.trim().is_empty() {
+            indent
+        } else {
+            "    "
+        }
+    }
+
+    /// The top-level members and the position of the object's closing brace
+    fn members(&self) -> Result<(Vec<Member>, usize)> {
+        scan_members(&self.text)
+            .ok_or_else(|| ThermiteError::UnknownError("Unable to scan mod.json".into()))
+    }
+}
+
+fn scan_members(text: &str) -> Option<(Vec<Member>, usize)> {
+    let bytes = text.as_bytes();
+    let mut i = skip_trivia(bytes, 0);
+    if bytes.get(i) != Some(&b'{') {
+        return None;
+    }
+    i += 1;
+
+    let mut members = vec![];
+    loop {
+        i = skip_trivia(bytes, i);
+        match *bytes.get(i)? {
+            b'}' => return Some((members, i)),
+            b'"' | b'\'' => {
+                let key_start = i;
+                i = skip_string(bytes, i)?;
+                let key = json5::from_str(&text[key_start..i]).ok()?;
+                members.push(scan_value(bytes, key, key_start, i)?);
+            }
+            _ => {
+                let key_start = i;
+                while bytes
+                    .get(i)
+                    .is_some_and(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'$'))
+                {
+                    i += 1;
+                }
+                if i == key_start {
+                    return None;
+                }
+                members.push(scan_value(bytes, text[key_start..i].into(), key_start, i)?);
+            }
+        }
+
+        let last = members.last()?;
+        i = last.comma.map_or(last.value.end, |c| c + 1);
+    }
+}
+
+/// Scan the value of a member whose key ends at `i`, up to the next top-level comma or brace
+fn scan_value(bytes: &[u8], key: String, key_start: usize, i: usize) -> Option<Member> {
+    let mut i = skip_trivia(bytes, i);
+    if bytes.get(i) != Some(&b':') {
+        return None;
+    }
+    i = skip_trivia(bytes, i + 1);
+
+    let start = i;
+    let mut end = i;
+    let mut depth = 0usize;
+    loop {
+        match *bytes.get(i)? {
+            b'"' | b'\'' => {
+                i = skip_string(bytes, i)?;
+                end = i;
+                continue;
+            }
+            b'/' if matches!(bytes.get(i + 1), Some(b'/' | b'*')) => {
+                i = skip_trivia(bytes, i);
+                continue;
+            }
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' if depth == 0 => break,
+            b'}' | b']' => depth -= 1,
+            b',' if depth == 0 => break,
+            b if b.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+        end = i;
+    }
+
+    if start == end {
+        return None;
+    }
+
+    Some(Member {
+        key,
+        key_start,
+        value: start..end,
+        comma: (bytes[i] == b',').then_some(i),
+    })
+}
+
+/// Skip whitespace and comments starting at `i`
+fn skip_trivia(bytes: &[u8], mut i: usize) -> usize {
+    loop {
+        match (bytes.get(i), bytes.get(i + 1)) {
+            (Some(b), _) if b.is_ascii_whitespace() => i += 1,
+            (Some(b'/'), Some(b'/')) => {
+                while bytes.get(i).is_some_and(|b| *b != b'\n') {
+                    i += 1;
+                }
+            }
+            (Some(b'/'), Some(b'*')) => {
+                i += 2;
+                while i < bytes.len() && !bytes[i..].starts_with(b"*/") {
+                    i += 1;
+                }
+                i = (i + 2).min(bytes.len());
+            }
+            _ => return i,
+        }
+    }
+}
+
+/// Skip the string starting with the quote at `i`, returning the position after the closing quote
+fn skip_string(bytes: &[u8], mut i: usize) -> Option<usize> {
+    let quote = bytes[i];
+    i += 1;
+    loop {
+        match *bytes.get(i)? {
+            b'\\' => i += 2,
+            b if b == quote => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::ModJsonDocument;
+
+    const MOD_JSON: &str = r#"{
+    // shown in the mod browser
+    "Name": "Foo.Bar",
+    "Description": "Has a \"quoted\" word, and a comma",
+    Version: '1.0.0', /* bumped by hand */
+    "LoadPriority": 1,
+    "ConVars": [
+        { "Name": "foo_enabled", "DefaultValue": "1" }
+    ],
+    "Unknown": { "Nested": [1, 2, 3] }
+}"#;
+
+    #[test]
+    fn edit_keeps_formatting() {
+        let mut doc = ModJsonDocument::parse(MOD_JSON).expect("parse mod.json");
+        doc.set("LoadPriority", 2).expect("set load priority");
+        doc.set("Version", "1.0.1").expect("set version");
+        assert_eq!(
+            doc.as_str(),
+            MOD_JSON
+                .replace(r#""LoadPriority": 1"#, r#""LoadPriority": 2"#)
+                .replace("'1.0.0'", r#""1.0.1""#)
+        );
+
+        doc.set("RequiredOnClient", true).expect("add key");
+        assert!(doc.as_str().ends_with(
+            "\"Unknown\": { \"Nested\": [1, 2, 3] },\n    \"RequiredOnClient\": true\n}"
+        ));
+
+        let parsed = doc.to_mod_json().expect("to mod.json");
+        assert_eq!(parsed.load_priority, Some(2));
+        assert_eq!(parsed.required_on_client, Some(true));
+        assert_eq!(parsed._extra["Unknown"], json!({ "Nested": [1, 2, 3] }));
+        assert_eq!(
+            doc.get("ConVars").expect("get convars"),
+            Some(json!([{ "Name": "foo_enabled", "DefaultValue": "1" }]))
+        );
+    }
+
+    #[test]
+    fn remove_keys() {
+        let mut doc = ModJsonDocument::parse(MOD_JSON).expect("parse mod.json");
+        assert!(doc.remove("Unknown").expect("remove last key"));
+        assert!(doc.remove("LoadPriority").expect("remove key"));
+        assert!(!doc.remove("Missing").expect("remove missing key"));
+
+        assert!(!doc.as_str().contains("LoadPriority"));
+        assert!(doc.as_str().ends_with("    ]\n}"));
+        assert!(doc.as_str().contains("/* bumped by hand */"));
+        assert_eq!(doc.get("Unknown").expect("get removed key"), None);
+
+        let mut empty = ModJsonDocument::parse("{}").expect("parse empty object");
+        empty.set("Name", "Foo").expect("set name");
+        assert_eq!(empty.as_str(), "{\n    \"Name\": \"Foo\"\n}");
+        assert!(ModJsonDocument::parse("[]").is_err());
+    }
+}
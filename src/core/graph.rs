@@ -0,0 +1,392 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use tracing::trace;
+
+use crate::{
+    error::{Result, ThermiteError},
+    model::{Mod, ModString, ModVersion, Version},
+};
+
+use super::utils::{dep_key, package_key, parse_dep};
+
+/// The dependencies between a set of packages
+///
+/// Packages are identified by `author-name`, case-insensitively, so queries accept either that or a full
+/// modstring like `author-name-1.2.3`. Northstar itself is never part of the graph.
+///
+/// # Example
+/// ```no_run
+/// use thermite::core::graph::DepGraph;
+/// # use thermite::model::ModVersion;
+/// # fn installed() -> Vec<ModVersion> { vec![] }
+///
+/// let graph = DepGraph::from_packages(installed());
+/// for m in graph.dependents("Fifty-Server_Utilities") {
+///     println!("{} depends on ServerUtilities", m.full_name);
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DepGraph {
+    nodes: BTreeMap<String, ModVersion>,
+    edges: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl DepGraph {
+    /// Build the graph of `mods` and all of their dependencies, resolved from the index
+    ///
+    /// When several packages depend on different versions of the same package, the highest
+    /// version is chosen, and dependencies that only a lower version needed are left out.
+    ///
+    /// # Errors
+    /// - A dependency string isn't formatted like `author-name-X.Y.Z`
+    /// - A dependency or the required version of it isn't present in the index
+    pub fn new(mods: &[ModVersion], index: &[Mod]) -> Result<Self> {
+        let mut graph = Self::default();
+        let mut queue: VecDeque<ModVersion> = VecDeque::new();
+        for m in mods {
+            graph.insert(m.clone())?;
+            queue.push_back(m.clone());
+        }
+
+        while let Some(current) = queue.pop_front() {
+            for dep in &current.deps {
+                let Some((key, version)) = parse_dep(dep)? else {
+                    continue;
+                };
+
+                if graph
+                    .nodes
+                    .get(&key)
+                    .is_some_and(|v| Version::new(version.as_str()) <= v.version)
+                {
+                    continue;
+                }
+
+                let resolved = index
+                    .iter()
+                    .find(|m| dep_key(&m.author, &m.name) == key)
+                    .and_then(|m| m.get_version(&version))
+                    .ok_or_else(|| ThermiteError::DepError(dep.clone()))?;
+                trace!("Resolved {dep} to {}", resolved.full_name);
+
+                graph.insert(resolved.clone())?;
+                queue.push_back(resolved.clone());
+            }
+        }
+
+        // a lower version that was replaced may have pulled in dependencies nothing needs anymore
+        let roots: Vec<_> = mods.iter().map(|m| &m.full_name).collect();
+        let stale: Vec<_> = graph
+            .orphans(&roots)
+            .into_iter()
+            .map(|m| m.full_name.clone())
+            .collect();
+        for m in stale {
+            trace!("Dropping {m}, which is no longer needed");
+            graph.remove(m);
+        }
+
+        Ok(graph)
+    }
+
+    /// Build the graph of exactly these packages, e.g. everything that's installed, without resolving
+    /// anything from an index
+    ///
+    /// Packages with a `full_name` that isn't a valid modstring are skipped
+    #[must_use]
+    pub fn from_packages(mods: impl IntoIterator<Item = ModVersion>) -> Self {
+        let mut graph = Self::default();
+        for m in mods {
+            if let Err(e) = graph.insert(m) {
+                trace!("Skipping package: {e}");
+            }
+        }
+
+        graph
+    }
+
+    /// Add a package to the graph, replacing any other version of it
+    ///
+    /// # Errors
+    /// - The package's `full_name` or one of its dependency strings isn't formatted like `author-name-X.Y.Z`
+    pub fn insert(&mut self, package: ModVersion) -> Result<()> {
+        let ModString { author, name, .. } = ModString::try_from(&package)?;
+        let mut deps = BTreeSet::new();
+        for dep in &package.deps {
+            if let Some((key, _)) = parse_dep(dep)? {
+                deps.insert(key);
+            }
+        }
+
+        let key = dep_key(&author, &name);
+        self.edges.insert(key.clone(), deps);
+        self.nodes.insert(key, package);
+        Ok(())
+    }
+
+    /// Remove a package from the graph. Packages that depend on it keep their edge to it
+    pub fn remove(&mut self, package: impl AsRef<str>) -> Option<ModVersion> {
+        let key = package_key(package.as_ref());
+        self.edges.remove(&key);
+        self.nodes.remove(&key)
+    }
+
+    #[must_use]
+    pub fn get(&self, package: impl AsRef<str>) -> Option<&ModVersion> {
+        self.nodes.get(&package_key(package.as_ref()))
+    }
+
+    #[must_use]
+    pub fn contains(&self, package: impl AsRef<str>) -> bool {
+        self.get(package).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ModVersion> {
+        self.nodes.values()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The packages in the graph that `package` depends on directly
+    #[must_use]
+    pub fn dependencies(&self, package: impl AsRef<str>) -> Vec<&ModVersion> {
+        self.edges
+            .get(&package_key(package.as_ref()))
+            .into_iter()
+            .flatten()
+            .filter_map(|k| self.nodes.get(k))
+            .collect()
+    }
+
+    /// The packages in the graph that depend on `package` directly
+    #[must_use]
+    pub fn dependents(&self, package: impl AsRef<str>) -> Vec<&ModVersion> {
+        let key = package_key(package.as_ref());
+        self.edges
+            .iter()
+            .filter(|(_, deps)| deps.contains(&key))
+            .filter_map(|(k, _)| self.nodes.get(k))
+            .collect()
+    }
+
+    /// Dependencies that aren't in the graph, as `author-name` keys, e.g. after removing a package
+    /// that others still depend on
+    #[must_use]
+    pub fn missing(&self) -> BTreeSet<&str> {
+        self.edges
+            .values()
+            .flatten()
+            .filter(|k| !self.nodes.contains_key(*k))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Packages that can't be reached from any of `roots`, i.e. dependencies that nothing needs anymore
+    ///
+    /// # Params
+    /// * `roots` - the packages that were installed explicitly
+    #[must_use]
+    pub fn orphans(&self, roots: &[impl AsRef<str>]) -> Vec<&ModVersion> {
+        let mut reachable = BTreeSet::new();
+        let mut queue: VecDeque<String> = roots.iter().map(|r| package_key(r.as_ref())).collect();
+        while let Some(key) = queue.pop_front() {
+            if !reachable.insert(key.clone()) {
+                continue;
+            }
+            queue.extend(self.edges.get(&key).into_iter().flatten().cloned());
+        }
+
+        self.nodes
+            .iter()
+            .filter(|(k, _)| !reachable.contains(*k))
+            .map(|(_, v)| v)
+            .collect()
+    }
+
+    /// Find a dependency cycle, returned as the keys along it with the first key repeated at the end
+    #[must_use]
+    pub fn find_cycle(&self) -> Option<Vec<String>> {
+        match self.install_order() {
+            Err(Cycle(path)) => Some(path),
+            Ok(_) => None,
+        }
+    }
+
+    /// Every package in the graph, ordered so each one comes after the packages it depends on
+    ///
+    /// # Errors
+    /// - The dependencies contain a cycle
+    pub fn install_order(&self) -> Result<Vec<&ModVersion>, Cycle> {
+        let mut done = BTreeSet::new();
+        let mut plan = Vec::with_capacity(self.nodes.len());
+        for key in self.nodes.keys() {
+            self.visit(key, &mut done, &mut vec![], &mut plan)?;
+        }
+
+        Ok(plan)
+    }
+
+    fn visit<'a>(
+        &'a self,
+        key: &str,
+        done: &mut BTreeSet<String>,
+        path: &mut Vec<String>,
+        plan: &mut Vec<&'a ModVersion>,
+    ) -> Result<(), Cycle> {
+        if done.contains(key) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|p| p == key) {
+            let mut cycle = path.split_off(start);
+            cycle.push(key.to_owned());
+            return Err(Cycle(cycle));
+        }
+
+        let Some(current) = self.nodes.get(key) else {
+            return Ok(());
+        };
+        path.push(key.to_owned());
+        for dep in self.edges.get(key).into_iter().flatten() {
+            self.visit(dep, done, path, plan)?;
+        }
+        path.pop();
+
+        done.insert(key.to_owned());
+        plan.push(current);
+        Ok(())
+    }
+}
+
+/// A dependency cycle, as the `author-name` keys along it with the first key repeated at the end
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle(pub Vec<String>);
+
+impl From<Cycle> for ThermiteError {
+    fn from(value: Cycle) -> Self {
+        Self::DepError(format!("Dependency cycle: {}", value.0.join(" -> ")))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        error::ThermiteError,
+        model::{Mod, ModVersion},
+    };
+
+    use super::DepGraph;
+
+    fn names(mods: Vec<&ModVersion>) -> Vec<&str> {
+        mods.into_iter().map(|m| m.full_name.as_str()).collect()
+    }
+
+    fn package(versions: &[ModVersion]) -> Mod {
+        Mod {
+            name: versions[0].name.clone(),
+            latest: versions[versions.len() - 1].version.as_str().into(),
+            versions: versions
+                .iter()
+                .map(|v| (v.version.as_str().into(), v.clone()))
+                .collect(),
+            author: "foo".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn drop_deps_of_replaced_versions() {
+        let index = [
+            package(&[ModVersion::test("foo-other-1.0.0", &["foo-lib-2.0.0"])]),
+            package(&[
+                ModVersion::test("foo-lib-1.0.0", &["foo-old-1.0.0"]),
+                ModVersion::test("foo-lib-2.0.0", &[]),
+            ]),
+            package(&[ModVersion::test("foo-old-1.0.0", &[])]),
+        ];
+        let app = ModVersion::test("foo-app-1.0.0", &["foo-lib-1.0.0", "foo-other-1.0.0"]);
+
+        let graph = DepGraph::new(&[app], &index).expect("resolve graph");
+        assert_eq!(graph.len(), 3);
+        assert!(!graph.contains("foo-old"));
+        assert_eq!(
+            graph.get("foo-lib").map(|m| m.version.as_str()),
+            Some("2.0.0")
+        );
+
+        let order = names(graph.install_order().expect("install order"));
+        assert_eq!(order.len(), 3);
+        assert!(!order.contains(&"foo-old-1.0.0"));
+    }
+
+    #[test]
+    fn query_graph() {
+        let graph = DepGraph::from_packages([
+            ModVersion::test("foo-app-1.0.0", &["Fifty-Server_Utilities-1.0.0"]),
+            ModVersion::test("foo-other-1.0.0", &["fifty-server_utilities-1.0.0"]),
+            ModVersion::test(
+                "Fifty-Server_Utilities-1.0.0",
+                &["northstar-Northstar-1.0.0"],
+            ),
+            ModVersion::test("foo-lib-1.0.0", &[]),
+        ]);
+
+        assert_eq!(graph.len(), 4);
+        assert_eq!(
+            names(graph.dependents("Fifty-Server_Utilities")),
+            ["foo-app-1.0.0", "foo-other-1.0.0"]
+        );
+        assert_eq!(
+            names(graph.dependencies("foo-app-1.0.0")),
+            ["Fifty-Server_Utilities-1.0.0"]
+        );
+
+        let order = names(graph.install_order().expect("install order"));
+        let pos = |n: &str| order.iter().position(|v| *v == n).expect("in order");
+        assert!(pos("Fifty-Server_Utilities-1.0.0") < pos("foo-app-1.0.0"));
+        assert!(pos("Fifty-Server_Utilities-1.0.0") < pos("foo-other-1.0.0"));
+    }
+
+    #[test]
+    fn find_orphans_after_removal() {
+        let mut graph = DepGraph::from_packages([
+            ModVersion::test("foo-app-1.0.0", &["foo-mid-1.0.0"]),
+            ModVersion::test("foo-mid-1.0.0", &["foo-lib-1.0.0"]),
+            ModVersion::test("foo-lib-1.0.0", &[]),
+            ModVersion::test("foo-other-1.0.0", &[]),
+        ]);
+        assert!(graph.orphans(&["foo-app", "foo-other"]).is_empty());
+
+        graph.remove("foo-app");
+        assert_eq!(
+            names(graph.orphans(&["foo-other"])),
+            ["foo-lib-1.0.0", "foo-mid-1.0.0"]
+        );
+
+        graph.remove("foo-lib");
+        assert_eq!(graph.missing().into_iter().collect::<Vec<_>>(), ["foo-lib"]);
+    }
+
+    #[test]
+    fn detect_cycle() {
+        let graph = DepGraph::from_packages([
+            ModVersion::test("foo-app-1.0.0", &["foo-a-1.0.0"]),
+            ModVersion::test("foo-a-1.0.0", &["foo-b-1.0.0"]),
+            ModVersion::test("foo-b-1.0.0", &["foo-a-1.0.0"]),
+        ]);
+
+        assert_eq!(
+            graph.find_cycle().expect("cycle"),
+            ["foo-a", "foo-b", "foo-a"]
+        );
+        let err: ThermiteError = graph.install_order().expect_err("cycle").into();
+        assert!(matches!(err, ThermiteError::DepError(msg) if msg.contains("cycle")));
+    }
+}
@@ -0,0 +1,337 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+use tracing::debug;
+
+use crate::error::{Result, ThermiteError};
+
+/// Environment variable that overrides where Titanfall 2 is installed
+pub const GAME_PATH_VAR: &str = "TITANFALL2_PATH";
+
+/// Where [`GameLocator`] found the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameSource {
+    /// The path passed to [`GameLocator::path`]
+    Override,
+    /// The [`GAME_PATH_VAR`] environment variable
+    Environment,
+    Steam,
+    /// The EA App or Origin, including under Wine. Requires the `ea` feature
+    Ea,
+    /// The Xbox app, through Game Pass or EA Play. Only checked on Windows
+    Xbox,
+}
+
+/// Where the Xbox app installed the game, which decides whether Northstar can be installed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XboxInstall {
+    /// Under `WindowsApps`, which is read-only even for administrators. The game has to be moved
+    /// to an `XboxGames` folder from the Xbox app before Northstar can be installed
+    WindowsApps,
+    /// Under `XboxGames`, where the game's files can be modified in its `Content` folder
+    XboxGames {
+        /// The folder to install Northstar to
+        content: PathBuf,
+    },
+}
+
+impl XboxInstall {
+    /// `true` if Northstar can be installed
+    #[must_use]
+    pub const fn is_moddable(&self) -> bool {
+        matches!(self, Self::XboxGames { .. })
+    }
+}
+
+impl Display for GameSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Override => write!(f, "override"),
+            Self::Environment => write!(f, "{GAME_PATH_VAR}"),
+            Self::Steam => write!(f, "Steam"),
+            Self::Ea => write!(f, "EA App"),
+            Self::Xbox => write!(f, "Xbox app"),
+        }
+    }
+}
+
+/// Finds Titanfall 2, checking an explicit path, then [`GAME_PATH_VAR`], then Steam, then the EA App,
+/// then the Xbox app
+///
+/// Steam and the EA App are only checked with the `steam` and `ea` features enabled.
+///
+/// # Example
+/// ```no_run
+/// use thermite::core::locate::GameLocator;
+///
+/// # fn main() -> thermite::error::Result<()> {
+/// let (path, source) = GameLocator::new().locate()?;
+/// println!("Found Titanfall 2 at {} through {source}", path.display());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameLocator {
+    path: Option<PathBuf>,
+    env: bool,
+    /// Set by tests to avoid touching the process environment, `None` to read it
+    env_value: Option<Option<PathBuf>>,
+    steam: bool,
+    ea: bool,
+    xbox: bool,
+}
+
+impl Default for GameLocator {
+    fn default() -> Self {
+        Self {
+            path: None,
+            env: true,
+            env_value: None,
+            steam: true,
+            ea: true,
+            xbox: true,
+        }
+    }
+}
+
+impl GameLocator {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use this path instead of searching, e.g. one the user picked
+    #[must_use]
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Whether to check [`GAME_PATH_VAR`]. Defaults to `true`
+    #[must_use]
+    pub const fn env(mut self, env: bool) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Use this as the value of [`GAME_PATH_VAR`] instead of reading it from the environment, with
+    /// `None` treated as unset
+    #[cfg(test)]
+    #[must_use]
+    pub(crate) fn env_var(mut self, value: Option<PathBuf>) -> Self {
+        self.env_value = Some(value);
+        self
+    }
+
+    /// Whether to search Steam's libraries. Defaults to `true`
+    #[must_use]
+    pub const fn steam(mut self, steam: bool) -> Self {
+        self.steam = steam;
+        self
+    }
+
+    /// Whether to search for EA App and Origin installs. Defaults to `true`
+    #[must_use]
+    pub const fn ea(mut self, ea: bool) -> Self {
+        self.ea = ea;
+        self
+    }
+
+    /// Whether to search for Game Pass and EA Play installs from the Xbox app. Defaults to `true`
+    #[must_use]
+    pub const fn xbox(mut self, xbox: bool) -> Self {
+        self.xbox = xbox;
+        self
+    }
+
+    /// Find the game directory, the one containing `Titanfall2.exe`
+    ///
+    /// # Errors
+    /// - The override or [`GAME_PATH_VAR`] is set but doesn't contain `Titanfall2.exe`
+    /// - The game couldn't be found
+    pub fn locate(&self) -> Result<(PathBuf, GameSource)> {
+        if let Some(path) = &self.path {
+            return check_game_dir(path).map(|path| (path, GameSource::Override));
+        }
+
+        if self.env {
+            let value = match &self.env_value {
+                Some(value) => value.clone(),
+                None => std::env::var_os(GAME_PATH_VAR).map(PathBuf::from),
+            };
+            if let Some(path) = value.filter(|p| !p.as_os_str().is_empty()) {
+                return check_game_dir(&path).map(|p| (p, GameSource::Environment));
+            }
+        }
+
+        if self.steam {
+            #[cfg(feature = "steam")]
+            if let Some(path) = crate::core::titanfall() {
+                return Ok((path, GameSource::Steam));
+            }
+            debug!("Titanfall 2 isn't installed through Steam");
+        }
+
+        if self.ea {
+            #[cfg(feature = "ea")]
+            if let Some(path) =
+                crate::core::titanfall2_ea_dir().or_else(crate::core::titanfall2_wine_dir)
+            {
+                return Ok((path, GameSource::Ea));
+            }
+            debug!("Titanfall 2 isn't installed through the EA App");
+        }
+
+        if self.xbox {
+            if let Some(path) = titanfall2_xbox_dir() {
+                return Ok((path, GameSource::Xbox));
+            }
+            debug!("Titanfall 2 isn't installed through the Xbox app");
+        }
+
+        Err(ThermiteError::UnknownError(
+            "Unable to find Titanfall 2".into(),
+        ))
+    }
+}
+
+/// Check if a game directory was installed by the Xbox app, and whether Northstar can be installed to it
+///
+/// # Returns
+/// * `None` if the game wasn't installed by the Xbox app
+#[must_use]
+pub fn xbox_install(game_path: impl AsRef<Path>) -> Option<XboxInstall> {
+    let game_path = game_path.as_ref();
+    let is_named = |name: &str| {
+        game_path
+            .components()
+            .any(|c| c.as_os_str().eq_ignore_ascii_case(name))
+    };
+
+    if is_named("WindowsApps") {
+        Some(XboxInstall::WindowsApps)
+    } else if game_path.join("MicrosoftGame.config").is_file() {
+        Some(XboxInstall::XboxGames {
+            content: game_path.to_path_buf(),
+        })
+    } else if game_path
+        .join("Content")
+        .join("MicrosoftGame.config")
+        .is_file()
+        || (is_named("XboxGames") && game_path.join("Content").is_dir())
+    {
+        Some(XboxInstall::XboxGames {
+            content: game_path.join("Content"),
+        })
+    } else if is_named("XboxGames") {
+        Some(XboxInstall::XboxGames {
+            content: game_path.to_path_buf(),
+        })
+    } else {
+        None
+    }
+}
+
+/// Find Titanfall 2 in the `XboxGames` folder the Xbox app creates at the root of each drive
+///
+/// Returns the `Content` folder, which is where the game's files are. Always `None` on platforms
+/// other than Windows
+#[must_use]
+pub fn titanfall2_xbox_dir() -> Option<PathBuf> {
+    #[cfg(windows)]
+    {
+        (b'A'..=b'Z')
+            .map(|drive| {
+                PathBuf::from(format!("{}:\\", char::from(drive)))
+                    .join("XboxGames")
+                    .join("Titanfall 2")
+                    .join("Content")
+            })
+            .find(|dir| dir.join("Titanfall2.exe").is_file())
+    }
+    #[cfg(not(windows))]
+    {
+        None
+    }
+}
+
+fn check_game_dir(path: &Path) -> Result<PathBuf> {
+    let exe = path.join("Titanfall2.exe");
+    if exe.is_file() {
+        Ok(path.to_path_buf())
+    } else {
+        Err(ThermiteError::MissingFile(Box::new(exe)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::{core::utils::TempDir, error::ThermiteError};
+
+    use super::{xbox_install, GameLocator, GameSource, XboxInstall};
+
+    #[test]
+    fn locate_in_order() {
+        let dir = TempDir::create("./locate_game").expect("Unable to create temp dir");
+        let game = dir.join("Titanfall2");
+        let other = dir.join("Other");
+        fs::create_dir_all(&game).expect("create dir");
+        fs::create_dir_all(&other).expect("create dir");
+        fs::write(game.join("Titanfall2.exe"), "").expect("write exe");
+
+        let locator = GameLocator::new()
+            .env_var(None)
+            .steam(false)
+            .ea(false)
+            .xbox(false);
+        assert_eq!(
+            locator
+                .clone()
+                .path(&game)
+                .locate()
+                .expect("locate override"),
+            (game.clone(), GameSource::Override)
+        );
+        assert!(matches!(
+            locator.clone().path(&other).locate(),
+            Err(ThermiteError::MissingFile(_))
+        ));
+
+        assert!(locator.locate().is_err());
+
+        let locator = locator.env_var(Some(game.clone()));
+        assert_eq!(
+            locator.locate().expect("locate from env"),
+            (game, GameSource::Environment)
+        );
+        assert!(locator.clone().env(false).locate().is_err());
+        assert!(matches!(
+            locator.env_var(Some(other)).locate(),
+            Err(ThermiteError::MissingFile(_))
+        ));
+    }
+
+    #[test]
+    fn detect_xbox_installs() {
+        let dir = TempDir::create("./locate_xbox").expect("Unable to create temp dir");
+        let game = dir.join("XboxGames/Titanfall 2");
+        fs::create_dir_all(game.join("Content")).expect("create dir");
+        fs::write(game.join("Content/MicrosoftGame.config"), "").expect("write config");
+
+        let expected = XboxInstall::XboxGames {
+            content: game.join("Content"),
+        };
+        assert_eq!(xbox_install(&game), Some(expected.clone()));
+        assert_eq!(xbox_install(game.join("Content")), Some(expected));
+        assert!(xbox_install(&game).is_some_and(|i| i.is_moddable()));
+
+        let locked = xbox_install("C:/Program Files/WindowsApps/TitanFall2_1.0.0.0_x64");
+        assert_eq!(locked, Some(XboxInstall::WindowsApps));
+        assert!(!locked.is_some_and(|i| i.is_moddable()));
+        assert_eq!(xbox_install(&dir), None);
+    }
+}
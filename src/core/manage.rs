@@ -1,22 +1,60 @@
 use std::{
+    collections::HashSet,
     error::Error,
-    ffi::OsString,
-    fs::{self, OpenOptions},
+    ffi::{OsStr, OsString},
+    fmt::{self, Display, Formatter},
+    fs::{self, File, OpenOptions},
     io::{self, Read, Seek, Write},
+    ops::ControlFlow,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Mutex, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use crate::error::{Result, ThermiteError};
+use crate::{
+    api,
+    error::{Result, ThermiteError, UnsafeReason},
+    model::{
+        EnabledMods, InstalledMod, LocalIndex, LocalPackage, Manifest, ModJSON, ModVersion,
+        NorthstarChannel, NorthstarFile, NorthstarFiles, NorthstarRelease, Plugin,
+    },
+};
 
-use zip::ZipArchive;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zip::{
+    write::{SimpleFileOptions, ZipWriter},
+    CompressionMethod, ZipArchive,
+};
 
 use tracing::{debug, trace, warn};
 
-use super::utils::validate_modstring;
+use super::{
+    http,
+    locate::{xbox_install, XboxInstall},
+    utils::{
+        check_write_access, extended_path, find_mods, find_orphans, get_submods, is_core_dir,
+        is_core_mod, parse_modstring, validate_modstring, write_atomic,
+    },
+};
 
-const CHUNK_SIZE: usize = 1024;
+/// File written to mod folders containing the Thunderstore author of the package they came from
+pub const AUTHOR_FILE: &str = "thunderstore_author.txt";
+/// File written to mod folders containing the version of the package they came from
+pub const VERSION_FILE: &str = "thunderstore_version.txt";
+/// File in the game directory listing everything extracted by `install_northstar`
+pub const NORTHSTAR_RECORD: &str = "R2Northstar/.thermite/northstar_files.json";
+const CHUNK_SIZE: usize = 128 * 1024;
+/// Minimum time between progress events while downloading
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
 
 /// Download a file and update a progress bar
+///
+/// Requests are made with the shared [`http::agent`], so connections are reused between downloads
 /// # Params
 /// * `output` - Writer to write the data to
 /// * `url` - URL to download from
@@ -27,284 +65,4777 @@ const CHUNK_SIZE: usize = 1024;
 ///
 /// # Errors
 /// * IO Errors
-pub fn download_with_progress<F>(mut output: impl Write, url: impl AsRef<str>, cb: F) -> Result<u64>
+pub fn download_with_progress<F>(output: impl Write, url: impl AsRef<str>, cb: F) -> Result<u64>
 where
     F: Fn(u64, u64, u64),
 {
-    //send the request
-    let res = ureq::get(url.as_ref()).call()?;
-
-    let file_size = res
-        .header("Content-Length")
-        .unwrap_or_else(|| {
-            warn!("Response missing 'Content-Length' header");
-            "0"
-        })
-        .parse::<u64>()?;
-    debug!("Downloading file of size: {}", file_size);
+    download_cancellable(output, url, |delta, current, total| {
+        cb(delta, current, total);
+        ControlFlow::Continue(())
+    })
+}
 
-    //start download in chunks
-    let mut downloaded: u64 = 0;
-    let mut buffer = [0; CHUNK_SIZE];
-    let mut body = res.into_reader();
-    debug!("Starting download from {}", url.as_ref());
+/// Download a file with a callback that can stop the download
+/// # Params
+/// * `output` - Writer to write the data to
+/// * `url` - URL to download from
+/// * `cb` - Same as `download_with_progress`, but returning `ControlFlow::Break` cancels the download
+///
+/// # Returns
+/// * total bytes downloaded & written
+///
+/// # Errors
+/// * IO Errors
+/// * Network errors
+/// * `ThermiteError::Cancelled` if the callback cancelled the download. Any data already written to `output` is left as is
+pub fn download_cancellable<F>(output: impl Write, url: impl AsRef<str>, cb: F) -> Result<u64>
+where
+    F: FnMut(u64, u64, u64) -> ControlFlow<()>,
+{
+    download_with_options(output, url, &DownloadOptions::default(), cb)
+}
 
-    while let Ok(n) = body.read(&mut buffer) {
-        output.write_all(&buffer[0..n])?;
-        downloaded += n as u64;
+/// Options for `download_with_options`
+///
+/// # Example
+/// ```no_run
+/// use std::ops::ControlFlow;
+/// use thermite::core::manage::{download_with_options, DownloadOptions};
+///
+/// let options = DownloadOptions::new().max_bytes_per_sec(512 * 1024);
+/// let mut output = vec![];
+/// download_with_options(&mut output, "https://example.com/mod.zip", &options, |_, _, _| {
+///     ControlFlow::Continue(())
+/// })
+/// .unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DownloadOptions {
+    max_bytes_per_sec: Option<u64>,
+    timeout: Option<Duration>,
+}
 
-        cb(n as u64, downloaded, file_size);
+impl DownloadOptions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        if n == 0 {
-            break;
-        }
+    /// Cap the download speed, e.g. to avoid starving a game server running on the same machine.
+    /// A limit of 0 is ignored
+    #[must_use]
+    pub const fn max_bytes_per_sec(mut self, limit: u64) -> Self {
+        self.max_bytes_per_sec = if limit == 0 { None } else { Some(limit) };
+        self
     }
 
-    Ok(downloaded)
+    /// Timeout for the entire download. Connect and read timeouts are configured with
+    /// [`http::set_config`](super::http::set_config)
+    #[must_use]
+    pub const fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
 }
 
-/// Wrapper for calling `download_with_progress` without a progress bar
+/// Download a file using the given options
 /// # Params
 /// * `output` - Writer to write the data to
-/// * `url` - Url to download from
+/// * `url` - URL to download from
+/// * `options` - see `DownloadOptions`
+/// * `cb` - Same as `download_cancellable`
 ///
 /// # Returns
 /// * total bytes downloaded & written
 ///
 /// # Errors
 /// * IO Errors
-pub fn download(output: impl Write, url: impl AsRef<str>) -> Result<u64> {
-    download_with_progress(output, url, |_, _, _| {})
+/// * Network errors
+/// * `ThermiteError::Cancelled` if the callback cancelled the download
+pub fn download_with_options<F>(
+    output: impl Write,
+    url: impl AsRef<str>,
+    options: &DownloadOptions,
+    cb: F,
+) -> Result<u64>
+where
+    F: FnMut(u64, u64, u64) -> ControlFlow<()>,
+{
+    download_with_events(output, url, options, progress_events(cb))
 }
 
-#[deprecated(since = "0.7.1", note = "just use std::fs directly")]
-pub fn uninstall(mods: &[impl AsRef<Path>]) -> Result<()> {
-    for p in mods {
-        if fs::remove_dir_all(p).is_err() {
-            //try removing a file too, just in case
-            debug!("Removing dir failed, attempting to remove file...");
-            fs::remove_file(p)?;
-        }
-    }
-    Ok(())
+/// Progress of a download or installation, suitable for forwarding to a frontend
+///
+/// Serializes as e.g. `{"event":"chunk","delta":1024,"downloaded":4096}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum ProgressEvent {
+    /// The download has started. `total` is 0 if the server didn't report the size
+    Started { total: u64 },
+    /// A chunk of `delta` bytes was written, for `downloaded` bytes in total
+    Chunk { delta: u64, downloaded: u64 },
+    /// The download or installation completed
+    Finished,
+    /// Entry `i` (starting from 0) of `of` in the archive is being extracted. `bytes` is its uncompressed size
+    ExtractFile {
+        name: String,
+        i: usize,
+        of: usize,
+        bytes: u64,
+    },
 }
 
-/// Install a mod to a directory
+/// Download a file, reporting progress as `ProgressEvent`s
 /// # Params
-/// * `zip_file` - compressed mod file
-/// * `target_dir` - directory to install to
-/// * `extract_dir` - directory to extract to before installing. Defaults to a temp directory in `target_dir`
-/// * `sanity_check` - function that will be called before performing the installation. The operation will fail with `ThermiteError::SanityError` if this returns `false`
-///     - takes `File` of the zip file
-///     - returns `bool`
+/// * `output` - Writer to write the data to
+/// * `url` - URL to download from
+/// * `options` - see `DownloadOptions`
+/// * `cb` - Called with every event. Returning `ControlFlow::Break` cancels the download
 ///
-/// `target_dir` will be treated as the root of the `mods` directory in the mod file
-////// # Errors
-/// * IO Errors
-/// * Misformatted mods (typically missing the `mods` directory)
+/// # Returns
+/// * total bytes downloaded & written
 ///
-/// # Panics
-/// This function will panic if it is unable to get the current system time
-pub fn install_with_sanity<T, F>(
-    mod_string: impl AsRef<str>,
-    zip_file: T,
-    target_dir: impl AsRef<Path>,
-    sanity_check: F,
-) -> Result<PathBuf>
+/// # Errors
+/// * IO Errors
+/// * Network errors
+/// * `ThermiteError::Cancelled` if the callback cancelled the download
+pub fn download_with_events<F>(
+    output: impl Write,
+    url: impl AsRef<str>,
+    options: &DownloadOptions,
+    cb: F,
+) -> Result<u64>
 where
-    T: Read + Seek,
-    F: FnOnce(&T) -> Result<(), Box<dyn Error + Send + Sync + 'static>>,
+    F: FnMut(ProgressEvent) -> ControlFlow<()>,
 {
-    if let Err(e) = sanity_check(&zip_file) {
-        return Err(ThermiteError::SanityError(e));
-    }
-
-    if !validate_modstring(mod_string.as_ref()) {
-        return Err(ThermiteError::NameError(mod_string.as_ref().into()));
+    //send the request
+    let mut req = http::agent().get(url.as_ref());
+    if let Some(timeout) = options.timeout {
+        req = req.timeout(timeout);
     }
+    let res = req.call()?;
 
-    let path = target_dir.as_ref().join(mod_string.as_ref());
-    ZipArchive::new(zip_file)?.extract(&path)?;
-
-    Ok(path)
+    read_body(res, output, url.as_ref(), options, cb)
 }
 
-pub fn install_mod<T>(
-    mod_string: impl AsRef<str>,
-    zip_file: T,
-    target_dir: impl AsRef<Path>,
-) -> Result<PathBuf>
+/// Adapts a `|delta_bytes, current_bytes, total_size|` callback to receive `ProgressEvent`s
+fn progress_events<F>(mut cb: F) -> impl FnMut(ProgressEvent) -> ControlFlow<()>
 where
-    T: Read + Seek,
+    F: FnMut(u64, u64, u64) -> ControlFlow<()>,
 {
-    install_with_sanity(mod_string, zip_file, target_dir, |_| Ok(()))
+    let mut total = 0;
+    let mut current = 0;
+    move |event| match event {
+        ProgressEvent::Started { total: size } => {
+            total = size;
+            ControlFlow::Continue(())
+        }
+        ProgressEvent::Chunk { delta, downloaded } => {
+            current = downloaded;
+            cb(delta, current, total)
+        }
+        ProgressEvent::Finished => cb(0, current, total),
+        ProgressEvent::ExtractFile { .. } => ControlFlow::Continue(()),
+    }
 }
 
-/// Install N* to the provided path
-///
+/// Download a file from the first of several mirrors that responds
 /// # Params
-/// * `zip_file` - compressed mod file
-/// * `game_path` - the path of the Titanfall 2 install
+/// * `output` - Writer to write the data to
+/// * `urls` - URLs to try, in order of preference
+/// * `cb` - Same as `download_with_progress`
+///
+/// A mirror is skipped if anything goes wrong with it, e.g. it can't be connected to, responds
+/// with an error page or the connection drops partway through. `output` is then rewound to where
+/// it started and overwritten by the next mirror, so every mirror should serve the same file.
+///
+/// # Returns
+/// * total bytes downloaded & written
+/// * the index in `urls` of the mirror that was used
 ///
 /// # Errors
 /// * IO Errors
-pub fn install_northstar(zip_file: impl Read + Seek, game_path: impl AsRef<Path>) -> Result<()> {
-    let target = game_path.as_ref();
-    let mut archive = ZipArchive::new(zip_file)?;
+/// * Every mirror failed, in which case the error from the last one is returned
+pub fn download_from_mirrors<F>(
+    mut output: impl Write + Seek,
+    urls: &[impl AsRef<str>],
+    cb: F,
+) -> Result<(u64, usize)>
+where
+    F: Fn(u64, u64, u64),
+{
+    let start = output.stream_position()?;
+    let mut last_err = None;
+    for (i, url) in urls.iter().enumerate() {
+        let url = url.as_ref();
+        let res = http::agent()
+            .get(url)
+            .call()
+            .map_err(ThermiteError::from)
+            .and_then(|res| {
+                debug!("Trying mirror {url}");
+                read_body(
+                    res,
+                    &mut output,
+                    url,
+                    &DownloadOptions::default(),
+                    progress_events(|delta, current, total| {
+                        cb(delta, current, total);
+                        ControlFlow::Continue(())
+                    }),
+                )
+            });
 
-    let manifest = archive
-        .by_name("manifest.json")
-        .ok()
-        .map(|mut v| {
-            let mut buf = Vec::with_capacity(usize::try_from(v.size())?);
-            if let Err(e) = v.read_to_end(&mut buf) {
-                Err(ThermiteError::from(e))
-            } else {
-                Ok(buf)
+        match res {
+            Ok(size) => return Ok((size, i)),
+            Err(e) => {
+                warn!("Mirror {url} failed: {e}");
+                output.seek(io::SeekFrom::Start(start))?;
+                last_err = Some(e);
             }
-        })
-        .transpose()?;
+        }
+    }
 
-    for i in 0..archive.len() {
-        let mut f = archive.by_index(i)?;
+    Err(last_err
+        .unwrap_or_else(|| ThermiteError::UnknownError("No mirrors to download from".into())))
+}
 
-        //This should work fine for N* because the dir structure *should* always be the same
-        if f.enclosed_name()
-            .ok_or_else(|| ThermiteError::UnknownError("File missing enclosed name".into()))?
-            .starts_with("Northstar")
-        {
-            let out = target.join(
-                f.enclosed_name()
-                    .expect("enclosed name")
-                    .strip_prefix("Northstar")
-                    .expect("Nortstar prefix"),
-            );
+/// Download a file using several ranged requests in parallel, which can be much faster on
+/// high-latency connections
+/// # Params
+/// * `output` - Writer to write the data to. Segments are written out of order, so it must be seekable
+/// * `url` - URL to download from
+/// * `segments` - Number of requests to make in parallel
+/// * `cb` - Same as `download_with_progress`, called with the combined progress of all segments
+///
+/// Falls back to a normal download if the server doesn't report the file's size or doesn't support ranged requests.
+///
+/// # Returns
+/// * total bytes downloaded & written
+///
+/// # Errors
+/// * IO Errors
+/// * Network errors
+pub fn download_segmented<W, F>(
+    mut output: W,
+    url: impl AsRef<str>,
+    segments: usize,
+    cb: F,
+) -> Result<u64>
+where
+    W: Write + Seek + Send,
+    F: Fn(u64, u64, u64) + Sync,
+{
+    let url = url.as_ref();
+    let head = http::agent().head(url).call()?;
+    check_content_type(&head, url)?;
+    let file_size = head
+        .header("Content-Length")
+        .and_then(|v| v.parse::<u64>().ok());
+    let ranged = head
+        .header("Accept-Ranges")
+        .is_some_and(|v| v.eq_ignore_ascii_case("bytes"));
 
-            if (*f.name()).ends_with('/') {
-                trace!("Create directory {}", f.name());
-                fs::create_dir_all(target.join(f.name()))?;
-                continue;
-            } else if let Some(p) = out.parent() {
-                fs::create_dir_all(p)?;
-            }
+    let Some(file_size) = file_size.filter(|_| ranged && segments > 1) else {
+        debug!("Server doesn't support ranged requests, downloading normally");
+        return download_with_progress(output, url, cb);
+    };
+    debug!("Downloading file of size {file_size} in {segments} segments");
 
-            let mut outfile = OpenOptions::new()
-                .create(true)
-                .write(true)
-                .truncate(true)
-                .open(&out)?;
+    let downloaded = AtomicU64::new(0);
+    let output = Mutex::new(&mut output);
+    thread::scope(|s| {
+        let handles = split_ranges(file_size, segments)
+            .into_iter()
+            .map(|(start, end)| {
+                let output = &output;
+                let downloaded = &downloaded;
+                let cb = &cb;
+                s.spawn(move || -> Result<()> {
+                    let res = http::agent()
+                        .get(url)
+                        .set("Range", &format!("bytes={start}-{end}"))
+                        .call()?;
+                    if res.status() != 206 {
+                        return Err(ThermiteError::UnknownError(format!(
+                            "Expected partial content for range {start}-{end} but got status {}",
+                            res.status()
+                        )));
+                    }
 
-            trace!("Write file {}", out.display());
+                    let mut body = res.into_reader();
+                    let mut buffer = vec![0; CHUNK_SIZE];
+                    let mut offset = start;
+                    loop {
+                        let n = read_chunk(&mut body, &mut buffer).map_err(read_error)?;
+                        if n == 0 {
+                            break;
+                        }
 
-            io::copy(&mut f, &mut outfile)?;
-        }
+                        {
+                            let mut output = output.lock().unwrap_or_else(PoisonError::into_inner);
+                            output.seek(io::SeekFrom::Start(offset))?;
+                            output.write_all(&buffer[..n])?;
+                        }
+                        offset += n as u64;
+
+                        let current = downloaded.fetch_add(n as u64, Ordering::Relaxed) + n as u64;
+                        cb(n as u64, current, file_size);
+                    }
+
+                    if offset != end + 1 {
+                        return Err(ThermiteError::UnknownError(format!(
+                            "Segment {start}-{end} ended early at {offset}"
+                        )));
+                    }
+
+                    Ok(())
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles.into_iter().try_for_each(|h| {
+            h.join()
+                .map_err(|_| ThermiteError::UnknownError("Download thread panicked".into()))?
+        })
+    })?;
+
+    Ok(downloaded.into_inner())
+}
+
+/// Split `size` bytes into up to `segments` inclusive ranges of roughly equal length
+fn split_ranges(size: u64, segments: usize) -> Vec<(u64, u64)> {
+    if size == 0 {
+        return vec![];
     }
 
-    // add manifest and author file
-    for child in game_path
-        .as_ref()
-        .join("R2Northstar")
-        .join("mods")
-        .read_dir()?
-    {
-        let Ok(child) = child else {
-            continue;
-        };
-        if ![
-            OsString::from("Northstar.Client"),
-            OsString::from("Northstar.Custom"),
-            OsString::from("Northstar.CustomServers"),
-        ]
-        .contains(&child.file_name())
-        {
-            continue;
-        }
+    let segments = (segments.max(1) as u64).min(size);
+    let len = size.div_ceil(segments);
+    (0..size)
+        .step_by(usize::try_from(len).unwrap_or(usize::MAX))
+        .map(|start| (start, (start + len).min(size) - 1))
+        .collect()
+}
 
-        if child.file_type()?.is_dir() {
-            let dir = child.path();
-            let manifest_file = dir.join("manifest.json");
-            let author_file = dir.join("thunderstore_author.txt");
+fn read_body<F>(
+    res: ureq::Response,
+    mut output: impl Write,
+    url: &str,
+    options: &DownloadOptions,
+    mut cb: F,
+) -> Result<u64>
+where
+    F: FnMut(ProgressEvent) -> ControlFlow<()>,
+{
+    check_content_type(&res, url)?;
+    let file_size = res
+        .header("Content-Length")
+        .unwrap_or_else(|| {
+            warn!("Response missing 'Content-Length' header");
+            "0"
+        })
+        .parse::<u64>()?;
+    debug!("Downloading file of size: {}", file_size);
 
-            // write the manifest to the mod's directory
-            {
-                let mut file = OpenOptions::new()
-                    .create(true)
-                    .write(true)
-                    .truncate(true)
-                    .open(manifest_file)?;
-                if let Some(manifest) = &manifest {
-                    file.write_all(manifest)?;
-                }
+    //start download in chunks
+    let mut downloaded: u64 = 0;
+    let mut buffer = vec![0; CHUNK_SIZE];
+    let mut body = res.into_reader();
+    let throttle = options.max_bytes_per_sec.map(Throttle::new);
+    debug!("Starting download from {}", url);
+
+    if cb(ProgressEvent::Started { total: file_size }).is_break() {
+        debug!("Download from {} cancelled", url);
+        return Err(ThermiteError::Cancelled);
+    }
+
+    // progress is reported at most every PROGRESS_INTERVAL so fast downloads don't flood the callback
+    let mut unreported: u64 = 0;
+    let mut last_report = Instant::now();
+    loop {
+        let n = read_chunk(&mut body, &mut buffer).map_err(read_error)?;
+        if n > 0 {
+            output.write_all(&buffer[0..n])?;
+            downloaded += n as u64;
+            unreported += n as u64;
+            if let Some(throttle) = &throttle {
+                throttle.wait(downloaded);
             }
+        }
 
-            // write the author file to the mod's directory
-            {
-                let mut file = OpenOptions::new()
-                    .create(true)
-                    .write(true)
-                    .truncate(true)
-                    .open(author_file)?;
-                file.write_all(b"northstar")?;
+        let done = n < buffer.len();
+        if unreported > 0 && (done || last_report.elapsed() >= PROGRESS_INTERVAL) {
+            let event = ProgressEvent::Chunk {
+                delta: unreported,
+                downloaded,
+            };
+            if cb(event).is_break() {
+                debug!("Download from {} cancelled", url);
+                return Err(ThermiteError::Cancelled);
             }
+            unreported = 0;
+            last_report = Instant::now();
+        }
+
+        if done {
+            break;
         }
     }
 
-    Ok(())
+    // the download is complete at this point so there's nothing to cancel
+    let _ = cb(ProgressEvent::Finished);
+
+    Ok(downloaded)
 }
 
-#[cfg(test)]
-mod test {
+/// Thunderstore and GitHub serve error pages as HTML with a success status, which should never be
+/// written out as if they were the requested file
+fn check_content_type(res: &ureq::Response, url: &str) -> Result<()> {
+    match res.header("Content-Type") {
+        Some(ct)
+            if ct
+                .trim_start()
+                .to_ascii_lowercase()
+                .starts_with("text/html") =>
+        {
+            Err(ThermiteError::InvalidArchive {
+                url: Some(url.into()),
+                content_type: Some(ct.into()),
+            })
+        }
+        _ => Ok(()),
+    }
+}
 
-    use crate::core::utils::TempDir;
-    use mockall::mock;
-    use std::io::Cursor;
-    use tracing::info;
+/// Make sure `file` starts with a zip header, leaving it at the position it started at
+fn check_zip_magic(file: &mut (impl Read + Seek)) -> Result<()> {
+    let start = file.stream_position()?;
+    let mut magic = [0; 4];
+    let res = file.read_exact(&mut magic);
+    file.seek(io::SeekFrom::Start(start))?;
 
-    use super::{install_mod, *};
+    match (res, &magic) {
+        (Ok(()), b"PK\x03\x04" | b"PK\x05\x06" | b"PK\x07\x08") => Ok(()),
+        (Err(e), _) if e.kind() != io::ErrorKind::UnexpectedEof => Err(e.into()),
+        _ => Err(ThermiteError::InvalidArchive {
+            url: None,
+            content_type: None,
+        }),
+    }
+}
 
-    mock! {
-        Writer {}
-        impl Write for Writer {
-            fn write(&mut self, buf: &[u8]) -> io::Result<usize>;
-            fn write_all(&mut self, buf: &[u8]) -> io::Result<()>;
-            fn flush(&mut self) -> io::Result<()>;
-        }
+/// ureq reports timeouts while reading the body as IO errors
+fn read_error(e: io::Error) -> ThermiteError {
+    if e.kind() == io::ErrorKind::TimedOut {
+        ThermiteError::Timeout
+    } else {
+        e.into()
+    }
+}
 
+/// Read from `reader` until `buf` is full or the stream ends, so each chunk is written out in one go
+fn read_chunk(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
     }
 
-    mock! {
-        Archive {}
-        impl Read for Archive {
-            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+    Ok(filled)
+}
+
+/// Keeps a transfer under a fixed number of bytes per second
+struct Throttle {
+    bytes_per_sec: u64,
+    start: Instant,
+}
+
+impl Throttle {
+    fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec,
+            start: Instant::now(),
         }
+    }
 
-        impl Seek for Archive {
-            fn seek(&mut self, pos: std::io::SeekFrom) -> io::Result<u64>;
+    /// Block until `transferred` bytes are allowed to have been sent
+    fn wait(&self, transferred: u64) {
+        #[allow(clippy::cast_precision_loss)]
+        let target = Duration::from_secs_f64(transferred as f64 / self.bytes_per_sec as f64);
+        if let Some(delay) = target.checked_sub(self.start.elapsed()) {
+            trace!("Throttling download for {delay:?}");
+            thread::sleep(delay);
         }
     }
+}
 
-    const TEST_URL: &str =
-        "https://freetestdata.com/wp-content/uploads/2023/04/2.4KB_JSON-File_FreeTestData.json";
-    const TEST_SIZE_BYTES: u64 = 2455;
+/// Wrapper for calling `download_with_progress` without a progress bar
+/// # Params
+/// * `output` - Writer to write the data to
+/// * `url` - Url to download from
+///
+/// # Returns
+/// * total bytes downloaded & written
+///
+/// # Errors
+/// * IO Errors
+pub fn download(output: impl Write, url: impl AsRef<str>) -> Result<u64> {
+    download_with_progress(output, url, |_, _, _| {})
+}
+
+/// Download a file and verify its SHA-256 hash as it's written
+/// # Params
+/// * `output` - Writer to write the data to
+/// * `url` - URL to download from
+/// * `expected_sha256` - hex encoded SHA-256 hash the data should have
+///
+/// The data is written to `output` as it's received, so it should be discarded if the checksum doesn't match.
+///
+/// # Returns
+/// * total bytes downloaded & written
+///
+/// # Errors
+/// * IO Errors
+/// * Network errors
+/// * `ThermiteError::ChecksumMismatch` if the hash of the downloaded data doesn't match
+pub fn download_with_checksum(
+    output: impl Write,
+    url: impl AsRef<str>,
+    expected_sha256: impl AsRef<str>,
+) -> Result<u64> {
+    let mut writer = HashWriter {
+        inner: output,
+        hasher: Sha256::new(),
+    };
+    let size = download(&mut writer, url)?;
+
+    check_sha256(&writer.hasher.finalize(), expected_sha256.as_ref())?;
+    Ok(size)
+}
+
+/// Compute the hex encoded SHA-256 hash of a file, e.g. to check a previously downloaded mod
+///
+/// # Errors
+/// * IO Errors
+pub fn sha256_file(path: impl AsRef<Path>) -> Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+
+    Ok(to_hex(&hasher.finalize()))
+}
+
+fn check_sha256(hash: &[u8], expected: &str) -> Result<()> {
+    let actual = to_hex(hash);
+    if actual.eq_ignore_ascii_case(expected.trim()) {
+        Ok(())
+    } else {
+        Err(ThermiteError::ChecksumMismatch {
+            expected: expected.into(),
+            actual,
+        })
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Passes writes through to `inner` while hashing them
+struct HashWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> Write for HashWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Hashes everything read through it
+struct HashReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> Read for HashReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Download a file straight to disk
+///
+/// The data is streamed to `<dest>.part`, which is renamed to `dest` once the download completes,
+/// so `dest` never contains a partial download. The part file is removed if the download fails.
+/// # Params
+/// * `url` - URL to download from
+/// * `dest` - Path to save the file to. Parent directories will be created if needed
+/// * `cb` - Same as `download_with_progress`
+///
+/// # Returns
+/// * the downloaded file, opened for reading and ready to pass to `install_mod`
+///
+/// # Errors
+/// * IO Errors
+/// * Network errors
+pub fn download_file_with_progress<F>(
+    url: impl AsRef<str>,
+    dest: impl AsRef<Path>,
+    cb: F,
+) -> Result<File>
+where
+    F: Fn(u64, u64, u64),
+{
+    download_file_cancellable(url, dest, |delta, current, total| {
+        cb(delta, current, total);
+        ControlFlow::Continue(())
+    })
+}
+
+/// Same as `download_file_with_progress`, but returning `ControlFlow::Break` from `cb` cancels the
+/// download and removes the partially downloaded file
+///
+/// # Errors
+/// * IO Errors
+/// * Network errors
+/// * `ThermiteError::Cancelled` if the callback cancelled the download
+pub fn download_file_cancellable<F>(
+    url: impl AsRef<str>,
+    dest: impl AsRef<Path>,
+    cb: F,
+) -> Result<File>
+where
+    F: FnMut(u64, u64, u64) -> ControlFlow<()>,
+{
+    download_file_with_events(url, dest, &DownloadOptions::default(), progress_events(cb))
+}
+
+/// Same as `download_file_with_progress`, but using the given options and reporting `ProgressEvent`s.
+/// Returning `ControlFlow::Break` from `cb` cancels the download and removes the partially downloaded file
+///
+/// # Errors
+/// * IO Errors
+/// * Network errors
+/// * `ThermiteError::Cancelled` if the callback cancelled the download
+pub fn download_file_with_events<F>(
+    url: impl AsRef<str>,
+    dest: impl AsRef<Path>,
+    options: &DownloadOptions,
+    cb: F,
+) -> Result<File>
+where
+    F: FnMut(ProgressEvent) -> ControlFlow<()>,
+{
+    let dest = dest.as_ref();
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let part = part_path(dest);
+    let res = File::create(&part)
+        .map_err(ThermiteError::from)
+        .and_then(|mut file| {
+            download_with_events(&mut file, url, options, cb)?;
+            file.sync_all()?;
+            Ok(())
+        });
+
+    if let Err(e) = res {
+        if let Err(e) = fs::remove_file(&part) {
+            warn!("Unable to remove partial download {}: {e}", part.display());
+        }
+        return Err(e);
+    }
+
+    fs::rename(&part, dest)?;
+    Ok(File::open(dest)?)
+}
+
+/// Wrapper for calling `download_file_with_progress` without a progress bar
+///
+/// # Errors
+/// * IO Errors
+/// * Network errors
+pub fn download_file(url: impl AsRef<str>, dest: impl AsRef<Path>) -> Result<File> {
+    download_file_with_progress(url, dest, |_, _, _| {})
+}
+
+/// Downloads several mods in parallel
+///
+/// # Example
+/// ```no_run
+/// use thermite::prelude::*;
+/// use thermite::core::manage::DownloadQueue;
+///
+/// let index = get_package_index().unwrap();
+/// let versions = index.iter().filter_map(|m| m.get_latest()).cloned();
+/// for res in DownloadQueue::new(versions).concurrency(8).download_to("packages", |p| {
+///     println!("{}/{} bytes", p.downloaded, p.total);
+/// }) {
+///     if let Err(e) = res.result {
+///         eprintln!("Failed to download {}: {e}", res.version.full_name);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DownloadQueue {
+    items: Vec<ModVersion>,
+    concurrency: usize,
+    options: DownloadOptions,
+}
+
+/// Progress of an item in a `DownloadQueue`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueProgress {
+    /// Index of the item this event is for
+    pub index: usize,
+    pub event: ProgressEvent,
+    /// Bytes downloaded across the whole queue
+    pub downloaded: u64,
+    /// Expected size of the whole queue, based on each version's `file_size`
+    pub total: u64,
+}
+
+/// Outcome of downloading an item in a `DownloadQueue`
+#[derive(Debug)]
+pub struct QueueResult {
+    pub version: ModVersion,
+    /// Path of the downloaded file
+    pub result: Result<PathBuf>,
+}
+
+impl DownloadQueue {
+    /// Create a queue that downloads up to 4 mods at a time
+    #[must_use]
+    pub fn new(items: impl IntoIterator<Item = ModVersion>) -> Self {
+        Self {
+            items: items.into_iter().collect(),
+            concurrency: 4,
+            options: DownloadOptions::default(),
+        }
+    }
+
+    /// Maximum number of downloads to run at once
+    #[must_use]
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Options to use for every download
+    #[must_use]
+    pub fn options(mut self, options: DownloadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Download every item to `<dir>/<full_name>.zip`
+    ///
+    /// # Returns
+    /// * the result of each item, in the same order they were added to the queue
+    pub fn download_to<F>(self, dir: impl AsRef<Path>, cb: F) -> Vec<QueueResult>
+    where
+        F: Fn(QueueProgress) + Sync,
+    {
+        let dir = dir.as_ref();
+        let total = self.items.iter().map(|v| v.file_size).sum();
+        let downloaded = AtomicU64::new(0);
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(
+            self.items
+                .iter()
+                .map(|_| None)
+                .collect::<Vec<Option<Result<PathBuf>>>>(),
+        );
+
+        thread::scope(|s| {
+            for _ in 0..self.concurrency.min(self.items.len()) {
+                s.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(version) = self.items.get(index) else {
+                        break;
+                    };
+
+                    debug!("Downloading {}", version.full_name);
+                    let dest = dir.join(format!("{}.zip", version.full_name));
+                    let res =
+                        download_file_with_events(&version.url, &dest, &self.options, |event| {
+                            let current = match &event {
+                                ProgressEvent::Chunk { delta, .. } => {
+                                    downloaded.fetch_add(*delta, Ordering::Relaxed) + delta
+                                }
+                                _ => downloaded.load(Ordering::Relaxed),
+                            };
+                            cb(QueueProgress {
+                                index,
+                                event,
+                                downloaded: current,
+                                total,
+                            });
+                            ControlFlow::Continue(())
+                        })
+                        .map(|_| dest);
+
+                    if let Err(e) = &res {
+                        warn!("Failed to download {}: {e}", version.full_name);
+                    }
+                    results.lock().unwrap_or_else(PoisonError::into_inner)[index] = Some(res);
+                });
+            }
+        });
+
+        self.items
+            .into_iter()
+            .zip(results.into_inner().unwrap_or_else(PoisonError::into_inner))
+            .map(|(version, result)| QueueResult {
+                version,
+                result: result.unwrap_or_else(|| {
+                    Err(ThermiteError::UnknownError(
+                        "Download was never started".into(),
+                    ))
+                }),
+            })
+            .collect()
+    }
+}
+
+fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().map(OsString::from).unwrap_or_default();
+    name.push(".part");
+    dest.with_file_name(name)
+}
+
+/// Uninstall a mod by removing the whole Thunderstore package it was installed from
+/// # Params
+/// * `installed` - any of the mods in the package, as returned by `find_mods`
+/// * `packages_dir` - the directory the package was installed to
+/// * `enabled_mods` - if provided, the entries for every mod in the package are removed from it
+/// * `force` - allow removing packages that contain a Northstar core mod
+///
+/// # Returns
+/// * every mod that was in the package
+///
+/// # Errors
+/// * IO Errors
+/// * `ThermiteError::MissingFile` if the package's `manifest.json` can't be found directly inside `packages_dir`
+/// * `ThermiteError::CoreModError` if the package contains a core mod, as found by `is_core_mod`, and `force` is `false`
+pub fn uninstall_mod(
+    installed: &InstalledMod,
+    packages_dir: impl AsRef<Path>,
+    enabled_mods: Option<&mut EnabledMods>,
+    force: bool,
+) -> Result<Vec<InstalledMod>> {
+    let package = package_dir(installed, packages_dir.as_ref())?;
+    let submods = package_mods(installed, package);
+
+    if !force {
+        if let Some(core) = submods.iter().chain([installed]).find(|m| is_core_mod(m)) {
+            return Err(ThermiteError::CoreModError(core.mod_json.name.clone()));
+        }
+    }
+
+    debug!("Removing package at {}", package.display());
+    fs::remove_dir_all(package)?;
+
+    if let Some(enabled_mods) = enabled_mods {
+        for m in &submods {
+            enabled_mods.remove(&m.mod_json.name);
+        }
+    }
+
+    Ok(submods)
+}
+
+/// Uninstall every package that was only installed as a dependency and isn't needed anymore,
+/// as found by `find_orphans`
+///
+/// The removed packages are dropped from `local_index`, which has to be saved by the caller.
+/// Northstar itself and packages containing a core mod are never removed.
+///
+/// # Params
+/// * `packages_dir` - the directory the packages were installed to
+/// * `local_index` - the index of that directory
+/// * `enabled_mods` - if provided, the entries for every removed mod are removed from it
+///
+/// # Returns
+/// * the packages that were removed
+///
+/// # Errors
+/// * IO Errors
+pub fn autoremove(
+    packages_dir: impl AsRef<Path>,
+    local_index: &mut LocalIndex,
+    mut enabled_mods: Option<&mut EnabledMods>,
+) -> Result<Vec<LocalPackage>> {
+    let packages_dir = packages_dir.as_ref();
+    let orphans = find_orphans(local_index)
+        .into_iter()
+        .filter(|p| {
+            // the index is read from disk, so the modstring could be any path
+            if !validate_modstring(&p.mod_string) {
+                warn!("Not removing {}, it isn't a valid modstring", p.mod_string);
+                return false;
+            }
+            let mods_dir = packages_dir.join(&p.mod_string).join("mods");
+            let core = p.mods.iter().any(|m| is_core_dir(&mods_dir.join(m), m))
+                || find_mods(&mods_dir).is_ok_and(|mods| mods.iter().any(is_core_mod))
+                || parse_modstring(&p.mod_string)
+                    .is_ok_and(|(_, name, _)| name.eq_ignore_ascii_case("northstar"));
+            if core {
+                warn!("Not removing {}, it contains a core mod", p.mod_string);
+            }
+            !core
+        })
+        .map(|p| p.mod_string.clone())
+        .collect::<Vec<_>>();
+
+    let mut removed = vec![];
+    for mod_string in orphans {
+        let path = packages_dir.join(&mod_string);
+        if path.try_exists()? {
+            debug!("Removing orphaned package at {}", path.display());
+            fs::remove_dir_all(&path)?;
+        }
+
+        let Some(package) = local_index.remove(&mod_string) else {
+            continue;
+        };
+        if let Some(enabled_mods) = enabled_mods.as_deref_mut() {
+            for m in &package.mods {
+                enabled_mods.remove(m);
+            }
+        }
+        removed.push(package);
+    }
+
+    Ok(removed)
+}
+
+/// Update an installed mod to a new version
+/// # Params
+/// * `old` - any of the mods in the currently installed package, as returned by `find_mods`
+/// * `mod_string` - modstring of the new version, e.g. `author-name-1.2.3`
+/// * `new_zip` - compressed mod file of the new version
+/// * `target_dir` - directory to install the new version to, which the old version has to be in
+/// * `enabled_mods` - if provided, mods that were renamed in the new version keep their enabled state,
+///   and entries for mods that were removed are pruned
+/// * `preserve_config` - copy `.cfg` files from the old version over the new one, keeping any user edits
+///
+/// The new version is extracted next to the old one, and the old version is only removed once that succeeds
+/// and the new version has been moved into place. If the move fails, the old version is put back.
+///
+/// # Returns
+/// * path of the new package
+///
+/// # Errors
+/// * IO Errors
+/// * Misformatted mods
+/// * `ThermiteError::MissingFile` if the old package's `manifest.json` can't be found directly inside `target_dir`
+pub fn update_mod<T>(
+    old: &InstalledMod,
+    mod_string: impl AsRef<str>,
+    new_zip: T,
+    target_dir: impl AsRef<Path>,
+    enabled_mods: Option<&mut EnabledMods>,
+    preserve_config: bool,
+) -> Result<PathBuf>
+where
+    T: Read + Seek,
+{
+    if !validate_modstring(mod_string.as_ref()) {
+        return Err(ThermiteError::NameError(mod_string.as_ref().into()));
+    }
+
+    let old_package = package_dir(old, target_dir.as_ref())?;
+    let old_mods = package_mods(old, old_package);
+
+    // extract next to the old version first, so a bad archive leaves the old version untouched
+    let path = target_dir.as_ref().join(mod_string.as_ref());
+    let staging = target_dir
+        .as_ref()
+        .join(format!(".{}.update", mod_string.as_ref()));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+
+    let res = install_mod(mod_string.as_ref(), new_zip, &staging).and_then(|extracted| {
+        if preserve_config {
+            copy_configs(old_package, &extracted)?;
+        }
+        Ok(extracted)
+    });
+    let extracted = match res {
+        Ok(extracted) => extracted,
+        Err(e) => {
+            if let Err(e) = fs::remove_dir_all(&staging) {
+                warn!("Unable to clean up {}: {e}", staging.display());
+            }
+            return Err(e);
+        }
+    };
+
+    debug!(
+        "Replacing {} with {}",
+        old_package.display(),
+        path.display()
+    );
+    // keep the old version around until the new one is in place, so it can be restored
+    let mut backup_name = OsString::from(".");
+    backup_name.push(old_package.file_name().unwrap_or_default());
+    backup_name.push(".old");
+    let backup = old_package.with_file_name(backup_name);
+    if backup.exists() {
+        fs::remove_dir_all(&backup)?;
+    }
+    fs::rename(old_package, &backup)?;
+
+    let res = (|| {
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+        }
+        fs::rename(&extracted, &path)
+    })();
+    if let Err(e) = res {
+        if let Err(e) = fs::rename(&backup, old_package) {
+            warn!("Unable to restore {}: {e}", old_package.display());
+        }
+        if let Err(e) = fs::remove_dir_all(&staging) {
+            warn!("Unable to clean up {}: {e}", staging.display());
+        }
+        return Err(e.into());
+    }
+
+    if let Err(e) = fs::remove_dir_all(&backup) {
+        warn!("Unable to remove {}: {e}", backup.display());
+    }
+    fs::remove_dir_all(&staging)?;
+
+    if let Some(enabled_mods) = enabled_mods {
+        let manifest = fs::read_to_string(path.join("manifest.json"))
+            .map_err(ThermiteError::from)
+            .and_then(|raw| Ok(serde_json::from_str::<Manifest>(&raw)?))
+            .unwrap_or_else(|e| {
+                warn!("Unable to read the manifest of {}: {e}", path.display());
+                old.manifest.clone()
+            });
+        let new_mods = get_submods(&manifest, &path).unwrap_or_default();
+        for new in &new_mods {
+            if enabled_mods.get(&new.mod_json.name).is_some() {
+                continue;
+            }
+
+            // a mod that was renamed will still be in the same folder
+            if let Some(state) = old_mods
+                .iter()
+                .find(|m| m.path.file_name() == new.path.file_name())
+                .and_then(|m| enabled_mods.get(&m.mod_json.name))
+            {
+                enabled_mods.set(&new.mod_json.name, state);
+            }
+        }
+
+        for m in &old_mods {
+            if !new_mods.iter().any(|n| n.mod_json.name == m.mod_json.name) {
+                enabled_mods.remove(&m.mod_json.name);
+            }
+        }
+    }
+
+    Ok(path)
+}
+
+/// Find the Thunderstore package directory a mod was installed to, see [`InstalledMod::package_dir`]
+fn package_dir<'a>(installed: &'a InstalledMod, packages_dir: &Path) -> Result<&'a Path> {
+    installed
+        .package_dir(packages_dir)
+        .ok_or_else(|| ThermiteError::MissingFile(Box::new(installed.path.join("manifest.json"))))
+}
+
+/// All the mods in `installed`'s package
+fn package_mods(installed: &InstalledMod, package: &Path) -> Vec<InstalledMod> {
+    let mut mods = get_submods(&installed.manifest, package).unwrap_or_default();
+    for m in &mut mods {
+        m.author.clone_from(&installed.author);
+        m.version.clone_from(&installed.version);
+    }
+
+    mods
+}
+
+/// Copy every `.cfg` file in `from` to the same place in `to`
+fn copy_configs(from: &Path, to: &Path) -> Result<()> {
+    for child in from.read_dir()? {
+        let child = child?;
+        let dest = to.join(child.file_name());
+        if child.file_type()?.is_dir() {
+            copy_configs(&child.path(), &dest)?;
+        } else if child.path().extension().is_some_and(|ext| ext == "cfg") {
+            trace!("Preserving config {}", dest.display());
+            fs::create_dir_all(to)?;
+            fs::copy(child.path(), dest)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Files in an installed package that differ from its archive
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageReport {
+    pub missing: Vec<PathBuf>,
+    /// Files whose size or CRC-32 doesn't match
+    pub modified: Vec<PathBuf>,
+    /// Files that aren't in the archive, not counting the marker files written by `install_with_markers`
+    pub extra: Vec<PathBuf>,
+}
+
+impl PackageReport {
+    /// `true` if the package matched exactly
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.modified.is_empty() && self.extra.is_empty()
+    }
+}
+
+/// Check the files of an installed mod's package against the package downloaded from Thunderstore
+///
+/// Use `verify_package` instead to check against an archive that's already on disk
+///
+/// # Params
+/// * `installed` - any of the mods in the package, as returned by `find_mods`
+/// * `packages_dir` - the directory the package was installed to
+/// * `version` - the version of the package that should be installed
+///
+/// # Errors
+/// * IO Errors
+/// * Network errors
+/// * Misformatted archive
+/// * `ThermiteError::MissingFile` if the mod isn't inside a package with a `manifest.json` directly inside
+///   `packages_dir`
+pub fn verify_mod(
+    installed: &InstalledMod,
+    packages_dir: impl AsRef<Path>,
+    version: &ModVersion,
+) -> Result<PackageReport> {
+    let package = package_dir(installed, packages_dir.as_ref())?;
+    let mut archive = vec![];
+    download(&mut archive, &version.url)?;
+
+    verify_package(package, io::Cursor::new(archive))
+}
+
+/// Check the files in a package directory against the archive it was installed from
+///
+/// Files are compared by size and CRC-32 using the archive's central directory, so nothing is extracted
+///
+/// # Params
+/// * `package_dir` - the package's folder, e.g. `packages/author-name-1.0.0`
+/// * `zip_file` - compressed package
+///
+/// # Errors
+/// * IO Errors
+/// * Misformatted archive
+pub fn verify_package(
+    package_dir: impl AsRef<Path>,
+    mut zip_file: impl Read + Seek,
+) -> Result<PackageReport> {
+    let package = package_dir.as_ref();
+    check_zip_magic(&mut zip_file)?;
+    let mut archive = ZipArchive::new(zip_file)?;
+
+    let mut report = PackageReport::default();
+    let mut known = HashSet::new();
+    for i in 0..archive.len() {
+        let f = archive.by_index_raw(i)?;
+        let Some(name) = f.enclosed_name() else {
+            continue;
+        };
+        if f.is_dir() {
+            continue;
+        }
+
+        let path = package.join(&name);
+        known.insert(name);
+        let Ok(meta) = path.symlink_metadata() else {
+            report.missing.push(path);
+            continue;
+        };
+        if f.is_symlink() {
+            if !meta.file_type().is_symlink() {
+                report.modified.push(path);
+            }
+        } else if meta.len() != f.size() || crc32_file(&path)? != f.crc32() {
+            report.modified.push(path);
+        }
+    }
+
+    let mut dirs = vec![package.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for child in dir.read_dir()? {
+            let child = child?;
+            let path = child.path();
+            if child.file_type()?.is_dir() {
+                dirs.push(path);
+            } else if child.file_name() != AUTHOR_FILE
+                && child.file_name() != VERSION_FILE
+                && !path.strip_prefix(package).is_ok_and(|p| known.contains(p))
+            {
+                report.extra.push(path);
+            }
+        }
+    }
+    report.extra.sort();
+
+    Ok(report)
+}
+
+fn crc32_file(path: &Path) -> Result<u32> {
+    let mut file = File::open(path)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+        let read = read_chunk(&mut file, &mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+
+    Ok(hasher.finalize())
+}
+
+#[deprecated(
+    since = "0.7.1",
+    note = "use uninstall_mod, or just use std::fs directly"
+)]
+pub fn uninstall(mods: &[impl AsRef<Path>]) -> Result<()> {
+    for p in mods {
+        if fs::remove_dir_all(p).is_err() {
+            //try removing a file too, just in case
+            debug!("Removing dir failed, attempting to remove file...");
+            fs::remove_file(p)?;
+        }
+    }
+    Ok(())
+}
+
+/// Install a mod to a directory
+/// # Params
+/// * `zip_file` - compressed mod file
+/// * `target_dir` - directory to install to
+/// * `extract_dir` - directory to extract to before installing. Defaults to a temp directory in `target_dir`
+/// * `sanity_check` - function that will be called before performing the installation. The operation will fail with `ThermiteError::SanityError` if this returns `false`
+///     - takes `File` of the zip file
+///     - returns `bool`
+///
+/// `target_dir` will be treated as the root of the `mods` directory in the mod file
+////// # Errors
+/// * IO Errors
+/// * Misformatted mods (typically missing the `mods` directory)
+///
+/// # Panics
+/// This function will panic if it is unable to get the current system time
+pub fn install_with_sanity<T, F>(
+    mod_string: impl AsRef<str>,
+    mut zip_file: T,
+    target_dir: impl AsRef<Path>,
+    sanity_check: F,
+) -> Result<PathBuf>
+where
+    T: Read + Seek,
+    F: FnOnce(&T) -> Result<(), Box<dyn Error + Send + Sync + 'static>>,
+{
+    if let Err(e) = sanity_check(&zip_file) {
+        return Err(ThermiteError::SanityError(e));
+    }
+
+    if !validate_modstring(mod_string.as_ref()) {
+        return Err(ThermiteError::NameError(mod_string.as_ref().into()));
+    }
+
+    check_zip_magic(&mut zip_file)?;
+
+    let path = target_dir.as_ref().join(mod_string.as_ref());
+    let mut archive = ZipArchive::new(zip_file)?;
+    validate_archive(&mut archive)?;
+    archive.extract(&path)?;
+
+    Ok(path)
+}
+
+/// Install a mod to a directory, checking the opened archive first
+/// # Params
+/// * `mod_string` - same as `install_mod`
+/// * `zip_file` - compressed mod file
+/// * `target_dir` - directory to install to
+/// * `check` - called with the archive and its parsed `manifest.json`, if it has one. The installation will fail
+///   with `ThermiteError::SanityError` if this returns an error
+///
+/// Unlike `install_with_sanity`, the archive is only parsed once for both the check and the extraction.
+///
+/// # Example
+/// ```no_run
+/// use std::fs::File;
+/// use thermite::core::manage::install_checked;
+///
+/// let file = File::open("foo-bar-0.1.0.zip").unwrap();
+/// install_checked("foo-bar-0.1.0", file, "packages", |archive, _manifest| {
+///     if !archive.file_names().any(|n| n.starts_with("mods/") || n.starts_with("plugins/")) {
+///         return Err("package doesn't contain any mods or plugins".into());
+///     }
+///     Ok(())
+/// })
+/// .unwrap();
+/// ```
+///
+/// # Errors
+/// * IO Errors
+/// * Misformatted mods
+/// * `ThermiteError::SanityError` if the check failed
+pub fn install_checked<T, F>(
+    mod_string: impl AsRef<str>,
+    mut zip_file: T,
+    target_dir: impl AsRef<Path>,
+    check: F,
+) -> Result<PathBuf>
+where
+    T: Read + Seek,
+    F: FnOnce(
+        &mut ZipArchive<T>,
+        Option<&Manifest>,
+    ) -> Result<(), Box<dyn Error + Send + Sync + 'static>>,
+{
+    if !validate_modstring(mod_string.as_ref()) {
+        return Err(ThermiteError::NameError(mod_string.as_ref().into()));
+    }
+
+    check_zip_magic(&mut zip_file)?;
+
+    let mut archive = ZipArchive::new(zip_file)?;
+    validate_archive(&mut archive)?;
+    let manifest = read_manifest(&mut archive)?;
+    check(&mut archive, manifest.as_ref()).map_err(ThermiteError::SanityError)?;
+
+    let path = target_dir.as_ref().join(mod_string.as_ref());
+    archive.extract(&path)?;
+
+    Ok(path)
+}
+
+/// Parse the archive's `manifest.json`, if it has one
+fn read_manifest<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<Option<Manifest>> {
+    match archive.by_name("manifest.json") {
+        Ok(f) => Ok(Some(serde_json::from_reader(f)?)),
+        Err(zip::result::ZipError::FileNotFound) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Entries larger than this are checked for suspicious compression ratios
+const RATIO_CHECK_MIN_SIZE: u64 = 1024 * 1024;
+/// Highest uncompressed to compressed size ratio allowed for large entries
+const MAX_COMPRESSION_RATIO: u64 = 100;
+/// File names Windows won't let us create, regardless of extension
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Check every entry in an archive is safe to extract. This is done automatically by all of the install functions
+///
+/// Rejects entries with absolute paths or `..` components, symlinks that point outside of the archive or
+/// through another symlink, entries inside symlinked folders, names reserved on Windows, and large entries
+/// with suspiciously high compression ratios (zip bombs)
+///
+/// # Errors
+/// * `ThermiteError::UnsafeArchive` describing the first unsafe entry
+/// * Zip errors
+pub fn validate_archive<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<()> {
+    for i in 0..archive.len() {
+        let f = archive.by_index(i)?;
+        let name = f.name().replace('\\', "/");
+        let unsafe_entry = |reason| ThermiteError::UnsafeArchive {
+            entry: f.name().into(),
+            reason,
+        };
+
+        let bytes = name.as_bytes();
+        if name.starts_with('/') || (bytes.len() > 1 && bytes[1] == b':') {
+            return Err(unsafe_entry(UnsafeReason::AbsolutePath));
+        }
+
+        let mut depth = 0usize;
+        for part in name.split('/') {
+            match part {
+                "" | "." => {}
+                ".." if depth == 0 => return Err(unsafe_entry(UnsafeReason::PathTraversal)),
+                ".." => depth -= 1,
+                part => {
+                    let stem = part.split('.').next().unwrap_or(part).trim_end();
+                    if RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem)) {
+                        return Err(unsafe_entry(UnsafeReason::ReservedName));
+                    }
+                    depth += 1;
+                }
+            }
+        }
+
+        if f.size() > RATIO_CHECK_MIN_SIZE
+            && f.size() / f.compressed_size().max(1) > MAX_COMPRESSION_RATIO
+        {
+            return Err(unsafe_entry(UnsafeReason::CompressionRatio));
+        }
+    }
+
+    validate_links(archive, |name| Some(name.to_path_buf()))
+}
+
+/// Check the symlinks in an archive against where its entries are extracted to
+///
+/// `place` maps each entry's path in the archive to its path relative to the extraction directory, or `None` if
+/// it isn't extracted. Extractors that move entries, e.g. by stripping a folder, have to check again with their
+/// own mapping, since a target inside the archive can point outside of where the entries end up.
+///
+/// Rejects symlinks that point outside of the extraction directory or through another symlink, and entries that
+/// would be written through a symlink. Paths are compared case-insensitively, since they are on Windows and macOS
+///
+/// # Errors
+/// * `ThermiteError::UnsafeArchive` describing the first unsafe entry
+/// * Zip errors
+fn validate_links<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    place: impl Fn(&Path) -> Option<PathBuf>,
+) -> Result<()> {
+    let mut entries = vec![];
+    for i in 0..archive.len() {
+        let mut f = archive.by_index(i)?;
+        let Some(path) = f.enclosed_name().and_then(|name| place(&name)) else {
+            continue;
+        };
+        let target = if f.is_symlink() {
+            let mut target = String::new();
+            f.read_to_string(&mut target)?;
+            Some(target.replace('\\', "/").to_lowercase())
+        } else {
+            None
+        };
+        entries.push((f.name().to_owned(), link_path(&path), target));
+    }
+
+    let links = entries
+        .iter()
+        .filter(|(_, _, target)| target.is_some())
+        .map(|(_, path, _)| path.join("/"))
+        .collect::<HashSet<_>>();
+    let is_link = |path: &[String]| !path.is_empty() && links.contains(&path.join("/"));
+
+    for (entry, path, target) in &entries {
+        let escape = || ThermiteError::UnsafeArchive {
+            entry: entry.clone(),
+            reason: UnsafeReason::SymlinkEscape,
+        };
+
+        if (1..path.len()).any(|len| is_link(&path[..len])) {
+            return Err(escape());
+        }
+
+        let Some(target) = target else {
+            continue;
+        };
+        if target.starts_with('/') || target.as_bytes().get(1) == Some(&b':') {
+            return Err(escape());
+        }
+
+        // symlinks are resolved relative to the directory they're in
+        let mut resolved = path[..path.len().saturating_sub(1)].to_vec();
+        for part in target.split('/') {
+            if is_link(&resolved) {
+                return Err(escape());
+            }
+            match part {
+                "" | "." => {}
+                ".." => {
+                    if resolved.pop().is_none() {
+                        return Err(escape());
+                    }
+                }
+                part => resolved.push(part.to_owned()),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The lowercase components of a path in an archive, with `..` applied
+fn link_path(path: &Path) -> Vec<String> {
+    let mut parts = vec![];
+    for c in path.components() {
+        match c {
+            std::path::Component::Normal(part) => {
+                parts.push(part.to_string_lossy().to_lowercase());
+            }
+            std::path::Component::ParentDir => {
+                parts.pop();
+            }
+            _ => {}
+        }
+    }
+    parts
+}
+
+pub fn install_mod<T>(
+    mod_string: impl AsRef<str>,
+    zip_file: T,
+    target_dir: impl AsRef<Path>,
+) -> Result<PathBuf>
+where
+    T: Read + Seek,
+{
+    install_with_sanity(mod_string, zip_file, target_dir, |_| Ok(()))
+}
+
+/// Options for [`install_mod_with`]
+///
+/// The defaults match [`install_mod`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstallOptions {
+    repair_layout: bool,
+}
+
+impl InstallOptions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move mods that aren't in a `mods/<ModName>/` folder to where Northstar will load them from,
+    /// as found by [`check_layout`]. Disabled by default
+    #[must_use]
+    pub const fn repair_layout(mut self, repair: bool) -> Self {
+        self.repair_layout = repair;
+        self
+    }
+}
+
+/// The result of [`install_mod_with`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallReport {
+    /// The directory the package was installed to
+    pub path: PathBuf,
+    /// Folders that were moved while extracting, empty unless `repair_layout` was set
+    pub fixes: Vec<LayoutFix>,
+}
+
+/// A folder in a mod archive that Northstar won't load mods from, and where its contents belong instead
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutFix {
+    /// Folder in the archive, empty for the archive root
+    pub from: PathBuf,
+    /// Where the folder's contents belong, relative to the package folder
+    pub to: PathBuf,
+}
+
+impl Display for LayoutFix {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let show = |p: &Path| {
+            if p.as_os_str().is_empty() {
+                "the package root".into()
+            } else {
+                p.display().to_string()
+            }
+        };
+        write!(f, "Moved {} to {}", show(&self.from), show(&self.to))
+    }
+}
+
+/// Files Thunderstore expects at the root of a package, which stay there when the root is moved
+const PACKAGE_FILES: [&str; 4] = ["manifest.json", "icon.png", "README.md", "CHANGELOG.md"];
+
+/// Find mods in an archive that aren't laid out as `mods/<ModName>/mod.json`, and so won't be loaded
+///
+/// Recognizes a `mod.json` at the root of the archive, mod folders missing the `mods/` wrapper,
+/// and packages wrapped in an extra folder, e.g. from zipping the folder rather than its contents.
+/// Anything else is left alone.
+///
+/// # Errors
+/// * Zip errors
+/// * A `mod.json` at the root of the archive can't be parsed, since its `Name` is needed for the folder
+pub fn check_layout<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<Vec<LayoutFix>> {
+    let mut mod_dirs = vec![];
+    for i in 0..archive.len() {
+        let f = archive.by_index(i)?;
+        let Some(name) = f.enclosed_name() else {
+            continue;
+        };
+        if !f.is_dir() && name.file_name() == Some(OsStr::new("mod.json")) {
+            mod_dirs.push(name.parent().map(Path::to_path_buf).unwrap_or_default());
+        }
+    }
+    mod_dirs.sort_by_key(|d| d.components().count());
+
+    let mut fixes: Vec<LayoutFix> = vec![];
+    for dir in mod_dirs {
+        // mods nested in a folder that's already being moved will end up wherever it does
+        if fixes
+            .iter()
+            .any(|f| f.from.as_os_str().is_empty() || dir.starts_with(&f.from))
+        {
+            continue;
+        }
+
+        let parts = dir.iter().filter_map(OsStr::to_str).collect::<Vec<_>>();
+        let fix = match parts.as_slice() {
+            ["mods", _] => continue,
+            [] => {
+                let mod_json: ModJSON = json5::from_str(&read_entry(archive, "mod.json")?)?;
+                LayoutFix {
+                    from: PathBuf::new(),
+                    to: Path::new("mods").join(folder_name(&mod_json.name)?),
+                }
+            }
+            [folder] => LayoutFix {
+                from: dir.clone(),
+                to: Path::new("mods").join(folder),
+            },
+            [wrapper @ .., "mods", _] => LayoutFix {
+                from: wrapper.iter().collect(),
+                to: PathBuf::new(),
+            },
+            _ => {
+                warn!("Not sure where the mod in {} belongs", dir.display());
+                continue;
+            }
+        };
+        trace!("{fix}");
+        fixes.push(fix);
+    }
+
+    Ok(fixes)
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<String> {
+    let mut raw = String::new();
+    archive.by_name(name)?.read_to_string(&mut raw)?;
+    Ok(raw)
+}
+
+/// Turn a mod's `Name` into a folder name that's valid on every platform
+fn folder_name(name: &str) -> Result<String> {
+    let folder = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_control() || r#"<>:"/\|?*"#.contains(c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect::<String>();
+    if folder.is_empty() || folder == "." || folder == ".." {
+        return Err(ThermiteError::NameError(name.into()));
+    }
+
+    Ok(folder)
+}
+
+/// Where an archive entry should be extracted to after applying `fixes`
+fn relocate(name: &Path, fixes: &[LayoutFix]) -> PathBuf {
+    let stays_at_root = name.components().count() == 1
+        && PACKAGE_FILES
+            .iter()
+            .any(|f| name.as_os_str().eq_ignore_ascii_case(f))
+        || name.starts_with("mods")
+        || name.starts_with("plugins");
+
+    fixes
+        .iter()
+        .filter(|f| name.starts_with(&f.from))
+        .filter(|f| !(f.from.as_os_str().is_empty() && stays_at_root))
+        .max_by_key(|f| f.from.components().count())
+        .and_then(|f| name.strip_prefix(&f.from).ok().map(|rest| f.to.join(rest)))
+        .unwrap_or_else(|| name.to_path_buf())
+}
+
+/// Install a mod to a directory using the given options
+/// # Params
+/// * `mod_string` - same as `install_mod`
+/// * `zip_file` - compressed mod file
+/// * `target_dir` - directory to install to
+/// * `options` - see `InstallOptions`
+///
+/// # Returns
+/// * where the package was installed, and any layout problems that were fixed
+///
+/// # Example
+/// ```no_run
+/// use std::fs::File;
+/// use thermite::core::manage::{install_mod_with, InstallOptions};
+///
+/// let file = File::open("foo-bar-0.1.0.zip").unwrap();
+/// let report = install_mod_with("foo-bar-0.1.0", file, "packages", &InstallOptions::new().repair_layout(true))
+///     .unwrap();
+/// for fix in report.fixes {
+///     println!("{fix}");
+/// }
+/// ```
+///
+/// # Errors
+/// * IO Errors
+/// * Misformatted mods
+pub fn install_mod_with<T>(
+    mod_string: impl AsRef<str>,
+    mut zip_file: T,
+    target_dir: impl AsRef<Path>,
+    options: &InstallOptions,
+) -> Result<InstallReport>
+where
+    T: Read + Seek,
+{
+    if !validate_modstring(mod_string.as_ref()) {
+        return Err(ThermiteError::NameError(mod_string.as_ref().into()));
+    }
+
+    check_zip_magic(&mut zip_file)?;
+
+    let path = target_dir.as_ref().join(mod_string.as_ref());
+    let mut archive = ZipArchive::new(zip_file)?;
+    validate_archive(&mut archive)?;
+    let fixes = if options.repair_layout {
+        check_layout(&mut archive)?
+    } else {
+        vec![]
+    };
+
+    if fixes.is_empty() {
+        archive.extract(&path)?;
+    } else {
+        for fix in &fixes {
+            debug!("{fix} in {}", mod_string.as_ref());
+        }
+        extract_relocated(&mut archive, &path, &fixes)?;
+    }
+
+    Ok(InstallReport { path, fixes })
+}
+
+/// Extract every entry of `archive` into `dest`, moving them according to `fixes`
+fn extract_relocated<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    dest: &Path,
+    fixes: &[LayoutFix],
+) -> Result<()> {
+    // moving entries changes where their links point, so they have to be checked again
+    validate_links(archive, |name| Some(relocate(name, fixes)))?;
+
+    let dest = extended_path(dest);
+    let mut writer = EntryWriter::new(&dest)?;
+    for i in 0..archive.len() {
+        let mut f = archive.by_index(i)?;
+        let Some(name) = f.enclosed_name() else {
+            continue;
+        };
+
+        let mut out = dest.clone();
+        out.extend(relocate(&name, fixes).components());
+        let entry = f.name().to_owned();
+        if f.is_dir() {
+            writer.create_dir(&entry, &out)?;
+            continue;
+        }
+
+        let (symlink, mode) = (f.is_symlink(), f.unix_mode());
+        writer.write(&entry, &mut f, &out, symlink, mode)?;
+    }
+
+    writer.finish()
+}
+
+/// Same as `install_mod`, but returns the Northstar mods that were in the package, so callers
+/// don't have to run `find_mods` again to learn what was installed
+///
+/// # Returns
+/// * the installed mods, in the same form `find_mods` would return them. Packages without a `manifest.json`
+///   (e.g. plugin-only packages) return no mods
+///
+/// # Errors
+/// * IO Errors
+/// * Misformatted mods
+pub fn install_mod_verbose<T>(
+    mod_string: impl AsRef<str>,
+    mut zip_file: T,
+    target_dir: impl AsRef<Path>,
+) -> Result<Vec<InstalledMod>>
+where
+    T: Read + Seek,
+{
+    let (author, _, version) = parse_modstring(mod_string.as_ref())?;
+    check_zip_magic(&mut zip_file)?;
+
+    let path = target_dir.as_ref().join(mod_string.as_ref());
+    let mut archive = ZipArchive::new(zip_file)?;
+    validate_archive(&mut archive)?;
+
+    let manifest = read_manifest(&mut archive)?;
+
+    let mut mods = vec![];
+    if let Some(manifest) = manifest {
+        for i in 0..archive.len() {
+            let mut f = archive.by_index(i)?;
+            let Some(name) = f.enclosed_name() else {
+                continue;
+            };
+            if f.is_dir() || name.file_name() != Some("mod.json".as_ref()) {
+                continue;
+            }
+
+            let mut raw = String::new();
+            f.read_to_string(&mut raw)?;
+            match json5::from_str(&raw) {
+                Ok(mod_json) => mods.push(InstalledMod {
+                    manifest: manifest.clone(),
+                    mod_json,
+                    author: author.clone(),
+                    version: version.clone(),
+                    path: path.join(name.parent().unwrap_or(Path::new(""))),
+                }),
+                Err(e) => warn!("Error parsing {}: {e}", name.display()),
+            }
+        }
+    }
+
+    archive.extract(&path)?;
+
+    Ok(mods)
+}
+
+/// Same as `install_mod_verbose`, but also writes a [`AUTHOR_FILE`] and [`VERSION_FILE`] into each
+/// mod folder in the package, so the mods' origin can be recovered even if they're moved out of the package
+///
+/// # Errors
+/// * IO Errors
+/// * Misformatted mods
+pub fn install_with_markers<T>(
+    mod_string: impl AsRef<str>,
+    zip_file: T,
+    target_dir: impl AsRef<Path>,
+) -> Result<Vec<InstalledMod>>
+where
+    T: Read + Seek,
+{
+    let mods = install_mod_verbose(mod_string, zip_file, target_dir)?;
+    for m in &mods {
+        trace!("Writing marker files to {}", m.path.display());
+        fs::write(m.path.join(AUTHOR_FILE), &m.author)?;
+        fs::write(m.path.join(VERSION_FILE), &m.manifest.version_number)?;
+    }
+
+    Ok(mods)
+}
+
+/// Same as `install_mod`, but reports each file as it's extracted
+/// # Params
+/// * `cb` - Called with a `ProgressEvent::ExtractFile` for every entry in the archive, then `ProgressEvent::Finished`.
+///   Returning `ControlFlow::Break` stops the installation, leaving any files already extracted in place
+///
+/// # Errors
+/// * IO Errors
+/// * Misformatted mods
+/// * `ThermiteError::Cancelled` if the callback cancelled the installation
+pub fn install_with_progress<T, F>(
+    mod_string: impl AsRef<str>,
+    mut zip_file: T,
+    target_dir: impl AsRef<Path>,
+    mut cb: F,
+) -> Result<PathBuf>
+where
+    T: Read + Seek,
+    F: FnMut(ProgressEvent) -> ControlFlow<()>,
+{
+    if !validate_modstring(mod_string.as_ref()) {
+        return Err(ThermiteError::NameError(mod_string.as_ref().into()));
+    }
+
+    check_zip_magic(&mut zip_file)?;
+
+    let path = target_dir.as_ref().join(mod_string.as_ref());
+    extract_with_progress(&mut ZipArchive::new(zip_file)?, &path, &mut cb)?;
+    let _ = cb(ProgressEvent::Finished);
+
+    Ok(path)
+}
+
+/// Extract every entry of `archive` into `dest`, reporting each one to `cb`
+fn extract_with_progress<R, F>(archive: &mut ZipArchive<R>, dest: &Path, cb: &mut F) -> Result<()>
+where
+    R: Read + Seek,
+    F: FnMut(ProgressEvent) -> ControlFlow<()>,
+{
+    validate_archive(archive)?;
+    check_write_access(dest)?;
+    let dest = extended_path(dest);
+    let mut writer = EntryWriter::new(&dest)?;
+
+    let of = archive.len();
+    for i in 0..of {
+        let mut f = archive.by_index(i)?;
+        let Some(name) = f.enclosed_name() else {
+            return Err(ThermiteError::UnknownError(format!(
+                "Archive entry {} has an invalid path",
+                f.name()
+            )));
+        };
+
+        let event = ProgressEvent::ExtractFile {
+            name: f.name().into(),
+            i,
+            of,
+            bytes: f.size(),
+        };
+        if cb(event).is_break() {
+            debug!("Extraction to {} cancelled", dest.display());
+            return Err(ThermiteError::Cancelled);
+        }
+
+        let mut out = dest.clone();
+        out.extend(name.components());
+        let entry = f.name().to_owned();
+        if f.is_dir() {
+            writer.create_dir(&entry, &out)?;
+            continue;
+        }
+
+        let (symlink, mode) = (f.is_symlink(), f.unix_mode());
+        writer.write(&entry, &mut f, &out, symlink, mode)?;
+    }
+
+    writer.finish()
+}
+
+/// Writes archive entries to a directory, refusing to write anywhere outside of it
+///
+/// Symlinks are only created by `finish`, once every other entry has been written, so no entry is written
+/// through one from the same archive. Symlinks left in the directory by an earlier install are caught by
+/// checking where each entry's folder really is before writing to it.
+struct EntryWriter {
+    /// The canonical destination directory
+    dest: PathBuf,
+    links: Vec<(PathBuf, String)>,
+}
+
+impl EntryWriter {
+    fn new(dest: &Path) -> Result<Self> {
+        fs::create_dir_all(dest)?;
+        Ok(Self {
+            dest: dest.canonicalize()?,
+            links: vec![],
+        })
+    }
+
+    /// Create the directory `out` for the entry `name`, along with any missing parents
+    fn create_dir(&self, name: &str, out: &Path) -> Result<()> {
+        self.check_inside(name, out)?;
+        trace!("Create directory {}", out.display());
+        fs::create_dir_all(out)?;
+        Ok(())
+    }
+
+    /// Write the entry `name` to `out`, applying its unix permissions where supported. Symlinks are
+    /// recreated by `finish`
+    fn write(
+        &mut self,
+        name: &str,
+        entry: &mut impl Read,
+        out: &Path,
+        symlink: bool,
+        mode: Option<u32>,
+    ) -> Result<()> {
+        if let Some(parent) = out.parent() {
+            self.create_dir(name, parent)?;
+        }
+
+        #[cfg(unix)]
+        if symlink {
+            let mut target = String::new();
+            entry.read_to_string(&mut target)?;
+            self.links.push((out.to_path_buf(), target));
+            return Ok(());
+        }
+        #[cfg(not(unix))]
+        let _ = symlink;
+
+        // opening the file would follow a symlink already there
+        if out
+            .symlink_metadata()
+            .is_ok_and(|m| m.file_type().is_symlink())
+        {
+            fs::remove_file(out)?;
+        }
+
+        trace!("Write file {}", out.display());
+        let mut outfile = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(out)?;
+        io::copy(entry, &mut outfile)?;
+
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::PermissionsExt;
+            // keep the file writable, or installing over it again would fail
+            fs::set_permissions(out, fs::Permissions::from_mode((mode & 0o777) | 0o600))?;
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+
+        Ok(())
+    }
+
+    /// Create the symlinks, now that every other entry has been written
+    fn finish(self) -> Result<()> {
+        #[cfg(unix)]
+        for (out, target) in self.links {
+            if out.symlink_metadata().is_ok() {
+                fs::remove_file(&out)?;
+            }
+
+            trace!("Link {} to {target}", out.display());
+            std::os::unix::fs::symlink(target, out)?;
+        }
+
+        Ok(())
+    }
+
+    /// Refuse `path` if the closest part of it that exists resolves outside of the destination
+    fn check_inside(&self, name: &str, path: &Path) -> Result<()> {
+        let resolved = path.ancestors().find_map(|p| p.canonicalize().ok());
+        if resolved.is_some_and(|p| p.starts_with(&self.dest)) {
+            Ok(())
+        } else {
+            Err(ThermiteError::UnsafeArchive {
+                entry: name.into(),
+                reason: UnsafeReason::PathTraversal,
+            })
+        }
+    }
+}
+
+/// Install a mod from a zip file on disk, e.g. one that was sideloaded rather than downloaded from Thunderstore
+/// # Params
+/// * `path` - path of the compressed mod file
+/// * `target_dir` - same as `install_mod`
+///
+/// The modstring is taken from the file name if it's in `author-name-X.Y.Z` format, as files downloaded
+/// from Thunderstore are. Otherwise it's built from the `name` and `version_number` in the archive's
+/// `manifest.json`, with `local` as the author.
+///
+/// # Errors
+/// * IO Errors
+/// * `ThermiteError::NameError` if no valid modstring could be found
+/// * Misformatted mods
+pub fn install_from_file(path: impl AsRef<Path>, target_dir: impl AsRef<Path>) -> Result<PathBuf> {
+    let path = path.as_ref();
+    let mut file = File::open(path)?;
+    check_zip_magic(&mut file)?;
+
+    let from_name = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .filter(|s| validate_modstring(s))
+        .map(ToOwned::to_owned);
+    let mod_string = if let Some(mod_string) = from_name {
+        mod_string
+    } else {
+        let mut archive = ZipArchive::new(&mut file)?;
+        let Some(manifest) = read_manifest(&mut archive)? else {
+            return Err(ThermiteError::NameError(path.display().to_string()));
+        };
+        format!("local-{}-{}", manifest.name, manifest.version_number)
+    };
+    debug!("Installing {} as {mod_string}", path.display());
+
+    file.rewind()?;
+    install_mod(mod_string, file, target_dir)
+}
+
+/// Install the plugins from a Thunderstore package with a `plugins/` folder
+///
+/// Northstar only loads plugins from the profile's `plugins` directory, so the contents of the package's
+/// `plugins/` folder are extracted there directly. The rest of the package is ignored, use `install_mod`
+/// for any mods it contains.
+///
+/// # Params
+/// * `zip_file` - compressed package
+/// * `plugins_dir` - the profile's plugins directory, e.g. `R2Northstar/plugins`
+///
+/// # Returns
+/// * the paths of the extracted files
+///
+/// # Errors
+/// * IO Errors
+/// * `ThermiteError::UnsafeArchive` if the archive fails `validate_archive`
+/// * `ThermiteError::MissingFile` if the package doesn't have a `plugins/` folder
+pub fn install_plugin(
+    mut zip_file: impl Read + Seek,
+    plugins_dir: impl AsRef<Path>,
+) -> Result<Vec<PathBuf>> {
+    check_zip_magic(&mut zip_file)?;
+    let mut archive = ZipArchive::new(zip_file)?;
+    validate_archive(&mut archive)?;
+    let place = |name: &Path| {
+        name.strip_prefix("plugins")
+            .ok()
+            .filter(|rel| !rel.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+    };
+    // plugins/ is stripped, so links have to be checked against where they end up
+    validate_links(&mut archive, place)?;
+
+    let plugins_dir = plugins_dir.as_ref();
+    let extended = extended_path(plugins_dir);
+    let mut writer = EntryWriter::new(&extended)?;
+    let mut written = vec![];
+    for i in 0..archive.len() {
+        let mut f = archive.by_index(i)?;
+        let Some(rel) = f.enclosed_name().and_then(|name| place(&name)) else {
+            continue;
+        };
+
+        let mut out = extended.clone();
+        out.extend(rel.components());
+        let entry = f.name().to_owned();
+        if f.is_dir() {
+            writer.create_dir(&entry, &out)?;
+            continue;
+        }
+
+        let (symlink, mode) = (f.is_symlink(), f.unix_mode());
+        writer.write(&entry, &mut f, &out, symlink, mode)?;
+        written.push(plugins_dir.join(rel));
+    }
+    writer.finish()?;
+
+    if written.is_empty() {
+        return Err(ThermiteError::MissingFile(Box::new(PathBuf::from(
+            "plugins",
+        ))));
+    }
+
+    Ok(written)
+}
+
+/// Remove a plugin, as returned by `find_plugins`
+///
+/// # Errors
+/// * IO Errors, e.g. the plugin is loaded by a running game
+pub fn remove_plugin(plugin: &Plugin) -> Result<()> {
+    debug!("Removing plugin at {}", plugin.path.display());
+    fs::remove_file(&plugin.path)?;
+
+    Ok(())
+}
+
+/// Install N* to the provided path
+///
+/// Every extracted file is recorded in [`NORTHSTAR_RECORD`] along with its hash and the release version
+///
+/// # Params
+/// * `zip_file` - compressed mod file
+/// * `game_path` - the path of the Titanfall 2 install
+///
+/// # Errors
+/// * IO Errors
+/// * `ThermiteError::GameRunning` if the game is running
+/// * `ThermiteError::UnsupportedInstall` if the game is an Xbox app install under `WindowsApps`, or
+///   `game_path` is the Xbox app's folder for the game rather than its `Content` folder
+pub fn install_northstar(zip_file: impl Read + Seek, game_path: impl AsRef<Path>) -> Result<()> {
+    install_northstar_with_progress(zip_file, game_path, |_| ControlFlow::Continue(()))
+}
+
+/// Same as `install_northstar`, but reports each file as it's extracted
+/// # Params
+/// * `cb` - Called with a `ProgressEvent::ExtractFile` for every entry in the archive, then `ProgressEvent::Finished`.
+///   Returning `ControlFlow::Break` stops the installation, leaving any files already extracted in place
+///
+/// # Errors
+/// * IO Errors
+/// * `ThermiteError::Cancelled` if the callback cancelled the installation
+pub fn install_northstar_with_progress<F>(
+    zip_file: impl Read + Seek,
+    game_path: impl AsRef<Path>,
+    mut cb: F,
+) -> Result<()>
+where
+    F: FnMut(ProgressEvent) -> ControlFlow<()>,
+{
+    let target = game_path.as_ref();
+    let record = extract_northstar(zip_file, target, |_| true, &mut cb)?;
+    write_northstar_record(target, &record)?;
+
+    let _ = cb(ProgressEvent::Finished);
+
+    Ok(())
+}
+
+/// Files that only the game client needs, relative to the game directory
+const CLIENT_ONLY: [&str; 2] = [
+    "R2Northstar/mods/Northstar.Client",
+    "R2Northstar/plugins/DiscordRPC.dll",
+];
+
+/// Install only the parts of N* needed to run a dedicated server
+///
+/// `Northstar.Client`, client plugins like Discord rich presence, and any audio overrides are skipped.
+///
+/// # Params
+/// * `zip_file` - compressed Northstar release
+/// * `game_path` - the path of the Titanfall 2 install
+///
+/// # Errors
+/// * IO Errors
+/// * Misformatted archive
+pub fn install_northstar_dedicated(
+    zip_file: impl Read + Seek,
+    game_path: impl AsRef<Path>,
+) -> Result<()> {
+    let target = game_path.as_ref();
+    let record = extract_northstar(zip_file, target, |path| !is_client_only(path), &mut |_| {
+        ControlFlow::Continue(())
+    })?;
+    write_northstar_record(target, &record)
+}
+
+fn is_client_only(path: &Path) -> bool {
+    CLIENT_ONLY.iter().any(|p| path.starts_with(p))
+        || (path.starts_with("R2Northstar/mods") && path.iter().any(|c| c == "audio"))
+}
+
+/// Check that Titanfall 2 or a dedicated server isn't running from `game_path`, so installing N* won't
+/// fail halfway through
+///
+/// Running executables are only locked on Windows, so on other platforms this checks for a game
+/// process whose executable, working directory or `argv[0]` is inside `game_path` instead. Games
+/// running from other installs, e.g. a dedicated server, don't count
+///
+/// # Errors
+/// * `ThermiteError::GameRunning` if `NorthstarLauncher.exe` or `Northstar.dll` are in use, or a
+///   game process is running from `game_path`
+pub fn check_game_running(game_path: impl AsRef<Path>) -> Result<()> {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        const ERROR_SHARING_VIOLATION: i32 = 32;
+
+        for file in ["NorthstarLauncher.exe", "Northstar.dll"] {
+            let path = game_path.as_ref().join(file);
+            // opening without sharing fails if any other process has the file open
+            match OpenOptions::new().read(true).share_mode(0).open(&path) {
+                Err(e) if e.raw_os_error() == Some(ERROR_SHARING_VIOLATION) => {
+                    return Err(ThermiteError::GameRunning(path));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[cfg(not(windows))]
+    if let Some(exe) = crate::core::utils::running_game_process_in(game_path.as_ref()) {
+        return Err(ThermiteError::GameRunning(game_path.as_ref().join(exe)));
+    }
+
+    Ok(())
+}
+
+/// Extract every file in a N* release accepted by `filter`, which is passed paths relative to the game directory
+///
+/// Returns a record of the extracted files, which the caller is responsible for writing
+fn extract_northstar<F>(
+    mut zip_file: impl Read + Seek,
+    target: &Path,
+    filter: impl Fn(&Path) -> bool,
+    cb: &mut F,
+) -> Result<NorthstarFiles>
+where
+    F: FnMut(ProgressEvent) -> ControlFlow<()>,
+{
+    check_game_running(target)?;
+    match xbox_install(target) {
+        // the game's files are in `Content`, installing next to it wouldn't be loaded
+        Some(XboxInstall::XboxGames { content }) if content == target => {}
+        Some(_) => return Err(ThermiteError::UnsupportedInstall(target.to_path_buf())),
+        None => {}
+    }
+    check_write_access(target)?;
+    check_zip_magic(&mut zip_file)?;
+    let mut archive = ZipArchive::new(zip_file)?;
+    validate_archive(&mut archive)?;
+    let extended = extended_path(target);
+    let mut writer = EntryWriter::new(&extended)?;
+
+    let manifest = archive
+        .by_name("manifest.json")
+        .ok()
+        .map(|mut v| {
+            let mut buf = Vec::with_capacity(usize::try_from(v.size())?);
+            if let Err(e) = v.read_to_end(&mut buf) {
+                Err(ThermiteError::from(e))
+            } else {
+                Ok(buf)
+            }
+        })
+        .transpose()?;
+
+    let root = northstar_root(&archive);
+    let place = |name: &Path| {
+        name.strip_prefix(root)
+            .ok()
+            .filter(|rel| filter(rel))
+            .map(Path::to_path_buf)
+    };
+    // the Northstar/ folder is stripped, so links have to be checked against where they end up
+    validate_links(&mut archive, place)?;
+
+    let of = archive.len();
+    let mut record = NorthstarFiles::default();
+    for i in 0..of {
+        let mut f = archive.by_index(i)?;
+
+        let event = ProgressEvent::ExtractFile {
+            name: f.name().into(),
+            i,
+            of,
+            bytes: f.size(),
+        };
+        if cb(event).is_break() {
+            debug!("Northstar installation to {} cancelled", target.display());
+            return Err(ThermiteError::Cancelled);
+        }
+
+        let name = f
+            .enclosed_name()
+            .ok_or_else(|| ThermiteError::UnknownError("File missing enclosed name".into()))?;
+        if let Some(relative) = place(&name) {
+            let mut out = extended.clone();
+            out.extend(relative.components());
+            let entry = f.name().to_owned();
+
+            if f.is_dir() {
+                writer.create_dir(&entry, &out)?;
+                continue;
+            }
+
+            let (symlink, mode) = (f.is_symlink(), f.unix_mode());
+            let mut reader = HashReader {
+                inner: &mut f,
+                hasher: Sha256::new(),
+            };
+            writer.write(&entry, &mut reader, &out, symlink, mode)?;
+            record.files.push(NorthstarFile {
+                path: record_path(&relative),
+                sha256: to_hex(&reader.hasher.finalize()),
+            });
+        }
+    }
+
+    writer.finish()?;
+
+    // add manifest and author file
+    for child in target.join("R2Northstar").join("mods").read_dir()? {
+        let Ok(child) = child else {
+            continue;
+        };
+        if ![
+            OsString::from("Northstar.Client"),
+            OsString::from("Northstar.Custom"),
+            OsString::from("Northstar.CustomServers"),
+        ]
+        .contains(&child.file_name())
+        {
+            continue;
+        }
+
+        if child.file_type()?.is_dir() {
+            let dir = child.path();
+            let manifest_file = dir.join("manifest.json");
+            let author_file = dir.join(AUTHOR_FILE);
+
+            // write the manifest to the mod's directory
+            {
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .truncate(true)
+                    .open(manifest_file)?;
+                if let Some(manifest) = &manifest {
+                    file.write_all(manifest)?;
+                }
+            }
+
+            // write the author file to the mod's directory
+            {
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .truncate(true)
+                    .open(author_file)?;
+                file.write_all(b"northstar")?;
+            }
+        }
+    }
+
+    record.version = northstar_version(manifest.as_deref(), target);
+
+    Ok(record)
+}
+
+fn write_northstar_record(game_path: &Path, record: &NorthstarFiles) -> Result<()> {
+    write_atomic(
+        game_path.join(NORTHSTAR_RECORD),
+        serde_json::to_vec_pretty(record)?,
+    )
+}
+
+/// Download a N* release from GitHub and install it to the provided path
+///
+/// # Params
+/// * `release` - a release returned by `api::northstar_releases`
+/// * `game_path` - the path of the Titanfall 2 install
+///
+/// # Errors
+/// * The release doesn't include a Northstar zip
+/// * Network errors
+/// * IO Errors
+pub fn install_northstar_release(
+    release: &NorthstarRelease,
+    game_path: impl AsRef<Path>,
+) -> Result<()> {
+    let asset = release.zip().ok_or_else(|| {
+        ThermiteError::UnknownError(format!(
+            "Release {} doesn't include a Northstar zip",
+            release.tag_name
+        ))
+    })?;
+
+    let mut zipped = Vec::with_capacity(usize::try_from(asset.size)?);
+    download(&mut zipped, &asset.browser_download_url)?;
+    install_northstar(io::Cursor::new(zipped), game_path)
+}
+
+/// Install a specific version of N*, e.g. to downgrade after a broken release
+///
+/// The version is looked up on Thunderstore first, then in the GitHub releases so release candidates
+/// can be installed too.
+///
+/// # Params
+/// * `version` - the version to install, with or without a leading `v`
+/// * `game_path` - the path of the Titanfall 2 install
+///
+/// # Errors
+/// * The version doesn't exist
+/// * Network errors
+/// * IO Errors
+pub fn install_northstar_version(
+    version: impl AsRef<str>,
+    game_path: impl AsRef<Path>,
+) -> Result<()> {
+    let version = version.as_ref().trim_start_matches('v');
+    match api::get_package("northstar", "Northstar") {
+        Ok(package) => {
+            if let Some(found) = package.get_version(version) {
+                debug!("Installing Northstar {version} from Thunderstore");
+                let mut zipped = Vec::with_capacity(usize::try_from(found.file_size)?);
+                download(&mut zipped, &found.url)?;
+                return install_northstar(io::Cursor::new(zipped), game_path);
+            }
+        }
+        Err(e) => warn!("Unable to get Northstar from Thunderstore: {e}"),
+    }
+
+    let releases = api::northstar_releases()?;
+    let release = find_release(&releases, version).ok_or_else(|| {
+        ThermiteError::UnknownError(format!("Northstar version {version} not found"))
+    })?;
+    debug!("Installing Northstar {version} from GitHub");
+    install_northstar_release(release, game_path)
+}
+
+/// A release that can be downloaded from a `NorthstarChannel`
+struct ChannelRelease {
+    version: String,
+    url: String,
+    size: u64,
+}
+
+fn latest_release(channel: NorthstarChannel) -> Result<ChannelRelease> {
+    let release = match channel {
+        NorthstarChannel::Thunderstore => {
+            let package = api::get_package("northstar", "Northstar")?;
+            let latest = package.get_latest().ok_or_else(|| {
+                ThermiteError::UnknownError("Northstar has no versions on Thunderstore".into())
+            })?;
+            return Ok(ChannelRelease {
+                version: latest.version.to_string(),
+                url: latest.url.clone(),
+                size: latest.file_size,
+            });
+        }
+        NorthstarChannel::GitHub => api::northstar_releases()?
+            .into_iter()
+            .find(|r| !r.prerelease && r.zip().is_some()),
+        NorthstarChannel::GitHubPrerelease => api::northstar_releases()?
+            .into_iter()
+            .find(|r| r.zip().is_some()),
+    };
+
+    release
+        .and_then(|r| {
+            let asset = r.zip()?;
+            Some(ChannelRelease {
+                version: r.version().to_owned(),
+                url: asset.browser_download_url.clone(),
+                size: asset.size,
+            })
+        })
+        .ok_or_else(|| ThermiteError::UnknownError("No Northstar releases on GitHub".into()))
+}
+
+/// Get the latest version of N* available from a channel
+///
+/// # Errors
+/// * Network errors
+/// * The channel has no releases
+pub fn latest_northstar_version(channel: NorthstarChannel) -> Result<String> {
+    Ok(latest_release(channel)?.version)
+}
+
+/// The version of N* installed to `game_path`, read from [`NORTHSTAR_RECORD`] or the client's `mod.json`
+///
+/// # Errors
+/// * IO Errors
+/// * The record is malformed
+pub fn installed_northstar_version(game_path: impl AsRef<Path>) -> Result<Option<String>> {
+    let game_path = game_path.as_ref();
+    Ok(read_northstar_record(game_path)?
+        .and_then(|r| r.version)
+        .or_else(|| northstar_version(None, game_path)))
+}
+
+/// Check if the latest version in a channel differs from the installed version
+///
+/// Any difference counts as an update, so switching from release candidates back to stable offers
+/// the latest stable release even if it's older.
+///
+/// # Returns
+/// * the version to update to, or `None` if the install is up to date
+///
+/// # Errors
+/// * Network errors
+/// * IO Errors
+pub fn check_northstar_update(
+    game_path: impl AsRef<Path>,
+    channel: NorthstarChannel,
+) -> Result<Option<String>> {
+    let latest = latest_northstar_version(channel)?;
+    let installed = installed_northstar_version(game_path)?;
+
+    Ok((installed.as_deref() != Some(latest.as_str())).then_some(latest))
+}
+
+/// Download the latest N* from a channel and install it with `update_northstar`, so switching channels
+/// doesn't leave files from the previous release behind
+///
+/// # Returns
+/// * the version that was installed
+///
+/// # Errors
+/// * Network errors
+/// * IO Errors
+/// * Misformatted archive
+pub fn update_northstar_from(
+    channel: NorthstarChannel,
+    game_path: impl AsRef<Path>,
+) -> Result<String> {
+    let release = latest_release(channel)?;
+    debug!("Installing Northstar {} from {channel:?}", release.version);
+
+    let mut zipped = Vec::with_capacity(usize::try_from(release.size)?);
+    download(&mut zipped, &release.url)?;
+    update_northstar(io::Cursor::new(zipped), game_path)?;
+
+    Ok(release.version)
+}
+
+fn find_release<'a>(
+    releases: &'a [NorthstarRelease],
+    version: &str,
+) -> Option<&'a NorthstarRelease> {
+    let version = version.trim_start_matches('v');
+    releases.iter().find(|r| r.version() == version)
+}
+
+/// The directory N* is packaged in, `Northstar` for Thunderstore releases or the root of
+/// the archive for GitHub releases
+fn northstar_root<R: Read + Seek>(archive: &ZipArchive<R>) -> &'static Path {
+    let root = Path::new("Northstar");
+    if archive
+        .file_names()
+        .any(|name| Path::new(name).starts_with(root))
+    {
+        root
+    } else {
+        Path::new("")
+    }
+}
+
+/// Update an existing N* install, deleting any files from the previous release that the new one
+/// no longer ships
+///
+/// The previous release's files are read from [`NORTHSTAR_RECORD`]. If the game was set up without
+/// thermite and there is no record, only the core mods are pruned.
+/// Third-party mods in `R2Northstar/mods` and `.cfg` files are never deleted.
+///
+/// # Params
+/// * `zip_file` - compressed Northstar release
+/// * `game_path` - the path of the Titanfall 2 install
+///
+/// # Returns
+/// * every file that was deleted
+///
+/// # Errors
+/// * IO Errors
+/// * Misformatted archive
+pub fn update_northstar(
+    zip_file: impl Read + Seek,
+    game_path: impl AsRef<Path>,
+) -> Result<Vec<PathBuf>> {
+    let target = game_path.as_ref();
+    let previous = if let Some(record) = read_northstar_record(target)? {
+        record.files.into_iter().map(|f| f.path).collect()
+    } else {
+        warn!(
+            "No record of the files installed to {}, only pruning the core mods",
+            target.display()
+        );
+        core_mod_files(target)?
+    };
+
+    install_northstar(zip_file, target)?;
+    let current = read_northstar_record(target)?
+        .map(|r| r.files.into_iter().map(|f| f.path).collect::<HashSet<_>>())
+        .unwrap_or_default();
+
+    let mut removed = vec![];
+    for file in previous {
+        if current.contains(&file) || !is_northstar_owned(target, &file) {
+            continue;
+        }
+
+        let path = target.join(&file);
+        if !path.symlink_metadata().is_ok_and(|m| !m.is_dir()) {
+            continue;
+        }
+
+        trace!("Remove stale file {}", path.display());
+        fs::remove_file(&path)?;
+        // clean up directories left empty, stopping at the first one that isn't
+        for dir in path.ancestors().skip(1) {
+            if dir == target || fs::remove_dir(dir).is_err() {
+                break;
+            }
+        }
+        removed.push(path);
+    }
+
+    Ok(removed)
+}
+
+/// The release version from the archive's manifest, or the client's `mod.json` if it doesn't have one
+fn northstar_version(manifest: Option<&[u8]>, game_path: &Path) -> Option<String> {
+    if let Some(manifest) = manifest.and_then(|m| serde_json::from_slice::<Manifest>(m).ok()) {
+        return Some(manifest.version_number);
+    }
+
+    let mod_json = fs::read_to_string(
+        game_path
+            .join("R2Northstar")
+            .join("mods")
+            .join("Northstar.Client")
+            .join("mod.json"),
+    )
+    .ok()?;
+    json5::from_str::<ModJSON>(&mod_json)
+        .ok()
+        .map(|m| m.version)
+}
+
+/// Same as `install_northstar`, but backs up the current install with `backup_northstar` first
+///
+/// # Returns
+/// * the backup, or `None` if N* wasn't installed
+///
+/// # Errors
+/// * IO Errors
+/// * Misformatted archive
+pub fn install_northstar_with_backup(
+    zip_file: impl Read + Seek,
+    game_path: impl AsRef<Path>,
+    backup_dir: impl AsRef<Path>,
+) -> Result<Option<PathBuf>> {
+    let backup = backup_northstar(&game_path, backup_dir)?;
+    install_northstar(zip_file, game_path)?;
+
+    Ok(backup)
+}
+
+/// Same as `update_northstar`, but backs up the current install with `backup_northstar` first
+///
+/// # Returns
+/// * the backup, or `None` if N* wasn't installed
+/// * every file that was deleted
+///
+/// # Errors
+/// * IO Errors
+/// * Misformatted archive
+pub fn update_northstar_with_backup(
+    zip_file: impl Read + Seek,
+    game_path: impl AsRef<Path>,
+    backup_dir: impl AsRef<Path>,
+) -> Result<(Option<PathBuf>, Vec<PathBuf>)> {
+    let backup = backup_northstar(&game_path, backup_dir)?;
+    let removed = update_northstar(zip_file, game_path)?;
+
+    Ok((backup, removed))
+}
+
+/// Archive the `Northstar*` files in the game directory and the core mods to `northstar-<timestamp>.zip`,
+/// so a bad update can be rolled back with `restore_backup`
+///
+/// # Params
+/// * `game_path` - the path of the Titanfall 2 install
+/// * `backup_dir` - directory to write the backup to. It will be created if needed
+///
+/// # Returns
+/// * the backup, or `None` if N* isn't installed
+///
+/// # Errors
+/// * IO Errors
+pub fn backup_northstar(
+    game_path: impl AsRef<Path>,
+    backup_dir: impl AsRef<Path>,
+) -> Result<Option<PathBuf>> {
+    let target = game_path.as_ref();
+    let mut files = if target.join("R2Northstar").join("mods").is_dir() {
+        core_mod_files(target)?
+    } else {
+        vec![]
+    };
+    for child in target.read_dir()? {
+        let child = child?;
+        let name = child.file_name().to_string_lossy().into_owned();
+        if name.starts_with("Northstar") && child.file_type()?.is_file() {
+            files.push(name);
+        }
+    }
+
+    if files.is_empty() {
+        debug!("No Northstar install to back up in {}", target.display());
+        return Ok(None);
+    }
+    if target.join(NORTHSTAR_RECORD).is_file() {
+        files.push(NORTHSTAR_RECORD.into());
+    }
+
+    let backup_dir = backup_dir.as_ref();
+    fs::create_dir_all(backup_dir)?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    let path = backup_dir.join(format!("northstar-{timestamp}.zip"));
+    debug!("Backing up {} files to {}", files.len(), path.display());
+
+    let mut writer = ZipWriter::new(File::create(&path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for file in &files {
+        writer.start_file(file.as_str(), options)?;
+        io::copy(&mut File::open(target.join(file))?, &mut writer)?;
+    }
+    writer.finish()?;
+
+    Ok(Some(path))
+}
+
+/// Restore a backup made by `backup_northstar`
+///
+/// The core mods are removed before extracting the backup, so no files from the newer release are left behind
+///
+/// # Errors
+/// * IO Errors
+/// * Misformatted archive
+pub fn restore_backup(backup: impl AsRef<Path>, game_path: impl AsRef<Path>) -> Result<()> {
+    let target = game_path.as_ref();
+    check_game_running(target)?;
+    let mut file = File::open(backup.as_ref())?;
+    check_zip_magic(&mut file)?;
+    let mut archive = ZipArchive::new(file)?;
+    validate_archive(&mut archive)?;
+
+    let mods = target.join("R2Northstar").join("mods");
+    if mods.is_dir() {
+        for child in mods.read_dir()? {
+            let child = child?;
+            let name = child.file_name().to_string_lossy().into_owned();
+            if child.file_type()?.is_dir() && is_core_dir(&child.path(), &name) {
+                trace!("Remove {}", child.path().display());
+                fs::remove_dir_all(child.path())?;
+            }
+        }
+    }
+
+    debug!(
+        "Restoring {} to {}",
+        backup.as_ref().display(),
+        target.display()
+    );
+    extract_with_progress(&mut archive, target, &mut |_| ControlFlow::Continue(()))
+}
+
+/// Read the record of the files installed by `install_northstar`
+///
+/// # Returns
+/// * the record, or `None` if N* wasn't installed by thermite
+///
+/// # Errors
+/// * IO Errors
+/// * The record is malformed
+pub fn read_northstar_record(game_path: impl AsRef<Path>) -> Result<Option<NorthstarFiles>> {
+    let path = game_path.as_ref().join(NORTHSTAR_RECORD);
+    if !path.try_exists()? {
+        return Ok(None);
+    }
+
+    Ok(Some(serde_json::from_slice(&fs::read(path)?)?))
+}
+
+/// Files that differ between a N* install and what was expected
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NorthstarReport {
+    pub missing: Vec<PathBuf>,
+    /// Files whose hash doesn't match
+    pub modified: Vec<PathBuf>,
+    /// Files in the core mods that aren't part of the release
+    pub extra: Vec<PathBuf>,
+}
+
+impl NorthstarReport {
+    /// `true` if the install matched exactly
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.modified.is_empty() && self.extra.is_empty()
+    }
+}
+
+/// List every file in a N* release along with its hash, in the same format as [`NORTHSTAR_RECORD`]
+///
+/// # Errors
+/// * IO Errors
+/// * Misformatted archive
+pub fn northstar_files(mut zip_file: impl Read + Seek) -> Result<NorthstarFiles> {
+    check_zip_magic(&mut zip_file)?;
+    let mut archive = ZipArchive::new(zip_file)?;
+    let root = northstar_root(&archive);
+
+    let manifest = read_manifest(&mut archive)?;
+    let client = root.join("R2Northstar/mods/Northstar.Client/mod.json");
+    let mut record = NorthstarFiles {
+        version: manifest.map(|m| m.version_number),
+        files: vec![],
+    };
+    for i in 0..archive.len() {
+        let mut f = archive.by_index(i)?;
+        let Some(relative) = f
+            .enclosed_name()
+            .and_then(|n| n.strip_prefix(root).ok().map(Path::to_path_buf))
+        else {
+            continue;
+        };
+        if f.is_dir() {
+            continue;
+        }
+
+        let mut hasher = Sha256::new();
+        if record.version.is_none() && root.join(&relative) == client {
+            let mut buf = String::new();
+            f.read_to_string(&mut buf)?;
+            hasher.update(buf.as_bytes());
+            record.version = json5::from_str::<ModJSON>(&buf).ok().map(|m| m.version);
+        } else {
+            io::copy(&mut f, &mut hasher)?;
+        }
+
+        record.files.push(NorthstarFile {
+            path: record_path(&relative),
+            sha256: to_hex(&hasher.finalize()),
+        });
+    }
+
+    Ok(record)
+}
+
+/// Check an existing N* install against the files that should be installed
+///
+/// # Params
+/// * `game_path` - the path of the Titanfall 2 install
+/// * `expected` - the files that should be installed, from `read_northstar_record` or `northstar_files`
+///
+/// # Errors
+/// * IO Errors
+pub fn verify_northstar(
+    game_path: impl AsRef<Path>,
+    expected: &NorthstarFiles,
+) -> Result<NorthstarReport> {
+    let target = game_path.as_ref();
+    let mut report = NorthstarReport::default();
+    for file in &expected.files {
+        let path = target.join(&file.path);
+        if !path.try_exists()? {
+            report.missing.push(path);
+        } else if !sha256_file(&path)?.eq_ignore_ascii_case(&file.sha256) {
+            report.modified.push(path);
+        }
+    }
+
+    if target.join("R2Northstar").join("mods").is_dir() {
+        let known = expected
+            .files
+            .iter()
+            .map(|f| f.path.as_str())
+            .collect::<HashSet<_>>();
+        report.extra = core_mod_files(target)?
+            .into_iter()
+            .filter(|f| !known.contains(f.as_str()) && is_northstar_owned(target, f))
+            .map(|f| target.join(f))
+            .collect();
+    }
+
+    Ok(report)
+}
+
+/// Re-extract any files that are missing or modified, leaving the rest of the install untouched
+///
+/// Extra files are only reported, use `update_northstar` to remove them.
+///
+/// # Params
+/// * `zip_file` - compressed Northstar release
+/// * `game_path` - the path of the Titanfall 2 install
+///
+/// # Returns
+/// * the state of the install before it was repaired
+///
+/// # Errors
+/// * IO Errors
+/// * Misformatted archive
+pub fn repair_northstar(
+    mut zip_file: impl Read + Seek,
+    game_path: impl AsRef<Path>,
+) -> Result<NorthstarReport> {
+    let target = game_path.as_ref();
+    let expected = northstar_files(&mut zip_file)?;
+    let report = verify_northstar(target, &expected)?;
+
+    let broken = report
+        .missing
+        .iter()
+        .chain(&report.modified)
+        .filter_map(|p| p.strip_prefix(target).ok())
+        .map(record_path)
+        .collect::<HashSet<_>>();
+    if !broken.is_empty() {
+        debug!("Repairing {} files in {}", broken.len(), target.display());
+        zip_file.rewind()?;
+        extract_northstar(
+            zip_file,
+            target,
+            |p| broken.contains(&record_path(p)),
+            &mut |_| ControlFlow::Continue(()),
+        )?;
+        write_northstar_record(target, &expected)?;
+    }
+
+    Ok(report)
+}
+
+/// Every file in the core mod folders, for installs without a record
+fn core_mod_files(game_path: &Path) -> Result<Vec<String>> {
+    let mods = game_path.join("R2Northstar").join("mods");
+    let mut files = vec![];
+    let mut dirs = vec![];
+    for child in mods.read_dir()? {
+        let child = child?;
+        let name = child.file_name().to_string_lossy().into_owned();
+        if child.file_type()?.is_dir() && is_core_dir(&child.path(), &name) {
+            dirs.push(child.path());
+        }
+    }
+
+    while let Some(dir) = dirs.pop() {
+        for child in dir.read_dir()? {
+            let child = child?;
+            if child.file_type()?.is_dir() {
+                dirs.push(child.path());
+            } else {
+                let path = child.path();
+                files.push(record_path(
+                    path.strip_prefix(game_path).expect("file in game path"),
+                ));
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// Whether a file from a previous release is safe for `update_northstar` to delete
+fn is_northstar_owned(game_path: &Path, path: &str) -> bool {
+    let path = Path::new(path);
+    if path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("cfg"))
+    {
+        return false;
+    }
+
+    let Ok(rel) = path.strip_prefix("R2Northstar/mods") else {
+        return true;
+    };
+    let mut components = rel.iter();
+    match (components.next(), components.next(), components.next()) {
+        // loose files like the README
+        (Some(_), None, _) => true,
+        // files written by `install_northstar` itself
+        (Some(_), Some(name), None) if name == "manifest.json" || name == AUTHOR_FILE => false,
+        (Some(folder), Some(_), _) => is_core_dir(
+            &game_path.join("R2Northstar").join("mods").join(folder),
+            &folder.to_string_lossy(),
+        ),
+        _ => false,
+    }
+}
+
+/// Format a relative path for the N* record, so it's the same on every platform
+fn record_path(path: &Path) -> String {
+    path.iter()
+        .map(|c| c.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Install N* into a profile directory, e.g. `R2Northstar` or any other profile passed with `-profile`
+///
+/// Only `Northstar.dll` and the contents of `R2Northstar/mods` and `R2Northstar/plugins` are extracted, so the
+/// launcher and the rest of the game directory are left untouched.
+///
+/// # Params
+/// * `zip_file` - compressed Northstar release
+/// * `dest` - the profile directory
+///
+/// # Returns
+/// * every file that was written
+///
+/// # Errors
+/// * IO Errors
+/// * Misformatted archive
+pub fn install_northstar_profile(
+    mut zip_file: impl Read + Seek,
+    dest: impl AsRef<Path>,
+) -> Result<Vec<PathBuf>> {
+    let dest = dest.as_ref();
+    check_zip_magic(&mut zip_file)?;
+    let mut archive = ZipArchive::new(zip_file)?;
+    validate_archive(&mut archive)?;
+
+    let root = northstar_root(&archive);
+    let profile = root.join("R2Northstar");
+    let place = |name: &Path| {
+        if name == root.join("Northstar.dll") {
+            Some(PathBuf::from("Northstar.dll"))
+        } else {
+            name.strip_prefix(&profile)
+                .ok()
+                .filter(|rel| rel.starts_with("mods") || rel.starts_with("plugins"))
+                .map(Path::to_path_buf)
+        }
+    };
+    // R2Northstar/ is stripped, so links have to be checked against where they end up
+    validate_links(&mut archive, place)?;
+
+    let extended = extended_path(dest);
+    let mut writer = EntryWriter::new(&extended)?;
+    let mut written = vec![];
+    for i in 0..archive.len() {
+        let mut f = archive.by_index(i)?;
+        let Some(relative) = f.enclosed_name().and_then(|name| place(&name)) else {
+            continue;
+        };
+
+        let mut out = extended.clone();
+        out.extend(relative.components());
+        let entry = f.name().to_owned();
+        if f.is_dir() {
+            writer.create_dir(&entry, &out)?;
+            continue;
+        }
+
+        let (symlink, mode) = (f.is_symlink(), f.unix_mode());
+        writer.write(&entry, &mut f, &out, symlink, mode)?;
+        written.push(dest.join(relative));
+    }
+    writer.finish()?;
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::core::utils::TempDir;
+    use mockall::mock;
+    use std::io::{BufRead, BufReader, Cursor};
+    use std::net::{TcpListener, TcpStream};
+    use tracing::info;
+
+    use super::{install_mod, *};
+
+    mock! {
+        Writer {}
+        impl Write for Writer {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize>;
+            fn write_all(&mut self, buf: &[u8]) -> io::Result<()>;
+            fn flush(&mut self) -> io::Result<()>;
+        }
+
+    }
+
+    mock! {
+        Archive {}
+        impl Read for Archive {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+        }
+
+        impl Seek for Archive {
+            fn seek(&mut self, pos: std::io::SeekFrom) -> io::Result<u64>;
+        }
+    }
+
+    const TEST_URL: &str =
+        "https://freetestdata.com/wp-content/uploads/2023/04/2.4KB_JSON-File_FreeTestData.json";
+    const TEST_SIZE_BYTES: u64 = 2455;
+    const TEST_BODY: [u8; TEST_SIZE_BYTES as usize] = {
+        let mut body = [0; TEST_SIZE_BYTES as usize];
+        let mut i = 0;
+        while i < body.len() {
+            body[i] = (i % 251) as u8;
+            i += 1;
+        }
+        body
+    };
+
+    /// Serve [`TEST_BODY`] on localhost, with support for ranged requests, returning its url
+    fn serve_test_body() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind test server");
+        let url = format!(
+            "http://{}/test.bin",
+            listener.local_addr().expect("test server address")
+        );
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                std::thread::spawn(move || respond(stream));
+            }
+        });
+
+        url
+    }
+
+    /// Serve an HTML error page with a success status on localhost, like a mirror that's down for
+    /// maintenance, returning its url
+    fn serve_html_page() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind test server");
+        let url = format!(
+            "http://{}/test.bin",
+            listener.local_addr().expect("test server address")
+        );
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let page = b"<html><body>Down for maintenance</body></html>";
+                let mut reader = BufReader::new(stream.try_clone().expect("clone stream"));
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                    line.clear();
+                }
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    page.len()
+                )
+                .and_then(|()| stream.write_all(page));
+            }
+        });
+
+        url
+    }
+
+    fn respond(mut stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        // ureq reuses connections, so keep answering until it hangs up
+        loop {
+            let mut request = String::new();
+            if reader.read_line(&mut request)? == 0 {
+                return Ok(());
+            }
+            let mut range = None;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header)?;
+                let header = header.trim_end();
+                if header.is_empty() {
+                    break;
+                }
+                if let Some((start, end)) = header
+                    .strip_prefix("Range: bytes=")
+                    .and_then(|r| r.split_once('-'))
+                {
+                    range = start.parse::<usize>().ok().zip(end.parse::<usize>().ok());
+                }
+            }
+
+            let (status, body, content_range) = match range {
+                Some((start, _)) if start >= TEST_BODY.len() => (
+                    "416 Range Not Satisfiable",
+                    &[][..],
+                    format!("Content-Range: bytes */{TEST_SIZE_BYTES}\r\n"),
+                ),
+                Some((start, end)) if start <= end => {
+                    let end = end.min(TEST_BODY.len() - 1);
+                    (
+                        "206 Partial Content",
+                        &TEST_BODY[start..=end],
+                        format!("Content-Range: bytes {start}-{end}/{TEST_SIZE_BYTES}\r\n"),
+                    )
+                }
+                // a backwards range is invalid, so the header is ignored
+                _ => ("200 OK", &TEST_BODY[..], String::new()),
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\nAccept-Ranges: bytes\r\n{content_range}\r\n",
+                body.len()
+            )?;
+            if !request.starts_with("HEAD ") {
+                stream.write_all(body)?;
+            }
+        }
+    }
+
+    const TEST_ARCHIVE: &[u8] = include_bytes!("test_media/test_archive.zip");
+    const MANIFEST: &str = r#"{
+        "name": "bar",
+        "version_number": "1.0.0",
+        "website_url": "",
+        "description": "",
+        "dependencies": []
+    }"#;
+    const TEST_NS_ARCHIVE: &[u8] = include_bytes!("test_media/northstar.zip");
+
+    #[test]
+    fn download_file() {
+        let mut mock_writer = MockWriter::new();
+        mock_writer
+            .expect_write_all()
+            .returning(|_| Ok(()))
+            .times((TEST_SIZE_BYTES as usize / super::CHUNK_SIZE)..);
+
+        let res = download(mock_writer, TEST_URL);
+        assert!(res.is_ok());
+        res.map(|size| {
+            assert_eq!(size, TEST_SIZE_BYTES);
+            size
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_server_ranges() {
+        let url = serve_test_body();
+        let res = http::agent()
+            .get(&url)
+            .set("Range", "bytes=2400-9999")
+            .call()
+            .expect("clamped range");
+        assert_eq!(res.status(), 206);
+        assert_eq!(res.header("Content-Length"), Some("55"));
+
+        let res = http::agent()
+            .get(&url)
+            .set("Range", "bytes=9999-10000")
+            .call();
+        assert!(matches!(res, Err(ureq::Error::Status(416, _))));
+    }
+
+    #[test]
+    fn download_from_fallback_mirror() {
+        let mut output = Cursor::new(vec![]);
+        let res = download_from_mirrors(
+            &mut output,
+            &["http://your_mother:8008", &serve_test_body()],
+            |_, _, _| {},
+        );
+
+        let (size, mirror) = res.expect("download from mirror");
+        assert_eq!(size, TEST_SIZE_BYTES);
+        assert_eq!(mirror, 1);
+    }
+
+    #[test]
+    fn skip_mirror_serving_html() {
+        let mut output = Cursor::new(vec![]);
+        let res = download_from_mirrors(
+            &mut output,
+            &[serve_html_page(), serve_test_body()],
+            |_, _, _| {},
+        );
+
+        let (size, mirror) = res.expect("download from mirror");
+        assert_eq!(size, TEST_SIZE_BYTES);
+        assert_eq!(mirror, 1);
+        assert_eq!(output.into_inner(), TEST_BODY);
+
+        let res = download_from_mirrors(Cursor::new(vec![]), &[serve_html_page()], |_, _, _| {});
+        assert!(matches!(res, Err(ThermiteError::InvalidArchive { .. })));
+    }
+
+    #[test]
+    fn fail_all_mirrors() {
+        let res = download_from_mirrors(
+            Cursor::new(vec![]),
+            &["http://your_mother:8008"],
+            |_, _, _| {},
+        );
+        assert!(matches!(res, Err(ThermiteError::NetworkError(_))));
+
+        let res = download_from_mirrors(Cursor::new(vec![]), &[] as &[&str], |_, _, _| {});
+        assert!(matches!(res, Err(ThermiteError::UnknownError(_))));
+    }
+
+    #[test]
+    fn download_to_file() {
+        let dir = TempDir::create("./download_to_file").expect("Unable to create temp dir");
+        let dest = dir.join("test.json");
+        let file = super::download_file(serve_test_body(), &dest).expect("download file");
+
+        assert_eq!(
+            file.metadata().expect("file metadata").len(),
+            TEST_SIZE_BYTES
+        );
+        assert!(!part_path(&dest).exists());
+    }
+
+    #[test]
+    fn fail_download_to_file() {
+        let dir = TempDir::create("./fail_download_to_file").expect("Unable to create temp dir");
+        let dest = dir.join("test.json");
+        let res = super::download_file("http://your_mother:8008", &dest);
+
+        assert!(matches!(res, Err(ThermiteError::NetworkError(_))));
+        assert!(!dest.exists());
+        assert!(!part_path(&dest).exists());
+    }
+
+    #[test]
+    fn hash_while_writing() {
+        let mut writer = HashWriter {
+            inner: vec![],
+            hasher: Sha256::new(),
+        };
+        writer.write_all(b"hello world").expect("write to vec");
+
+        assert_eq!(writer.inner, b"hello world");
+        assert!(check_sha256(
+            &writer.hasher.finalize(),
+            "B94D27B9934D3E08A52E52D7DA7DABFAC484EFE37A5380EE9088F7ACE2EFCDE9"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn fail_checksum_mismatch() {
+        let res = check_sha256(&Sha256::digest(b"hello world"), "abcd");
+
+        assert!(matches!(
+            res,
+            Err(ThermiteError::ChecksumMismatch { expected, .. }) if expected == "abcd"
+        ));
+    }
+
+    #[test]
+    fn hash_file() {
+        let dir = TempDir::create("./hash_file").expect("Unable to create temp dir");
+        let path = dir.join("test.txt");
+        fs::write(&path, b"hello world").expect("write test file");
+
+        assert_eq!(
+            sha256_file(&path).expect("hash file"),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+    }
+
+    #[test]
+    fn cancel_download() {
+        let res = ureq::Response::new(200, "OK", "some data").expect("build response");
+        let mut output = vec![];
+        let mut calls = 0;
+        let res = read_body(
+            res,
+            &mut output,
+            "test",
+            &DownloadOptions::default(),
+            progress_events(|_, _, _| {
+                calls += 1;
+                ControlFlow::Break(())
+            }),
+        );
+
+        assert!(matches!(res, Err(ThermiteError::Cancelled)));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn split_download_ranges() {
+        assert_eq!(split_ranges(10, 3), vec![(0, 3), (4, 7), (8, 9)]);
+        assert_eq!(split_ranges(2, 4), vec![(0, 0), (1, 1)]);
+        assert_eq!(split_ranges(5, 0), vec![(0, 4)]);
+        assert!(split_ranges(0, 4).is_empty());
+    }
+
+    #[test]
+    fn download_file_segmented() {
+        let mut output = Cursor::new(vec![]);
+        let size = download_segmented(&mut output, serve_test_body(), 4, |_, _, _| {})
+            .expect("segmented download");
+
+        assert_eq!(size, TEST_SIZE_BYTES);
+        assert_eq!(output.into_inner(), TEST_BODY);
+    }
+
+    #[test]
+    fn throttle_download() {
+        let res = ureq::Response::new(200, "OK", &"a".repeat(4096)).expect("build response");
+        let options = DownloadOptions::new().max_bytes_per_sec(20_000);
+        let start = Instant::now();
+        let size = read_body(res, vec![], "test", &options, |_| ControlFlow::Continue(()))
+            .expect("read body");
+
+        assert_eq!(size, 4096);
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn report_download_events() {
+        let res: ureq::Response = "HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\nsome data"
+            .parse()
+            .expect("parse response");
+        let mut events = vec![];
+        read_body(res, vec![], "test", &DownloadOptions::default(), |e| {
+            events.push(e);
+            ControlFlow::Continue(())
+        })
+        .expect("read body");
+
+        assert_eq!(
+            events,
+            [
+                ProgressEvent::Started { total: 9 },
+                ProgressEvent::Chunk {
+                    delta: 9,
+                    downloaded: 9
+                },
+                ProgressEvent::Finished,
+            ]
+        );
+    }
+
+    #[test]
+    fn serialize_progress_event() {
+        let event = ProgressEvent::Chunk {
+            delta: 1024,
+            downloaded: 4096,
+        };
+
+        assert_eq!(
+            serde_json::to_string(&event).expect("serialize event"),
+            r#"{"event":"chunk","delta":1024,"downloaded":4096}"#
+        );
+    }
+
+    #[test]
+    fn read_full_chunks() {
+        let mut reader = MockArchive::new();
+        let mut reads = 0;
+        reader.expect_read().returning(move |buf| {
+            reads += 1;
+            match reads {
+                1 => Err(io::ErrorKind::Interrupted.into()),
+                2 | 3 => {
+                    buf[..2].copy_from_slice(b"ab");
+                    Ok(2)
+                }
+                _ => Ok(0),
+            }
+        });
+
+        let mut buf = [0; 8];
+        assert_eq!(read_chunk(&mut reader, &mut buf).expect("read chunk"), 4);
+        assert_eq!(&buf[..4], b"abab");
+    }
+
+    #[test]
+    fn fail_read_chunk() {
+        let mut reader = MockArchive::new();
+        reader
+            .expect_read()
+            .returning(|_| Err(io::ErrorKind::ConnectionReset.into()));
+
+        let res = read_chunk(&mut reader, &mut [0; 8]);
+        assert!(res.is_err_and(|e| e.kind() == io::ErrorKind::ConnectionReset));
+    }
+
+    fn queue_item(name: &str, url: &str) -> ModVersion {
+        ModVersion {
+            url: url.into(),
+            file_size: TEST_SIZE_BYTES,
+            ..ModVersion::test(&format!("test-{name}-0.1.0"), &[])
+        }
+    }
+
+    #[test]
+    fn download_queue() {
+        let dir = TempDir::create("./download_queue").expect("Unable to create temp dir");
+        let url = serve_test_body();
+        let items = ["a", "b", "c"].map(|name| queue_item(name, &url));
+        let results = DownloadQueue::new(items)
+            .concurrency(2)
+            .download_to(&dir, |p| assert_eq!(p.total, TEST_SIZE_BYTES * 3));
+
+        assert_eq!(results.len(), 3);
+        for res in results {
+            let path = res.result.expect("download item");
+            assert_eq!(path, dir.join(format!("{}.zip", res.version.full_name)));
+            assert!(path.exists());
+        }
+    }
+
+    #[test]
+    fn fail_download_queue_item() {
+        let dir = TempDir::create("./fail_download_queue").expect("Unable to create temp dir");
+        let items = vec![
+            queue_item("a", "http://your_mother:8008"),
+            queue_item("b", "http://your_mother:8008"),
+        ];
+        let results = DownloadQueue::new(items)
+            .concurrency(0)
+            .download_to(&dir, |_| {});
+
+        assert_eq!(results[0].version.name, "a");
+        assert_eq!(results[1].version.name, "b");
+        assert!(results
+            .iter()
+            .all(|r| matches!(r.result, Err(ThermiteError::NetworkError(_)))));
+    }
+
+    #[test]
+    fn map_read_timeout() {
+        assert!(matches!(
+            read_error(io::ErrorKind::TimedOut.into()),
+            ThermiteError::Timeout
+        ));
+        assert!(matches!(
+            read_error(io::ErrorKind::ConnectionReset.into()),
+            ThermiteError::IoError(_)
+        ));
+    }
+
+    #[test]
+    fn fail_html_response() {
+        let res: ureq::Response =
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\n\r\n<html></html>"
+                .parse()
+                .expect("parse response");
+        let res = read_body(res, vec![], "test", &DownloadOptions::default(), |_| {
+            ControlFlow::Continue(())
+        });
+
+        assert!(matches!(
+            res,
+            Err(ThermiteError::InvalidArchive { url: Some(url), content_type: Some(ct) })
+                if url == "test" && ct.starts_with("text/html")
+        ));
+    }
+
+    #[test]
+    fn fail_install_not_zip() {
+        let dir = TempDir::create("./fail_install_not_zip").expect("Unable to create temp dir");
+        let res = install_mod(
+            "foo-bar-0.1.0",
+            Cursor::new(b"<html>Not found</html>"),
+            &dir,
+        );
+        assert!(matches!(res, Err(ThermiteError::InvalidArchive { .. })));
+
+        let res = install_mod("foo-bar-0.1.0", Cursor::new(b"PK"), &dir);
+        assert!(matches!(res, Err(ThermiteError::InvalidArchive { .. })));
+    }
+
+    #[test]
+    fn install_reporting_progress() {
+        let dir = TempDir::create("./install_progress").expect("Unable to create temp dir");
+        let mut events = vec![];
+        let path = install_with_progress("foo-bar-0.1.0", Cursor::new(TEST_ARCHIVE), &dir, |e| {
+            events.push(e);
+            ControlFlow::Continue(())
+        })
+        .expect("install mod");
+
+        assert!(path
+            .join("mods")
+            .join("Smart CAR")
+            .join("mod.json")
+            .try_exists()
+            .expect("check mod.json"));
+
+        let of = ZipArchive::new(Cursor::new(TEST_ARCHIVE))
+            .expect("open archive")
+            .len();
+        assert_eq!(events.len(), of + 1);
+        assert!(matches!(
+            events.first(),
+            Some(ProgressEvent::ExtractFile { i: 0, of: n, .. }) if *n == of
+        ));
+        assert_eq!(events.last(), Some(&ProgressEvent::Finished));
+    }
+
+    #[test]
+    fn cancel_install() {
+        let dir = TempDir::create("./cancel_install").expect("Unable to create temp dir");
+        let res = install_with_progress("foo-bar-0.1.0", Cursor::new(TEST_ARCHIVE), &dir, |_| {
+            ControlFlow::Break(())
+        });
+
+        assert!(matches!(res, Err(ThermiteError::Cancelled)));
+    }
 
-    const TEST_ARCHIVE: &[u8] = include_bytes!("test_media/test_archive.zip");
-    const TEST_NS_ARCHIVE: &[u8] = include_bytes!("test_media/northstar.zip");
+    #[test]
+    fn uninstall_package() {
+        let dir = TempDir::create("./uninstall_package").expect("Unable to create temp dir");
+        install_mod("foo-bar-0.1.0", Cursor::new(TEST_ARCHIVE), &dir).expect("install mod");
+        let installed = crate::core::find_mods(&dir).expect("find mods");
+        let mut enabled = EnabledMods::default();
+        enabled.dont_save();
+        enabled.set("Smart CAR", false);
+        enabled.set("Other", true);
+
+        let removed =
+            uninstall_mod(&installed[0], &dir, Some(&mut enabled), false).expect("uninstall mod");
+
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].mod_json.name, "Smart CAR");
+        assert!(!dir.join("foo-bar-0.1.0").exists());
+        assert_eq!(enabled.get("Smart CAR"), None);
+        assert_eq!(enabled.get("Other"), Some(true));
+    }
 
     #[test]
-    fn download_file() {
-        let mut mock_writer = MockWriter::new();
-        mock_writer
-            .expect_write_all()
-            .returning(|_| Ok(()))
-            .times((TEST_SIZE_BYTES as usize / super::CHUNK_SIZE)..);
+    fn fail_uninstall_core_mod() {
+        let dir = TempDir::create("./fail_uninstall_core_mod").expect("Unable to create temp dir");
+        install_northstar(Cursor::new(TEST_NS_ARCHIVE), &dir).expect("install northstar");
+        // the test archive doesn't include a manifest, so give the client a real one
+        let client_dir = dir
+            .join("R2Northstar")
+            .join("mods")
+            .join("Northstar.Client");
+        let manifest = r#"{"name":"Northstar","version_number":"1.0.0","website_url":"","description":"","dependencies":[]}"#;
+        fs::write(client_dir.join("manifest.json"), manifest).expect("write manifest");
+        let client = get_submods(
+            &serde_json::from_str(manifest).expect("parse manifest"),
+            &client_dir,
+        )
+        .and_then(|m| m.into_iter().next())
+        .expect("client mod");
 
-        let res = download(mock_writer, TEST_URL);
-        assert!(res.is_ok());
-        res.map(|size| {
-            assert_eq!(size, TEST_SIZE_BYTES);
-            size
+        let mods = dir.join("R2Northstar").join("mods");
+        let res = uninstall_mod(&client, &mods, None, false);
+        assert!(
+            matches!(res, Err(ThermiteError::CoreModError(name)) if name == "Northstar.Client")
+        );
+        assert!(client.path.exists());
+
+        uninstall_mod(&client, &mods, None, true).expect("force uninstall");
+        assert!(!client.path.exists());
+    }
+
+    #[test]
+    fn fail_uninstall_outside_packages_dir() {
+        let dir = TempDir::create("./fail_uninstall_outside").expect("Unable to create temp dir");
+        install_mod("foo-bar-0.1.0", Cursor::new(TEST_ARCHIVE), &dir).expect("install mod");
+        let mut installed = crate::core::find_mods(&dir).expect("find mods").remove(0);
+        // a mod without its own manifest, in a folder that has one further up
+        fs::write(dir.join("manifest.json"), MANIFEST).expect("write manifest");
+        fs::remove_file(dir.join("foo-bar-0.1.0").join("manifest.json")).expect("remove manifest");
+        installed.path = dir.join("foo-bar-0.1.0").join("mods").join("Smart CAR");
+
+        let res = uninstall_mod(&installed, &dir, None, true);
+        assert!(matches!(res, Err(ThermiteError::MissingFile(_))));
+        assert!(installed.path.exists());
+        assert!(dir.join("manifest.json").exists());
+    }
+
+    #[test]
+    fn update_package() {
+        let dir = TempDir::create("./update_package").expect("Unable to create temp dir");
+        install_mod("foo-bar-0.1.0", Cursor::new(TEST_ARCHIVE), &dir).expect("install mod");
+        let old = crate::core::find_mods(&dir).expect("find mods").remove(0);
+        let cfg = Path::new("mods").join("Smart CAR").join("mod").join("cfg");
+        fs::create_dir_all(old.path.join("mod").join("cfg")).expect("create cfg dir");
+        fs::write(old.path.join("mod").join("cfg").join("user.cfg"), "edited").expect("write cfg");
+        let mut enabled = EnabledMods::default();
+        enabled.dont_save();
+        enabled.set("Smart CAR", false);
+
+        let path = update_mod(
+            &old,
+            "foo-bar-0.2.0",
+            Cursor::new(TEST_ARCHIVE),
+            &dir,
+            Some(&mut enabled),
+            true,
+        )
+        .expect("update mod");
+
+        assert_eq!(path, dir.join("foo-bar-0.2.0"));
+        assert!(!dir.join("foo-bar-0.1.0").exists());
+        assert!(!dir.join(".foo-bar-0.2.0.update").exists());
+        assert_eq!(
+            fs::read_to_string(path.join(cfg).join("user.cfg")).expect("read cfg"),
+            "edited"
+        );
+        assert_eq!(enabled.get("Smart CAR"), Some(false));
+    }
+
+    #[test]
+    fn fail_update_keeps_old_version() {
+        let dir = TempDir::create("./fail_update_package").expect("Unable to create temp dir");
+        install_mod("foo-bar-0.1.0", Cursor::new(TEST_ARCHIVE), &dir).expect("install mod");
+        let old = crate::core::find_mods(&dir).expect("find mods").remove(0);
+
+        let res = update_mod(
+            &old,
+            "foo-bar-0.2.0",
+            Cursor::new(b"not a zip"),
+            &dir,
+            None,
+            false,
+        );
+
+        assert!(matches!(res, Err(ThermiteError::InvalidArchive { .. })));
+        assert!(old.path.exists());
+        assert!(!dir.join(".foo-bar-0.2.0.update").exists());
+    }
+
+    #[test]
+    fn fail_update_restores_old_version() {
+        let dir = TempDir::create("./fail_update_restore").expect("Unable to create temp dir");
+        install_mod("foo-bar-0.1.0", Cursor::new(TEST_ARCHIVE), &dir).expect("install mod");
+        let old = crate::core::find_mods(&dir).expect("find mods").remove(0);
+        // a file where the new version goes can't be replaced with a folder
+        fs::write(dir.join("foo-bar-0.2.0"), "").expect("write file");
+
+        let res = update_mod(
+            &old,
+            "foo-bar-0.2.0",
+            Cursor::new(TEST_ARCHIVE),
+            &dir,
+            None,
+            false,
+        );
+
+        assert!(res.is_err());
+        assert!(old.path.join("mod.json").exists());
+        assert!(!dir.join(".foo-bar-0.1.0.old").exists());
+        assert!(!dir.join(".foo-bar-0.2.0.update").exists());
+    }
+
+    #[test]
+    fn install_local_file() {
+        let dir = TempDir::create("./install_local_file").expect("Unable to create temp dir");
+        let named = dir.join("foo-bar-0.1.0.zip");
+        let unnamed = dir.join("Smart CAR (1).zip");
+        fs::write(&named, TEST_ARCHIVE).expect("write archive");
+        fs::write(&unnamed, TEST_ARCHIVE).expect("write archive");
+
+        let path = install_from_file(&named, dir.join("packages")).expect("install named file");
+        assert_eq!(path, dir.join("packages").join("foo-bar-0.1.0"));
+
+        let path = install_from_file(&unnamed, dir.join("packages")).expect("install unnamed file");
+        assert_eq!(path, dir.join("packages").join("local-SmartCAR-1.0.0"));
+        assert!(path.join("manifest.json").exists());
+    }
+
+    #[test]
+    fn install_returning_mods() {
+        let dir = TempDir::create("./install_verbose").expect("Unable to create temp dir");
+        let mods = install_mod_verbose("foo-bar-0.1.0", Cursor::new(TEST_ARCHIVE), &dir)
+            .expect("install mod");
+
+        assert_eq!(mods.len(), 1);
+        assert_eq!(mods[0].mod_json.name, "Smart CAR");
+        assert_eq!(mods[0].author, "foo");
+        assert_eq!(mods[0].manifest.name, "SmartCAR");
+        assert_eq!(
+            mods[0].path,
+            dir.join("foo-bar-0.1.0").join("mods").join("Smart CAR")
+        );
+        assert!(mods[0].path.join("mod.json").exists());
+    }
+
+    #[test]
+    fn install_writing_markers() {
+        let dir = TempDir::create("./install_markers").expect("Unable to create temp dir");
+        let mods = install_with_markers("foo-bar-0.1.0", Cursor::new(TEST_ARCHIVE), &dir)
+            .expect("install mod");
+
+        let path = &mods[0].path;
+        assert_eq!(
+            fs::read_to_string(path.join(AUTHOR_FILE)).expect("read author file"),
+            "foo"
+        );
+        assert_eq!(
+            fs::read_to_string(path.join(VERSION_FILE)).expect("read version file"),
+            "1.0.0"
+        );
+    }
+
+    #[test]
+    fn install_with_archive_check() {
+        let dir = TempDir::create("./install_checked").expect("Unable to create temp dir");
+        let path = install_checked(
+            "foo-bar-0.1.0",
+            Cursor::new(TEST_ARCHIVE),
+            &dir,
+            |archive, manifest| {
+                assert_eq!(manifest.map(|m| m.name.as_str()), Some("SmartCAR"));
+                assert!(archive.file_names().any(|n| n.starts_with("mods/")));
+                Ok(())
+            },
+        )
+        .expect("install mod");
+
+        assert!(path.join("manifest.json").exists());
+    }
+
+    #[test]
+    fn fail_archive_check() {
+        let dir = TempDir::create("./fail_install_checked").expect("Unable to create temp dir");
+        let res = install_checked("foo-bar-0.1.0", Cursor::new(TEST_ARCHIVE), &dir, |a, _| {
+            if a.file_names().any(|n| n.starts_with("plugins/")) {
+                Ok(())
+            } else {
+                Err("no plugins".into())
+            }
+        });
+
+        assert!(matches!(res, Err(ThermiteError::SanityError(_))));
+        assert!(!dir.join("foo-bar-0.1.0").exists());
+    }
+
+    fn build_archive(build: impl FnOnce(&mut zip::ZipWriter<Cursor<Vec<u8>>>)) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+        build(&mut writer);
+        writer.finish().expect("finish archive").into_inner()
+    }
+
+    fn validate_bytes(bytes: Vec<u8>) -> Result<()> {
+        validate_archive(&mut ZipArchive::new(Cursor::new(bytes)).expect("open archive"))
+    }
+
+    fn unsafe_reason(res: Result<()>) -> Option<UnsafeReason> {
+        match res {
+            Err(ThermiteError::UnsafeArchive { reason, .. }) => Some(reason),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn validate_safe_archives() {
+        let options = zip::write::SimpleFileOptions::default();
+        let archive = build_archive(|w| {
+            w.start_file("mods/Test/mod.json", options)
+                .expect("start file");
+            w.write_all(b"{}").expect("write file");
+            w.add_symlink("mods/Test/link", "../Other/mod.json", options)
+                .expect("add symlink");
+        });
+
+        assert!(validate_bytes(archive).is_ok());
+        assert!(validate_archive(
+            &mut ZipArchive::new(Cursor::new(TEST_NS_ARCHIVE)).expect("open")
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn fail_unsafe_archives() {
+        let options = zip::write::SimpleFileOptions::default();
+        let with_file = |name: &'static str| {
+            build_archive(|w| {
+                w.start_file(name, options).expect("start file");
+            })
+        };
+
+        assert_eq!(
+            unsafe_reason(validate_bytes(with_file("/etc/passwd"))),
+            Some(UnsafeReason::AbsolutePath)
+        );
+        assert_eq!(
+            unsafe_reason(validate_bytes(with_file("C:\\Windows\\evil.dll"))),
+            Some(UnsafeReason::AbsolutePath)
+        );
+        assert_eq!(
+            unsafe_reason(validate_bytes(with_file("mods/../../evil.dll"))),
+            Some(UnsafeReason::PathTraversal)
+        );
+        assert_eq!(
+            unsafe_reason(validate_bytes(with_file("mods/Test/con.txt"))),
+            Some(UnsafeReason::ReservedName)
+        );
+
+        let archive = build_archive(|w| {
+            w.add_symlink("mods/link", "../../../etc", options)
+                .expect("add symlink");
+        });
+        assert_eq!(
+            unsafe_reason(validate_bytes(archive)),
+            Some(UnsafeReason::SymlinkEscape)
+        );
+
+        let archive = build_archive(|w| {
+            w.add_symlink("mods/link", "Test", options)
+                .expect("add symlink");
+            w.start_file("mods/LINK/evil.dll", options)
+                .expect("start file");
+        });
+        assert_eq!(
+            unsafe_reason(validate_bytes(archive)),
+            Some(UnsafeReason::SymlinkEscape)
+        );
+
+        let archive = build_archive(|w| {
+            w.start_file(
+                "bomb.bin",
+                options.compression_method(zip::CompressionMethod::Deflated),
+            )
+            .expect("start file");
+            w.write_all(&vec![0; 4 * 1024 * 1024]).expect("write file");
+        });
+        assert_eq!(
+            unsafe_reason(validate_bytes(archive)),
+            Some(UnsafeReason::CompressionRatio)
+        );
+    }
+
+    /// Each link only escapes once it's resolved through the one before it
+    fn symlink_chain_archive() -> Vec<u8> {
+        let options = zip::write::SimpleFileOptions::default();
+        build_archive(|w| {
+            w.add_directory("sub/", options).expect("add directory");
+            w.add_symlink("l1", "sub/..", options).expect("add symlink");
+            w.add_symlink("l2", "l1/..", options).expect("add symlink");
+            w.start_file("l2/escaped.txt", options).expect("start file");
+            w.write_all(b"escaped").expect("write file");
         })
-        .unwrap();
+    }
+
+    #[test]
+    fn fail_symlink_chain() {
+        assert_eq!(
+            unsafe_reason(validate_bytes(symlink_chain_archive())),
+            Some(UnsafeReason::SymlinkEscape)
+        );
+
+        let dir = TempDir::create("./fail_symlink_chain").expect("Unable to create temp dir");
+        let res = install_with_progress(
+            "foo-bar-0.1.0",
+            Cursor::new(symlink_chain_archive()),
+            &dir,
+            |_| ControlFlow::Continue(()),
+        );
+        assert!(matches!(
+            res,
+            Err(ThermiteError::UnsafeArchive {
+                reason: UnsafeReason::SymlinkEscape,
+                ..
+            })
+        ));
+        assert!(!dir.join("escaped.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn extract_permissions_and_symlinks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::create("./extract_permissions").expect("Unable to create temp dir");
+        let options = zip::write::SimpleFileOptions::default();
+        let archive = build_archive(|w| {
+            w.start_file("plugins/helper.sh", options.unix_permissions(0o755))
+                .expect("start file");
+            w.write_all(b"#!/bin/sh").expect("write file");
+            w.add_symlink("plugins/link.sh", "helper.sh", options)
+                .expect("add symlink");
+            w.start_file("plugins/readonly.txt", options.unix_permissions(0o444))
+                .expect("start file");
+        });
+
+        let install = || {
+            install_with_progress("foo-bar-0.1.0", Cursor::new(&archive), &dir, |_| {
+                ControlFlow::Continue(())
+            })
+        };
+        install().expect("install mod");
+        let path = install().expect("reinstall mod");
+
+        let helper = path.join("plugins").join("helper.sh");
+        let mode = fs::metadata(&helper)
+            .expect("helper metadata")
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o755);
+
+        let link = path.join("plugins").join("link.sh");
+        assert!(link
+            .symlink_metadata()
+            .expect("link metadata")
+            .file_type()
+            .is_symlink());
+        assert_eq!(
+            fs::read_link(link).expect("read link"),
+            Path::new("helper.sh")
+        );
+
+        let readonly = fs::metadata(path.join("plugins").join("readonly.txt"))
+            .expect("readonly metadata")
+            .permissions()
+            .mode();
+        assert_eq!(readonly & 0o777, 0o644);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fail_extract_through_existing_symlink() {
+        let dir = TempDir::create("./extract_existing_symlink").expect("Unable to create temp dir");
+        let outside = dir.join("outside");
+        let package = dir.join("foo-bar-0.1.0");
+        fs::create_dir_all(&outside).expect("create dir");
+        fs::create_dir_all(&package).expect("create dir");
+        let target = outside.canonicalize().expect("canonicalize");
+        std::os::unix::fs::symlink(target, package.join("mods")).expect("create symlink");
+
+        let options = zip::write::SimpleFileOptions::default();
+        let archive = build_archive(|w| {
+            w.start_file("mods/evil.txt", options).expect("start file");
+        });
+        let res = install_with_progress("foo-bar-0.1.0", Cursor::new(archive), &dir, |_| {
+            ControlFlow::Continue(())
+        });
+
+        assert!(matches!(
+            res,
+            Err(ThermiteError::UnsafeArchive {
+                reason: UnsafeReason::PathTraversal,
+                ..
+            })
+        ));
+        assert!(!outside.join("evil.txt").exists());
+    }
+
+    #[test]
+    fn install_ns_profile() {
+        let dir = TempDir::create("./install_ns_profile").expect("Unable to create temp dir");
+        let profile = dir.join("R2Custom");
+        let written = install_northstar_profile(Cursor::new(TEST_NS_ARCHIVE), &profile)
+            .expect("install profile");
+
+        assert!(written.contains(&profile.join("Northstar.dll")));
+        assert!(written.contains(&profile.join("plugins").join("DiscordRPC.dll")));
+        assert!(written.contains(
+            &profile
+                .join("mods")
+                .join("Northstar.Client")
+                .join("mod.json")
+        ));
+        assert!(written.iter().all(|p| p.is_file()));
+
+        assert!(!profile.join("NorthstarLauncher.exe").exists());
+        assert!(!dir.join("NorthstarLauncher.exe").exists());
+        assert!(!profile.join("R2Northstar").exists());
+    }
+
+    #[test]
+    fn fail_plugin_symlink_escape() {
+        let dir = TempDir::create("./plugin_symlink").expect("Unable to create temp dir");
+        let options = zip::write::SimpleFileOptions::default();
+        // stays inside the archive, but not once plugins/ is stripped
+        let archive = build_archive(|w| {
+            w.add_symlink("plugins/link", "../manifest.json", options)
+                .expect("add symlink");
+        });
+        assert!(validate_bytes(archive.clone()).is_ok());
+
+        let res = install_plugin(Cursor::new(archive), dir.join("plugins"));
+        assert!(matches!(
+            res,
+            Err(ThermiteError::UnsafeArchive {
+                reason: UnsafeReason::SymlinkEscape,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn fail_ns_profile_symlink_escape() {
+        let dir = TempDir::create("./ns_profile_symlink").expect("Unable to create temp dir");
+        let options = zip::write::SimpleFileOptions::default();
+        // stays inside the archive, but not once R2Northstar/ is stripped
+        let archive = build_archive(|w| {
+            w.add_symlink("Northstar/R2Northstar/mods/link", "../../..", options)
+                .expect("add symlink");
+        });
+        assert!(validate_bytes(archive.clone()).is_ok());
+
+        let res = install_northstar_profile(Cursor::new(archive), dir.join("R2Custom"));
+        assert!(matches!(
+            res,
+            Err(ThermiteError::UnsafeArchive {
+                reason: UnsafeReason::SymlinkEscape,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn install_ns_with_progress() {
+        let dir = TempDir::create("./install_ns_progress").expect("Unable to create temp dir");
+        let mut events = vec![];
+        install_northstar_with_progress(Cursor::new(TEST_NS_ARCHIVE), &dir, |e| {
+            events.push(e);
+            ControlFlow::Continue(())
+        })
+        .expect("install northstar");
+
+        let of = ZipArchive::new(Cursor::new(TEST_NS_ARCHIVE))
+            .expect("open archive")
+            .len();
+        assert_eq!(events.len(), of + 1);
+        assert!(events[..of]
+            .iter()
+            .enumerate()
+            .all(|(n, e)| matches!(e, ProgressEvent::ExtractFile { i, .. } if *i == n)));
+        assert_eq!(events.last(), Some(&ProgressEvent::Finished));
+        assert!(dir.join("Northstar.dll").exists());
+    }
+
+    #[test]
+    fn update_ns_removes_stale_files() {
+        let dir = TempDir::create("./update_ns_stale").expect("Unable to create temp dir");
+        install_northstar(Cursor::new(TEST_NS_ARCHIVE), &dir).expect("install northstar");
+
+        let stale = [
+            "R2Northstar/plugins/Old.dll",
+            "R2Northstar/mods/Northstar.Client/mod/old/old.nut",
+        ];
+        let kept = [
+            "R2Northstar/mods/Northstar.Client/mod/cfg/user.cfg",
+            "R2Northstar/mods/Foo.Bar/mod.json",
+        ];
+        let mut record: NorthstarFiles =
+            serde_json::from_slice(&fs::read(dir.join(NORTHSTAR_RECORD)).expect("read record"))
+                .expect("parse record");
+        for file in stale.iter().chain(&kept) {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+            fs::write(path, "stale").expect("write file");
+            record.files.push(NorthstarFile {
+                path: (*file).into(),
+                sha256: sha256_file(dir.join(file)).expect("hash file"),
+            });
+        }
+        fs::write(
+            dir.join(NORTHSTAR_RECORD),
+            serde_json::to_vec(&record).expect("serialize record"),
+        )
+        .expect("write record");
+
+        let removed = update_northstar(Cursor::new(TEST_NS_ARCHIVE), &dir).expect("update");
+
+        assert_eq!(removed.len(), stale.len());
+        assert!(stale.iter().all(|f| !dir.join(f).exists()));
+        assert!(!dir
+            .join("R2Northstar/mods/Northstar.Client/mod/old")
+            .exists());
+        assert!(kept.iter().all(|f| dir.join(f).exists()));
+        assert!(dir.join("Northstar.dll").exists());
+        assert!(dir.join("R2Northstar/plugins/DiscordRPC.dll").exists());
+    }
+
+    #[test]
+    fn update_ns_without_record() {
+        let dir = TempDir::create("./update_ns_no_record").expect("Unable to create temp dir");
+        install_northstar(Cursor::new(TEST_NS_ARCHIVE), &dir).expect("install northstar");
+        fs::remove_file(dir.join(NORTHSTAR_RECORD)).expect("remove record");
+
+        let stale = dir.join("R2Northstar/mods/Northstar.CustomServers/old.txt");
+        let third_party = dir.join("R2Northstar/mods/Foo.Bar/old.txt");
+        for path in [&stale, &third_party] {
+            fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+            fs::write(path, "stale").expect("write file");
+        }
+
+        let removed = update_northstar(Cursor::new(TEST_NS_ARCHIVE), &dir).expect("update");
+
+        assert_eq!(removed, vec![stale]);
+        assert!(third_party.exists());
+        assert!(dir
+            .join("R2Northstar/mods/Northstar.Client/manifest.json")
+            .exists());
+        assert!(dir.join(NORTHSTAR_RECORD).exists());
+    }
+
+    #[test]
+    fn install_ns_writes_record() {
+        let dir = TempDir::create("./install_ns_record").expect("Unable to create temp dir");
+        install_northstar(Cursor::new(TEST_NS_ARCHIVE), &dir).expect("install northstar");
+
+        let record: NorthstarFiles =
+            serde_json::from_slice(&fs::read(dir.join(NORTHSTAR_RECORD)).expect("read record"))
+                .expect("parse record");
+        let files = ZipArchive::new(Cursor::new(TEST_NS_ARCHIVE))
+            .expect("open archive")
+            .file_names()
+            .filter(|n| !n.ends_with('/'))
+            .count();
+
+        assert_eq!(record.version.as_deref(), Some("1.22.0"));
+        assert_eq!(record.files.len(), files);
+        let dll = record
+            .files
+            .iter()
+            .find(|f| f.path == "Northstar.dll")
+            .expect("Northstar.dll in record");
+        assert_eq!(
+            dll.sha256,
+            sha256_file(dir.join("Northstar.dll")).expect("hash file")
+        );
+        assert!(record
+            .files
+            .iter()
+            .any(|f| f.path == "R2Northstar/mods/Northstar.Client/mod.json"));
+    }
+
+    #[test]
+    fn install_ns_github_layout() {
+        let dir = TempDir::create("./install_ns_github").expect("Unable to create temp dir");
+        let options = zip::write::SimpleFileOptions::default();
+        let archive = build_archive(|w| {
+            w.start_file("Northstar.dll", options).expect("start file");
+            w.write_all(b"dll").expect("write");
+            w.add_directory("R2Northstar/mods/Northstar.Client/", options)
+                .expect("add directory");
+            w.start_file("R2Northstar/mods/Northstar.Client/mod.json", options)
+                .expect("start file");
+            w.write_all(br#"{"Name":"Northstar.Client","Version":"1.24.0"}"#)
+                .expect("write");
+        });
+
+        install_northstar(Cursor::new(archive), &dir).expect("install northstar");
+
+        assert!(dir.join("Northstar.dll").is_file());
+        assert!(dir
+            .join("R2Northstar/mods/Northstar.Client/mod.json")
+            .is_file());
+        assert!(!dir.join("Northstar").exists());
+    }
+
+    #[test]
+    fn find_release_by_version() {
+        let release = |tag: &str| NorthstarRelease {
+            tag_name: tag.into(),
+            name: None,
+            body: None,
+            prerelease: false,
+            published_at: None,
+            assets: vec![],
+        };
+        let releases = [release("v1.24.0"), release("v1.23.0")];
+
+        assert_eq!(
+            find_release(&releases, "1.23.0").map(|r| r.tag_name.as_str()),
+            Some("v1.23.0")
+        );
+        assert_eq!(
+            find_release(&releases, "v1.24.0").map(|r| r.tag_name.as_str()),
+            Some("v1.24.0")
+        );
+        assert!(find_release(&releases, "1.22.0").is_none());
+    }
+
+    #[test]
+    fn fail_ns_symlink_escape() {
+        let dir = TempDir::create("./ns_symlink").expect("Unable to create temp dir");
+        let options = zip::write::SimpleFileOptions::default();
+        // stays inside the archive, but not once Northstar/ is stripped
+        let archive = build_archive(|w| {
+            w.add_symlink("Northstar/R2Northstar/mods/link", "../../..", options)
+                .expect("add symlink");
+        });
+        assert!(validate_bytes(archive.clone()).is_ok());
+
+        let res = install_northstar(Cursor::new(archive), &dir);
+        assert!(matches!(
+            res,
+            Err(ThermiteError::UnsafeArchive {
+                reason: UnsafeReason::SymlinkEscape,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn install_ns_xbox_content() {
+        let dir = TempDir::create("./install_ns_xbox").expect("Unable to create temp dir");
+        let game = dir.join("XboxGames").join("Titanfall 2");
+        let content = game.join("Content");
+        fs::create_dir_all(&content).expect("create dir");
+        fs::write(content.join("MicrosoftGame.config"), "").expect("write config");
+
+        let res = install_northstar(Cursor::new(TEST_NS_ARCHIVE), &game);
+        assert!(matches!(res, Err(ThermiteError::UnsupportedInstall(path)) if path == game));
+        assert!(!game.join("R2Northstar").exists());
+
+        install_northstar(Cursor::new(TEST_NS_ARCHIVE), &content).expect("install northstar");
+        assert!(content.join("R2Northstar").is_dir());
+    }
+
+    #[test]
+    fn install_ns_dedicated() {
+        let dir = TempDir::create("./install_ns_dedicated").expect("Unable to create temp dir");
+        install_northstar_dedicated(Cursor::new(TEST_NS_ARCHIVE), &dir).expect("install northstar");
+
+        let mods = dir.join("R2Northstar").join("mods");
+        assert!(dir.join("NorthstarLauncher.exe").exists());
+        assert!(dir.join("Northstar.dll").exists());
+        assert!(mods
+            .join("Northstar.CustomServers")
+            .join("mod.json")
+            .exists());
+        assert!(!mods.join("Northstar.Client").exists());
+        assert!(!dir.join("R2Northstar/plugins/DiscordRPC.dll").exists());
+    }
+
+    #[test]
+    fn client_only_files() {
+        assert!(is_client_only(Path::new(
+            "R2Northstar/mods/Northstar.Client/mod.json"
+        )));
+        assert!(is_client_only(Path::new(
+            "R2Northstar/mods/Some.Sounds/audio/foo.wav"
+        )));
+        assert!(!is_client_only(Path::new(
+            "R2Northstar/mods/Northstar.CustomServers/mod.json"
+        )));
+        assert!(!is_client_only(Path::new("Northstar.dll")));
+    }
+
+    #[test]
+    fn backup_and_restore_ns() {
+        let dir = TempDir::create("./backup_restore_ns").expect("Unable to create temp dir");
+        let backups = dir.join("backups");
+        let game = dir.join("game");
+        fs::create_dir_all(&game).expect("create dir");
+
+        assert!(backup_northstar(&game, &backups)
+            .expect("backup empty dir")
+            .is_none());
+        install_northstar(Cursor::new(TEST_NS_ARCHIVE), &game).expect("install northstar");
+        let dll = fs::read(game.join("Northstar.dll")).expect("read dll");
+
+        let (backup, _) =
+            update_northstar_with_backup(Cursor::new(TEST_NS_ARCHIVE), &game, &backups)
+                .expect("update northstar");
+        let backup = backup.expect("backup");
+        assert!(backup.is_file());
+
+        let client = game.join("R2Northstar/mods/Northstar.Client");
+        fs::write(game.join("Northstar.dll"), "broken").expect("write dll");
+        fs::write(client.join("new.txt"), "new").expect("write file");
+        fs::remove_file(client.join("mod.json")).expect("remove mod.json");
+
+        restore_backup(&backup, &game).expect("restore backup");
+
+        assert_eq!(fs::read(game.join("Northstar.dll")).expect("read dll"), dll);
+        assert!(client.join("mod.json").exists());
+        assert!(client.join("manifest.json").exists());
+        assert!(!client.join("new.txt").exists());
+        assert!(game.join(NORTHSTAR_RECORD).exists());
+    }
+
+    #[test]
+    fn verify_and_repair_ns() {
+        let dir = TempDir::create("./verify_repair_ns").expect("Unable to create temp dir");
+        install_northstar(Cursor::new(TEST_NS_ARCHIVE), &dir).expect("install northstar");
+
+        let expected = northstar_files(Cursor::new(TEST_NS_ARCHIVE)).expect("list files");
+        assert_eq!(
+            read_northstar_record(&dir).expect("read record"),
+            Some(expected.clone())
+        );
+        assert!(verify_northstar(&dir, &expected).expect("verify").is_ok());
+
+        let client = dir.join("R2Northstar/mods/Northstar.Client");
+        fs::write(dir.join("Northstar.dll"), "broken").expect("write dll");
+        fs::remove_file(client.join("mod.json")).expect("remove mod.json");
+        fs::write(client.join("extra.nut"), "extra").expect("write file");
+        fs::write(client.join("user.cfg"), "user").expect("write file");
+
+        let report = verify_northstar(&dir, &expected).expect("verify");
+        assert_eq!(report.missing, vec![client.join("mod.json")]);
+        assert_eq!(report.modified, vec![dir.join("Northstar.dll")]);
+        assert_eq!(report.extra, vec![client.join("extra.nut")]);
+
+        let repaired = repair_northstar(Cursor::new(TEST_NS_ARCHIVE), &dir).expect("repair");
+        assert_eq!(repaired, report);
+
+        let report = verify_northstar(&dir, &expected).expect("verify");
+        assert!(report.missing.is_empty());
+        assert!(report.modified.is_empty());
+        assert_eq!(report.extra.len(), 1);
+    }
+
+    #[test]
+    fn game_not_running() {
+        let dir = TempDir::create("./game_not_running").expect("Unable to create temp dir");
+        assert!(check_game_running(&dir).is_ok());
+
+        install_northstar(Cursor::new(TEST_NS_ARCHIVE), &dir).expect("install northstar");
+        assert!(check_game_running(&dir).is_ok());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn game_running_from_path() {
+        let dir = TempDir::create("./game_running").expect("Unable to create temp dir");
+        let game = dir.join("game");
+        let other = dir.join("other");
+        fs::create_dir_all(&game).expect("create dir");
+        fs::create_dir_all(&other).expect("create dir");
+        let exe = game.join("Titanfall2.exe");
+        fs::copy("/bin/sleep", &exe).expect("copy executable");
+
+        let mut child = std::process::Command::new(exe.canonicalize().expect("canonicalize"))
+            .arg("30")
+            .spawn()
+            .expect("spawn process");
+        // give the process time to start
+        let mut res = check_game_running(&game);
+        for _ in 0..50 {
+            if res.is_err() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+            res = check_game_running(&game);
+        }
+        let other_res = check_game_running(&other);
+        child.kill().expect("kill process");
+        let _ = child.wait();
+
+        assert!(
+            matches!(res, Err(ThermiteError::GameRunning(path)) if path == game.join("Titanfall2.exe"))
+        );
+        assert!(other_res.is_ok());
+    }
+
+    #[test]
+    fn get_installed_ns_version() {
+        let dir = TempDir::create("./installed_ns_version").expect("Unable to create temp dir");
+        assert_eq!(
+            installed_northstar_version(&dir).expect("read version"),
+            None
+        );
+
+        install_northstar(Cursor::new(TEST_NS_ARCHIVE), &dir).expect("install northstar");
+        assert_eq!(
+            installed_northstar_version(&dir).expect("read version"),
+            Some("1.22.0".into())
+        );
+
+        fs::remove_file(dir.join(NORTHSTAR_RECORD)).expect("remove record");
+        assert_eq!(
+            installed_northstar_version(&dir).expect("read version"),
+            Some("1.22.0".into())
+        );
+    }
+
+    #[test]
+    fn verify_installed_package() {
+        let dir = TempDir::create("./verify_package").expect("Unable to create temp dir");
+        let path = install_mod("foo-bar-0.1.0", Cursor::new(TEST_ARCHIVE), &dir).expect("install");
+        let report = verify_package(&path, Cursor::new(TEST_ARCHIVE)).expect("verify");
+        assert!(report.is_ok(), "{report:?}");
+
+        let mut files = vec![];
+        let mut dirs = vec![path.clone()];
+        while let Some(d) = dirs.pop() {
+            for child in d.read_dir().expect("read dir") {
+                let child = child.expect("entry").path();
+                if child.is_dir() {
+                    dirs.push(child);
+                } else {
+                    files.push(child);
+                }
+            }
+        }
+        files.sort();
+        fs::remove_file(&files[0]).expect("remove file");
+        fs::write(&files[1], "modified").expect("write file");
+        fs::write(path.join("extra.txt"), "").expect("write file");
+        fs::write(path.join(AUTHOR_FILE), "foo").expect("write file");
+
+        let report = verify_package(&path, Cursor::new(TEST_ARCHIVE)).expect("verify");
+        assert_eq!(report.missing, [files[0].clone()]);
+        assert_eq!(report.modified, [files[1].clone()]);
+        assert_eq!(report.extra, [path.join("extra.txt")]);
+    }
+
+    #[test]
+    fn fail_relocated_symlink_escape() {
+        let dir = TempDir::create("./relocated_symlink").expect("Unable to create temp dir");
+        let options = zip::write::SimpleFileOptions::default();
+        // stays inside the archive, but not once the wrapper folder is stripped
+        let archive = build_archive(|w| {
+            w.start_file("bar-main/mods/Bar/mod.json", options)
+                .expect("start file");
+            w.add_symlink("bar-main/mods/Bar/link", "../../../manifest.json", options)
+                .expect("add symlink");
+        });
+        assert!(validate_bytes(archive.clone()).is_ok());
+
+        let res = install_mod_with(
+            "foo-bar-1.0.0",
+            Cursor::new(archive),
+            &dir,
+            &InstallOptions::new().repair_layout(true),
+        );
+        assert!(matches!(
+            res,
+            Err(ThermiteError::UnsafeArchive {
+                reason: UnsafeReason::SymlinkEscape,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn repair_misfolded_archives() {
+        let options = zip::write::SimpleFileOptions::default();
+        let install = |archive: Vec<u8>, repair: bool| {
+            let dir = TempDir::create("./repair_layout").expect("Unable to create temp dir");
+            let report = install_mod_with(
+                "foo-bar-1.0.0",
+                Cursor::new(archive),
+                &dir,
+                &InstallOptions::new().repair_layout(repair),
+            )
+            .expect("install mod");
+            // only folders directly inside `mods/` are loaded by Northstar
+            let mods = fs::read_dir(report.path.join("mods")).map_or(0, |d| {
+                d.filter(|e| {
+                    e.as_ref()
+                        .is_ok_and(|e| e.path().join("mod.json").is_file())
+                })
+                .count()
+            });
+            (report.fixes, mods)
+        };
+
+        let root = build_archive(|w| {
+            w.start_file("manifest.json", options).expect("start file");
+            w.write_all(MANIFEST.as_bytes()).expect("write file");
+            w.start_file("mod.json", options).expect("start file");
+            w.write_all(br#"{"Name": "Foo: Bar", "Description": "", "Version": "1.0.0"}"#)
+                .expect("write file");
+            w.start_file("mod/scripts/vscripts/bar.nut", options)
+                .expect("start file");
+        });
+        assert_eq!(install(root.clone(), false), (vec![], 0));
+        let (fixes, mods) = install(root, true);
+        assert_eq!(
+            fixes,
+            [LayoutFix {
+                from: PathBuf::new(),
+                to: PathBuf::from("mods/Foo_ Bar")
+            }]
+        );
+        assert_eq!(mods, 1);
+
+        let unwrapped = build_archive(|w| {
+            w.start_file("manifest.json", options).expect("start file");
+            w.write_all(MANIFEST.as_bytes()).expect("write file");
+            w.start_file("Bar/mod.json", options).expect("start file");
+            w.write_all(br#"{"Name": "Bar", "Description": "", "Version": "1.0.0"}"#)
+                .expect("write file");
+        });
+        let (fixes, mods) = install(unwrapped, true);
+        assert_eq!(fixes[0].to, Path::new("mods/Bar"));
+        assert_eq!(mods, 1);
+
+        let wrapped = build_archive(|w| {
+            w.start_file("bar-main/manifest.json", options)
+                .expect("start file");
+            w.write_all(MANIFEST.as_bytes()).expect("write file");
+            w.start_file("bar-main/mods/Bar/mod.json", options)
+                .expect("start file");
+            w.write_all(br#"{"Name": "Bar", "Description": "", "Version": "1.0.0"}"#)
+                .expect("write file");
+        });
+        let (fixes, mods) = install(wrapped, true);
+        assert_eq!(fixes[0].from, Path::new("bar-main"));
+        assert_eq!(mods, 1);
+
+        assert_eq!(install(TEST_ARCHIVE.to_vec(), true).0, []);
+    }
+
+    #[test]
+    fn install_and_remove_plugin() {
+        let dir = TempDir::create("./install_plugin").expect("Unable to create temp dir");
+        let options = zip::write::SimpleFileOptions::default();
+        let archive = build_archive(|w| {
+            w.start_file("manifest.json", options).expect("start file");
+            w.start_file("plugins/Helper.dll", options)
+                .expect("start file");
+            w.write_all(b"MZ").expect("write file");
+            w.start_file("mods/Test/mod.json", options)
+                .expect("start file");
+        });
+
+        let written = install_plugin(Cursor::new(archive), &dir).expect("install plugin");
+        assert_eq!(written, [dir.join("Helper.dll")]);
+        assert!(!dir.join("mods").exists());
+
+        let plugins = crate::core::utils::find_plugins(&dir).expect("find plugins");
+        assert_eq!(plugins.len(), 1);
+        assert_eq!(plugins[0].name, "Helper");
+        assert_eq!(plugins[0].size, 2);
+
+        remove_plugin(&plugins[0]).expect("remove plugin");
+        assert!(!dir.join("Helper.dll").exists());
+    }
+
+    #[test]
+    fn fail_install_without_plugins() {
+        let dir = TempDir::create("./install_no_plugin").expect("Unable to create temp dir");
+        let res = install_plugin(Cursor::new(TEST_ARCHIVE), &dir);
+
+        assert!(matches!(res, Err(ThermiteError::MissingFile(_))));
+    }
+
+    #[test]
+    fn autoremove_orphans() {
+        let dir = TempDir::create("./autoremove").expect("Unable to create temp dir");
+        let mut index = LocalIndex::default();
+        let package = |mod_string: &str, explicit: bool, deps: &[&str], mods: &[&str]| {
+            fs::create_dir_all(dir.join(mod_string)).expect("create package");
+            LocalPackage {
+                mod_string: mod_string.into(),
+                hash: None,
+                installed_at: 0,
+                explicit,
+                deps: deps.iter().map(ToString::to_string).collect(),
+                mods: mods.iter().map(ToString::to_string).collect(),
+            }
+        };
+        for p in [
+            package("foo-app-1.0.0", true, &["foo-lib-1.0.0"], &["Foo.App"]),
+            package("foo-lib-1.0.0", false, &["foo-base-1.0.0"], &["Foo.Lib"]),
+            package("foo-base-1.0.0", false, &[], &[]),
+            package("foo-old-1.0.0", false, &[], &["Foo.Old"]),
+            package("foo-core-1.0.0", false, &[], &["Northstar.Custom"]),
+            package("foo-coop-1.0.0", false, &[], &["Northstar.Coop"]),
+        ] {
+            index.insert(p).expect("insert package");
+        }
+        // marked as core by its author file, like `is_core_mod` checks
+        let coop = dir.join("foo-coop-1.0.0/mods/Northstar.Coop");
+        fs::create_dir_all(&coop).expect("create dir");
+        fs::write(coop.join(AUTHOR_FILE), "northstar").expect("write author");
+        let mut enabled = EnabledMods::default();
+        enabled.set("Foo.Old", true);
+        enabled.set("Foo.Lib", true);
+
+        let removed = autoremove(&dir, &mut index, Some(&mut enabled)).expect("autoremove");
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].mod_string, "foo-old-1.0.0");
+        assert!(!dir.join("foo-old-1.0.0").exists());
+        assert!(dir.join("foo-core-1.0.0").exists());
+        assert!(dir.join("foo-coop-1.0.0").exists());
+        assert_eq!(enabled.get("Foo.Old"), None);
+
+        index.remove("foo-app");
+        let removed = autoremove(&dir, &mut index, Some(&mut enabled)).expect("autoremove");
+        assert_eq!(removed.len(), 2);
+        assert!(!dir.join("foo-lib-1.0.0").exists());
+        assert!(!dir.join("foo-base-1.0.0").exists());
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn autoremove_skips_invalid_modstrings() {
+        let dir = TempDir::create("./autoremove_invalid").expect("Unable to create temp dir");
+        fs::create_dir_all(dir.join("foo-evil-1.0.0")).expect("create package");
+        let mut index: LocalIndex = serde_json::from_str(
+            r#"{"packages": {"foo-evil": {
+                "modString": "foo-evil-1.0.0/..",
+                "hash": null,
+                "installedAt": 0,
+                "explicit": false
+            }}}"#,
+        )
+        .expect("parse index");
+
+        let removed = autoremove(&dir, &mut index, None).expect("autoremove");
+        assert!(removed.is_empty());
+        assert!(dir.exists());
+        assert_eq!(index.len(), 1);
     }
 
     #[test]
@@ -1,9 +1,36 @@
+pub mod doctor;
+pub mod edit;
+pub mod graph;
+pub mod http;
+pub mod locate;
 pub mod manage;
+pub mod profiles;
 #[allow(dead_code)]
 pub mod utils;
+pub mod validate;
+pub mod vdf;
 
 #[cfg(all(target_os = "linux", feature = "proton"))]
-pub use utils::proton::{download_ns_proton, install_ns_proton, latest_release};
+pub use utils::proton::{
+    download_ns_proton, install_ns_proton, install_ns_proton_to_steam, installed_ns_proton_versions,
+    installed_protons, latest_release, recommended_proton, remove_ns_proton, set_compat_tool,
+    set_compat_tool_in, ProtonInstall, ProtonKind, MIN_PROTON_VERSION,
+};
+#[cfg(feature = "ea")]
+pub use utils::ea::{
+    ea_dir, is_ea_titanfall2, titanfall2_ea_dir, titanfall2_in_prefix, titanfall2_wine_dir,
+    wine_prefixes,
+};
 #[cfg(feature = "steam")]
-pub use utils::steam::{steam_dir, steam_libraries, titanfall};
-pub use utils::{find_mods, get_enabled_mods, resolve_deps};
+pub use utils::steam::{
+    is_steam_running, local_configs, set_titanfall2_launch_options, steam_dir, steam_install,
+    steam_libraries, titanfall, titanfall2_compatdata, titanfall2_launch_options, titanfall2_prefix,
+    SteamFlavor,
+};
+pub use utils::{
+    detect_core_mods, discover_mods, find_all_mods, find_incompatibilities, find_mods,
+    find_mods_with, find_mods_with_state, find_orphans, find_plugins, get_enabled_mods,
+    get_enabled_mods_or_default, get_local_index, get_startup_args, is_core_mod, is_game_running,
+    mod_states, resolve_deps, resolve_deps_checked, resolve_deps_recursive, which_outdated,
+    write_atomic, write_atomic_with, DiscoveryReport, FindOptions, Incompatibility, GAME_PROCESSES,
+};
@@ -0,0 +1,291 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use tracing::{debug, trace};
+
+use crate::{
+    error::{Result, ThermiteError},
+    model::{EnabledMods, ServerConfig},
+};
+
+use super::utils::get_enabled_mods_or_default;
+
+/// The profile Northstar uses when launched without `-profile=`
+pub const DEFAULT_PROFILE: &str = "R2Northstar";
+/// The dedicated server config, relative to the profile directory
+pub const SERVER_CONFIG_FILE: &str = "mods/Northstar.CustomServers/mod/cfg/autoexec_ns_server.cfg";
+
+/// A Northstar profile, i.e. a directory in the game directory passed to the game with `-profile=`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    name: String,
+    path: PathBuf,
+}
+
+impl Profile {
+    /// Open an existing profile
+    ///
+    /// # Errors
+    /// * The name isn't a valid directory name
+    /// * The profile doesn't exist
+    pub fn open(game_path: impl AsRef<Path>, name: impl AsRef<str>) -> Result<Self> {
+        let name = validate_name(name.as_ref())?;
+        let path = game_path.as_ref().join(name);
+        if !path.is_dir() {
+            return Err(ThermiteError::MissingFile(Box::new(path)));
+        }
+
+        Ok(Self {
+            name: name.to_owned(),
+            path,
+        })
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    #[must_use]
+    pub fn mods_dir(&self) -> PathBuf {
+        self.path.join("mods")
+    }
+
+    #[must_use]
+    pub fn plugins_dir(&self) -> PathBuf {
+        self.path.join("plugins")
+    }
+
+    #[must_use]
+    pub fn enabled_mods_path(&self) -> PathBuf {
+        self.path.join("enabledmods.json")
+    }
+
+    /// Load the profile's `autoexec_ns_server.cfg`
+    ///
+    /// # Errors
+    /// * The file doesn't exist, i.e. N* isn't installed to this profile
+    pub fn server_config(&self) -> Result<ServerConfig> {
+        ServerConfig::load(self.path.join(SERVER_CONFIG_FILE))
+    }
+
+    /// `true` for `R2Northstar`, which is used when no profile is passed to the game
+    #[must_use]
+    pub fn is_default(&self) -> bool {
+        self.name == DEFAULT_PROFILE
+    }
+
+    /// Load the profile's `enabledmods.json`, or a default one if it doesn't exist yet, which is only
+    /// written to the profile when it's saved
+    ///
+    /// # Errors
+    /// * IO Errors
+    /// * The file isn't formatted properly
+    pub fn enabled_mods(&self) -> Result<EnabledMods> {
+        get_enabled_mods_or_default(&self.path, false)
+    }
+}
+
+/// Create a new, empty profile
+///
+/// # Errors
+/// * The name isn't a valid directory name
+/// * A profile with the same name already exists
+/// * IO Errors
+pub fn create_profile(game_path: impl AsRef<Path>, name: impl AsRef<str>) -> Result<Profile> {
+    let name = validate_name(name.as_ref())?;
+    let path = game_path.as_ref().join(name);
+    if path.try_exists()? {
+        return Err(ThermiteError::NameError(format!(
+            "profile {name} already exists"
+        )));
+    }
+
+    debug!("Creating profile at {}", path.display());
+    let profile = Profile {
+        name: name.to_owned(),
+        path,
+    };
+    fs::create_dir_all(profile.mods_dir())?;
+    fs::create_dir_all(profile.plugins_dir())?;
+
+    Ok(profile)
+}
+
+/// List every profile in the game directory, starting with the default profile
+///
+/// Any directory with a `mods` folder is considered a profile
+///
+/// # Errors
+/// * IO Errors
+pub fn list_profiles(game_path: impl AsRef<Path>) -> Result<Vec<Profile>> {
+    let mut profiles = vec![];
+    for child in game_path.as_ref().read_dir()? {
+        let child = child?;
+        let path = child.path();
+        if !(child.file_type()?.is_dir() && path.join("mods").is_dir()) {
+            continue;
+        }
+
+        let Some(name) = child.file_name().to_str().map(ToOwned::to_owned) else {
+            trace!("Skipping profile with non UTF-8 name {}", path.display());
+            continue;
+        };
+        profiles.push(Profile { name, path });
+    }
+
+    profiles.sort_by(|a, b| {
+        b.is_default()
+            .cmp(&a.is_default())
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    Ok(profiles)
+}
+
+/// Copy a profile, including its mods, plugins, and `enabledmods.json`
+///
+/// # Errors
+/// * The name isn't a valid directory name
+/// * A profile with the same name already exists
+/// * IO Errors
+pub fn clone_profile(profile: &Profile, name: impl AsRef<str>) -> Result<Profile> {
+    let game_path = profile.path.parent().ok_or_else(|| {
+        ThermiteError::UnknownError(format!(
+            "Profile {} isn't in a game directory",
+            profile.path.display()
+        ))
+    })?;
+    let name = validate_name(name.as_ref())?;
+    let path = game_path.join(name);
+    if path.try_exists()? {
+        return Err(ThermiteError::NameError(format!(
+            "profile {name} already exists"
+        )));
+    }
+
+    debug!(
+        "Copying profile {} to {}",
+        profile.path.display(),
+        path.display()
+    );
+    copy_dir(&profile.path, &path)?;
+
+    Ok(Profile {
+        name: name.to_owned(),
+        path,
+    })
+}
+
+/// Delete a profile and everything in it
+///
+/// # Errors
+/// * The profile is the default profile
+/// * IO Errors
+pub fn delete_profile(profile: Profile) -> Result<()> {
+    if profile.is_default() {
+        return Err(ThermiteError::UnknownError(
+            "Refusing to delete the default profile".into(),
+        ));
+    }
+
+    debug!("Deleting profile at {}", profile.path.display());
+    fs::remove_dir_all(&profile.path)?;
+
+    Ok(())
+}
+
+/// Make sure a profile name is a single directory name
+fn validate_name(name: &str) -> Result<&str> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(name),
+        _ => Err(ThermiteError::NameError(format!(
+            "{name:?} isn't a valid profile name"
+        ))),
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for child in from.read_dir()? {
+        let child = child?;
+        let dest = to.join(child.file_name());
+        if child.file_type()?.is_dir() {
+            copy_dir(&child.path(), &dest)?;
+        } else {
+            fs::copy(child.path(), dest)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::{core::utils::TempDir, error::ThermiteError};
+
+    use super::{
+        clone_profile, create_profile, delete_profile, list_profiles, Profile, DEFAULT_PROFILE,
+    };
+
+    #[test]
+    fn manage_profiles() {
+        let dir = TempDir::create("./manage_profiles").expect("Unable to create temp dir");
+        fs::create_dir_all(dir.join(DEFAULT_PROFILE).join("mods")).expect("create dir");
+        fs::create_dir_all(dir.join("vpk")).expect("create dir");
+
+        let profile = create_profile(&dir, "Dev").expect("create profile");
+        assert!(profile.mods_dir().is_dir());
+        assert!(profile.plugins_dir().is_dir());
+        assert!(matches!(
+            create_profile(&dir, "Dev"),
+            Err(ThermiteError::NameError(_))
+        ));
+
+        let mut enabled = profile.enabled_mods().expect("enabled mods");
+        enabled.set("Foo.Bar", false);
+        enabled.save().expect("save enabled mods");
+        drop(enabled);
+
+        let clone = clone_profile(&profile, "Clone").expect("clone profile");
+        assert_eq!(
+            clone.enabled_mods().expect("enabled mods").get("Foo.Bar"),
+            Some(false)
+        );
+
+        let names = list_profiles(&dir)
+            .expect("list profiles")
+            .into_iter()
+            .map(|p| p.name().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(names, [DEFAULT_PROFILE, "Clone", "Dev"]);
+
+        delete_profile(clone).expect("delete profile");
+        assert!(!dir.join("Clone").exists());
+
+        let default = Profile::open(&dir, DEFAULT_PROFILE).expect("open profile");
+        assert!(default.is_default());
+        assert!(delete_profile(default).is_err());
+    }
+
+    #[test]
+    fn fail_invalid_profile_name() {
+        let dir = TempDir::create("./invalid_profile_name").expect("Unable to create temp dir");
+        for name in ["", "..", "a/b", "/abs"] {
+            assert!(
+                matches!(create_profile(&dir, name), Err(ThermiteError::NameError(_))),
+                "{name} should be invalid"
+            );
+        }
+    }
+}
@@ -1,36 +1,118 @@
+use crate::core::graph::DepGraph;
+use crate::core::manage::{read_northstar_record, AUTHOR_FILE};
 use crate::error::ThermiteError;
+use crate::model::serialize_path;
+use crate::model::BareMod;
 use crate::model::EnabledMods;
 use crate::model::InstalledMod;
+use crate::model::LaunchMode;
+use crate::model::LocalIndex;
+use crate::model::LocalPackage;
 use crate::model::Manifest;
 use crate::model::Mod;
+use crate::model::ModEntry;
+use crate::model::ModJSON;
+use crate::model::ModName;
+use crate::model::ModState;
+use crate::model::ModString;
+use crate::model::ModVersion;
+use crate::model::Outdated;
+use crate::model::PackageIndex;
+use crate::model::Plugin;
+use crate::model::Side;
+use crate::model::StartupArgs;
+use crate::model::Version;
+use crate::CORE_MODS;
 
 use regex::Regex;
-use std::fmt::Debug;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::fmt::{Debug, Display, Formatter};
 use std::fs;
+use std::io;
 use std::ops::Deref;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
 use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use tracing::trace;
 use tracing::{debug, error};
 
-pub(crate) type ModString = (String, String, String);
-
-#[derive(Debug, Clone)]
-pub(crate) struct TempDir {
-    pub(crate) path: PathBuf,
+/// A directory that's removed, along with everything in it, when dropped
+///
+/// Useful for staging files before moving them into place, e.g. extracting a package before
+/// replacing the installed version. Call [`TempDir::keep`] or [`TempDir::into_path`] to keep it.
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+    keep: bool,
 }
 
 impl TempDir {
+    /// Create a new directory at `path`, including any missing parents
+    ///
+    /// The directory must not exist yet, since everything in it is removed on drop
+    ///
     /// # Errors
+    /// - `path` already exists
     /// - IO errors
     pub fn create(path: impl AsRef<Path>) -> Result<Self, std::io::Error> {
-        fs::create_dir_all(path.as_ref())?;
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::create_dir(path)?;
         Ok(TempDir {
-            path: path.as_ref().to_path_buf(),
+            path: path.to_path_buf(),
+            keep: false,
         })
     }
+
+    /// Create a directory with a unique name inside `parent`
+    ///
+    /// # Errors
+    /// - IO errors
+    pub fn new_in(parent: impl AsRef<Path>) -> Result<Self, std::io::Error> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        fs::create_dir_all(parent.as_ref())?;
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.subsec_nanos());
+        loop {
+            let name = format!(
+                ".thermite-{}-{nanos:x}-{}",
+                std::process::id(),
+                COUNTER.fetch_add(1, AtomicOrdering::Relaxed)
+            );
+            let path = parent.as_ref().join(name);
+            match fs::create_dir(&path) {
+                Ok(()) => return Ok(TempDir { path, keep: false }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Don't remove the directory when dropped
+    pub fn keep(&mut self) {
+        self.keep = true;
+    }
+
+    /// Keep the directory and return its path
+    #[must_use]
+    pub fn into_path(mut self) -> PathBuf {
+        self.keep = true;
+        std::mem::take(&mut self.path)
+    }
 }
 
 impl AsRef<Path> for TempDir {
@@ -49,12 +131,53 @@ impl Deref for TempDir {
 
 impl Drop for TempDir {
     fn drop(&mut self) {
-        if let Err(e) = fs::remove_dir_all(&self.path) {
-            error!(
-                "Error removing temp directory at '{}': {}",
-                self.path.display(),
-                e
-            );
+        if self.keep {
+            return;
+        }
+
+        // entries can disappear while the tree is being removed, so retry once before giving up
+        let mut res = fs::remove_dir_all(&self.path);
+        if res.is_err() && self.path.exists() {
+            res = fs::remove_dir_all(&self.path);
+        }
+
+        match res {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                error!(
+                    "Error removing temp directory at '{}': {}",
+                    self.path.display(),
+                    e
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Compares two version strings as [`Version`]s, so `0.10.0` is newer than `0.9.0`
+pub(crate) fn compare_versions(a: &str, b: &str) -> Ordering {
+    Version::new(a).cmp(&Version::new(b))
+}
+
+/// Compares two version strings by their numeric components, for versions that aren't semver.
+/// Components that aren't numbers are compared as strings.
+pub(crate) fn compare_components(a: &str, b: &str) -> Ordering {
+    let mut a_parts = a.split('.');
+    let mut b_parts = b.split('.');
+    loop {
+        match (a_parts.next(), b_parts.next()) {
+            (None, None) => return Ordering::Equal,
+            (Some(_), None) => return Ordering::Greater,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(a), Some(b)) => {
+                let ord = match (a.parse::<u64>(), b.parse::<u64>()) {
+                    (Ok(a), Ok(b)) => a.cmp(&b),
+                    _ => a.cmp(b),
+                };
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
         }
     }
 }
@@ -74,12 +197,13 @@ pub fn resolve_deps(deps: &[impl AsRef<str>], index: &[Mod]) -> Result<Vec<Mod>,
             .nth(1)
             .ok_or_else(|| ThermiteError::DepError(dep.as_ref().into()))?;
 
-        if dep_name.to_lowercase() == "northstar" {
+        if dep_name.eq_ignore_ascii_case("northstar") {
             debug!("Skip unfiltered Northstar dependency");
             continue;
         }
 
-        if let Some(d) = index.iter().find(|f| f.name == dep_name) {
+        let dep_name = ModName::new(dep_name);
+        if let Some(d) = index.iter().find(|f| dep_name == f.name.as_str()) {
             valid.push(d.clone());
         } else {
             return Err(ThermiteError::DepError(dep.as_ref().into()));
@@ -88,284 +212,2720 @@ pub fn resolve_deps(deps: &[impl AsRef<str>], index: &[Mod]) -> Result<Vec<Mod>,
     Ok(valid)
 }
 
+/// Resolves the full set of dependencies of `mods`, returning them as an install plan where
+/// every package comes after the packages it depends on. The packages in `mods` are included.
+///
+/// When several packages depend on different versions of the same package, the highest
+/// version is chosen.
+///
+/// # Errors
+/// - A dependency string isn't formatted like `author-name-X.Y.Z`
+/// - A dependency or the required version of it isn't present in the index
+/// - The dependencies contain a cycle
+pub fn resolve_deps_recursive(
+    mods: &[ModVersion],
+    index: &[Mod],
+) -> Result<Vec<ModVersion>, ThermiteError> {
+    let graph = DepGraph::new(mods, index)?;
+    let plan = graph.install_order()?;
+
+    Ok(plan.into_iter().cloned().collect())
+}
+
+/// Like [`resolve_deps_recursive`], but refuses to return a plan that would install a package one of
+/// the `installed` mods declares it's incompatible with
+///
+/// # Errors
+/// - Any of the errors from [`resolve_deps_recursive`]
+/// - An installed mod is incompatible with a package in the plan. Use [`find_incompatibilities`] on
+///   the plan instead to only warn about it
+pub fn resolve_deps_checked(
+    mods: &[ModVersion],
+    index: &[Mod],
+    installed: &[InstalledMod],
+) -> Result<Vec<ModVersion>, ThermiteError> {
+    let plan = resolve_deps_recursive(mods, index)?;
+    if let Some(conflict) = find_incompatibilities(&plan, installed).into_iter().next() {
+        return Err(conflict.into());
+    }
+
+    Ok(plan)
+}
+
+/// An installed package that declares it can't be installed alongside another package
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Incompatibility {
+    /// Modstring of the installed package
+    pub installed: String,
+    /// Modstring of the package it's incompatible with
+    pub package: String,
+}
+
+impl Display for Incompatibility {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is incompatible with {}",
+            self.installed, self.package
+        )
+    }
+}
+
+impl From<Incompatibility> for ThermiteError {
+    fn from(value: Incompatibility) -> Self {
+        Self::Incompatible {
+            installed: value.installed,
+            package: value.package,
+        }
+    }
+}
+
+/// Find installed mods that declare they're incompatible with a package in `plan`
+///
+/// Manifests name incompatible packages, which are matched against the plan directly. A `mod.json`
+/// names incompatible mods, which are matched against the mods of planned packages that are already
+/// installed, e.g. ones being updated, since the mods in a package aren't known until it's downloaded.
+///
+/// Mods from a package that's also in `plan` are skipped, since installing the plan replaces them
+#[must_use]
+pub fn find_incompatibilities(
+    plan: &[ModVersion],
+    installed: &[InstalledMod],
+) -> Vec<Incompatibility> {
+    let planned = plan
+        .iter()
+        .map(|v| package_key(&v.full_name))
+        .collect::<BTreeSet<_>>();
+    let planned_mods = |v: &ModVersion| {
+        let key = package_key(&v.full_name);
+        installed
+            .iter()
+            .filter(move |m| dep_key(&m.author, &m.manifest.name) == key)
+            .map(|m| m.mod_json.name.as_str())
+    };
+
+    let mut found = BTreeSet::new();
+    for m in installed {
+        if planned.contains(&dep_key(&m.author, &m.manifest.name)) {
+            continue;
+        }
+        for v in plan.iter().filter(|v| {
+            m.is_incompatible_with(&v.full_name)
+                || planned_mods(v).any(|name| m.is_incompatible_with_mod(name))
+        }) {
+            found.insert(Incompatibility {
+                installed: m.full_name(),
+                package: v.full_name.clone(),
+            });
+        }
+    }
+
+    found.into_iter().collect()
+}
+
+pub(crate) fn dep_key(author: &str, name: &str) -> String {
+    format!("{author}-{name}").to_lowercase()
+}
+
+/// Normalize `author-name` or `author-name-X.Y.Z` to the key used by [`dep_key`]
+pub(crate) fn package_key(package: &str) -> String {
+    parse_modstring(package).map_or_else(
+        |_| package.to_lowercase(),
+        |(author, name, _)| dep_key(&author, &name),
+    )
+}
+
+/// Parses a dependency string into its key and version, or `None` if it's Northstar itself
+pub(crate) fn parse_dep(dep: &str) -> Result<Option<(String, String)>, ThermiteError> {
+    let (author, name, version) =
+        parse_modstring(dep).map_err(|_| ThermiteError::DepError(dep.into()))?;
+    if name.eq_ignore_ascii_case("northstar") {
+        debug!("Skip unfiltered Northstar dependency");
+        return Ok(None);
+    }
+
+    Ok(Some((dep_key(&author, &name), version)))
+}
+
 /// Get `enabledmods.json` from the given directory, if it exists
 ///
+/// The core mods are the ones found in the directory's `mods` folder like [`detect_core_mods`] finds
+/// them, or [`EnabledMods::DEFAULT_CORE_MODS`] if there aren't any
+///
 /// # Errors
 /// - The path cannot be canonicalized (broken symlinks)
 /// - The path is not a directory
 /// - There is no `enabledmods.json` file in the provided directory
 pub fn get_enabled_mods(dir: impl AsRef<Path>) -> Result<EnabledMods, ThermiteError> {
-    let path = dir.as_ref().canonicalize()?.join("enabledmods.json");
+    let dir = dir.as_ref().canonicalize()?;
+    let path = dir.join("enabledmods.json");
     if path.exists() {
         let raw = fs::read_to_string(&path)?;
         let mut mods: EnabledMods = serde_json::from_str(&raw)?;
         mods.set_path(path);
+        apply_core_mods(&mut mods, &dir);
         Ok(mods)
     } else {
         Err(ThermiteError::MissingFile(Box::new(path)))
     }
 }
 
-/// Search a directory for mod.json files in its children
+/// Use the core mods found in the profile's `mods` folder, if there are any
+fn apply_core_mods(mods: &mut EnabledMods, profile: &Path) {
+    // the Northstar record only lists the files in `R2Northstar`
+    let recorded = profile
+        .file_name()
+        .filter(|name| name.eq_ignore_ascii_case("R2Northstar"))
+        .and_then(|_| profile.parent())
+        .and_then(|game| read_northstar_record(game).ok().flatten())
+        .map(|record| {
+            record
+                .files
+                .into_iter()
+                .filter_map(|f| record_core_folder(&f.path))
+                .collect::<HashSet<_>>()
+        })
+        .unwrap_or_default();
+
+    match profile_core_mods(profile, &recorded) {
+        Ok(core) if !core.is_empty() => mods.set_core_mods(core),
+        Ok(_) => {}
+        Err(e) => debug!("Unable to detect core mods in {}: {e}", profile.display()),
+    }
+}
+
+/// The lowercase mod folder a file from the Northstar record is in, e.g. `northstar.client`
+fn record_core_folder(path: &str) -> Option<String> {
+    let mut parts = path.strip_prefix("R2Northstar/mods/")?.split('/');
+    let folder = parts.next()?;
+    parts.next().map(|_| folder.to_lowercase())
+}
+
+/// Same as [`get_enabled_mods`], but a missing file isn't an error
 ///
-/// Searches one level deep
+/// Returns a default `EnabledMods` with its path set instead. Unless `create` is set, it's only
+/// written when it's saved, or dropped after [`EnabledMods::do_save`]
+///
+/// # Params
+/// * `dir` - the profile directory containing `enabledmods.json`
+/// * `create` - write the default file right away, and again when it's dropped
 ///
 /// # Errors
-/// - The path cannot be canonicalized
-/// - IO Errors
-/// - Improperly formatted JSON files
-pub fn find_mods(dir: impl AsRef<Path>) -> Result<Vec<InstalledMod>, ThermiteError> {
-    let mut res = vec![];
-    let dir = dir.as_ref().canonicalize()?;
-    debug!("Finding mods in '{}'", dir.display());
-    for child in dir.read_dir()? {
-        let child = child?;
-        if !child.file_type()?.is_dir() {
-            debug!("Skipping file {}", child.path().display());
-            continue;
+/// - The path cannot be canonicalized (broken symlinks)
+/// - The file isn't formatted properly
+/// - IO Errors while creating the file
+pub fn get_enabled_mods_or_default(
+    dir: impl AsRef<Path>,
+    create: bool,
+) -> Result<EnabledMods, ThermiteError> {
+    match get_enabled_mods(dir) {
+        Err(ThermiteError::MissingFile(path)) => {
+            debug!("Using default enabled mods for {}", path.display());
+            let mut mods = EnabledMods::default_with_path(&*path);
+            if let Some(profile) = path.parent() {
+                apply_core_mods(&mut mods, profile);
+            }
+            if create {
+                mods.save()?;
+            } else {
+                mods.dont_save();
+            }
+            Ok(mods)
         }
+        res => res,
+    }
+}
 
-        let path = child.path().join("manifest.json");
-        let manifest = if path.try_exists()? {
-            let raw = fs::read_to_string(&path)?;
-            let Ok(parsed) = serde_json::from_str(&raw) else {
-                error!("Error parsing {}", path.display());
-                continue;
-            };
-            parsed
-        } else {
+/// Record of the packages thermite installed, in the packages directory
+pub const LOCAL_INDEX_FILE: &str = "thermite_index.json";
+
+/// Get the [`LocalIndex`] from a packages directory, e.g. `R2Northstar/packages`
+///
+/// Returns an empty index that will be saved to the directory if it doesn't exist yet
+///
+/// # Errors
+/// - IO Errors
+/// - The file isn't formatted properly
+pub fn get_local_index(dir: impl AsRef<Path>) -> Result<LocalIndex, ThermiteError> {
+    let path = dir.as_ref().join(LOCAL_INDEX_FILE);
+    if path.try_exists()? {
+        LocalIndex::load(path)
+    } else {
+        Ok(LocalIndex::default_with_path(path))
+    }
+}
+
+/// Find the packages that were only installed as dependencies and aren't needed by any explicitly
+/// installed package anymore, e.g. because their dependents were uninstalled
+///
+/// Packages with dependency strings that can't be parsed are treated as having no dependencies
+#[must_use]
+pub fn find_orphans(local_index: &LocalIndex) -> Vec<&LocalPackage> {
+    let mut needed = HashSet::new();
+    let mut queue = local_index
+        .iter()
+        .filter(|p| p.explicit)
+        .map(|p| package_key(&p.mod_string))
+        .collect::<VecDeque<_>>();
+    while let Some(key) = queue.pop_front() {
+        let Some(package) = local_index.get(&key) else {
             continue;
         };
+        if !needed.insert(key) {
+            continue;
+        }
 
-        if let Some(submods) = get_submods(&manifest, child.path()) {
-            debug!(
-                "Found {} submods in {}",
-                submods.len(),
-                child.path().display()
-            );
-            trace!("{:#?}", submods);
-            let modstring =
-                parse_modstring(child.file_name().to_str().ok_or(ThermiteError::UTF8Error)?)?;
-            res.append(
-                &mut submods
-                    .into_iter()
-                    .map(|mut m| {
-                        m.author.clone_from(&modstring.0);
-
-                        m
-                    })
-                    .collect(),
-            );
-        } else {
-            debug!("No mods in {}", child.path().display());
+        for dep in &package.deps {
+            match parse_dep(dep) {
+                Ok(Some((dep_key, _))) => queue.push_back(dep_key),
+                Ok(None) => {}
+                Err(e) => trace!("Ignoring dependency of {}: {e}", package.mod_string),
+            }
         }
     }
 
-    Ok(res)
+    local_index
+        .iter()
+        .filter(|p| !needed.contains(&package_key(&p.mod_string)))
+        .collect()
 }
 
-fn get_submods(manifest: &Manifest, dir: impl AsRef<Path>) -> Option<Vec<InstalledMod>> {
-    let dir = dir.as_ref();
-    debug!("Searching for submods in {}", dir.display());
-    if !dir.is_dir() {
-        debug!("Wasn't a directory, aborting");
-        return None;
-    }
+/// Startup arguments for the game client, in the game directory
+pub const STARTUP_ARGS_FILE: &str = "ns_startup_args.txt";
+/// Startup arguments for dedicated servers, in the game directory
+pub const STARTUP_ARGS_DEDI_FILE: &str = "ns_startup_args_dedi.txt";
 
-    let mut mods = vec![];
-    for child in dir.read_dir().ok()? {
-        let Ok(child) = child else { continue };
-        match child.file_type() {
-            Ok(ty) => {
-                if ty.is_dir() {
-                    let Some(mut next) = get_submods(manifest, child.path()) else {
-                        continue;
-                    };
-                    mods.append(&mut next);
-                } else {
-                    trace!("Is file {:?} mod.json?", child.file_name());
-                    if child.file_name() == "mod.json" {
-                        trace!("Yes");
-                        let Ok(file) = fs::read_to_string(child.path()) else {
-                            continue;
-                        };
-                        match json5::from_str(&file) {
-                            Ok(mod_json) => mods.push(InstalledMod {
-                                author: String::new(),
-                                manifest: manifest.clone(),
-                                mod_json,
-                                path: dir.to_path_buf(),
-                            }),
-                            Err(e) => {
-                                error!("Error parsing JSON in {}: {e}", child.path().display());
-                            }
-                        }
-                    } else {
-                        trace!("No");
-                    }
-                }
-            }
-            Err(e) => {
-                error!("Error {e}");
-            }
-        }
-    }
+/// Get `ns_startup_args.txt`, or `ns_startup_args_dedi.txt` if `dedicated` is set, from the game directory
+///
+/// Returns empty arguments that will be saved to the right file if it doesn't exist yet
+///
+/// # Errors
+/// - IO Errors
+pub fn get_startup_args(
+    game_path: impl AsRef<Path>,
+    dedicated: bool,
+) -> Result<StartupArgs, ThermiteError> {
+    let path = game_path.as_ref().join(if dedicated {
+        STARTUP_ARGS_DEDI_FILE
+    } else {
+        STARTUP_ARGS_FILE
+    });
 
-    if mods.is_empty() {
-        None
+    if path.try_exists()? {
+        StartupArgs::load(path)
     } else {
-        Some(
-            mods.into_iter()
-                .map(|mut m| {
-                    if m.path.ends_with("/mods") {
-                        m.path.pop();
-                    }
-
-                    m
-                })
-                .collect(),
-        )
+        Ok(StartupArgs::default_with_path(path))
     }
 }
 
-pub static RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^(\w+)-(\w+)-(\d+\.\d+\.\d+)$").expect("regex"));
+/// Toggle read by setups that replace `Titanfall2.exe`, containing `1` to start Northstar or `0` for vanilla
+pub const RUN_NORTHSTAR_FILE: &str = "run_northstar.txt";
 
-/// Returns the parts of a `author-name-X.Y.Z` string in (`author`, `name`, `version`) order
+/// Read the launch mode from `run_northstar.txt` in the game directory
 ///
-/// # Errors
+/// Returns `None` if the file doesn't exist
 ///
-/// Returns a `NameError` if the input string is not in the correct format
-pub fn parse_modstring(input: impl AsRef<str>) -> Result<ModString, ThermiteError> {
-    debug!("Parsing modstring {}", input.as_ref());
-    if let Some(captures) = RE.captures(input.as_ref()) {
-        let author = captures
-            .get(1)
-            .ok_or_else(|| ThermiteError::NameError(input.as_ref().into()))?
-            .as_str()
-            .to_owned();
-
-        let name = captures
-            .get(2)
-            .ok_or_else(|| ThermiteError::NameError(input.as_ref().into()))?
-            .as_str()
-            .to_owned();
-
-        let version = captures
-            .get(3)
-            .ok_or_else(|| ThermiteError::NameError(input.as_ref().into()))?
-            .as_str()
-            .to_owned();
-
-        Ok((author, name, version))
-    } else {
-        Err(ThermiteError::NameError(input.as_ref().into()))
+/// # Errors
+/// - IO Errors
+pub fn get_launch_mode(game_path: impl AsRef<Path>) -> Result<Option<LaunchMode>, ThermiteError> {
+    let path = game_path.as_ref().join(RUN_NORTHSTAR_FILE);
+    if !path.try_exists()? {
+        return Ok(None);
     }
+
+    let raw = fs::read_to_string(path)?;
+    Ok(Some(if raw.trim() == "0" {
+        LaunchMode::Vanilla
+    } else {
+        LaunchMode::Northstar
+    }))
 }
 
-/// Checks that a string is in `author-name-X.Y.Z` format
-#[inline]
+/// Write the launch mode to `run_northstar.txt` in the game directory
+///
+/// # Errors
+/// - IO Errors
+pub fn set_launch_mode(game_path: impl AsRef<Path>, mode: LaunchMode) -> Result<(), ThermiteError> {
+    let contents = match mode {
+        LaunchMode::Vanilla => "0",
+        LaunchMode::Northstar => "1",
+    };
+
+    write_atomic(game_path.as_ref().join(RUN_NORTHSTAR_FILE), contents)
+}
+
+/// Checks if a game's launch options start Northstar, either by running `NorthstarLauncher.exe`
+/// directly or passing `-northstar`
 #[must_use]
-pub fn validate_modstring(input: impl AsRef<str>) -> bool {
-    RE.is_match(input.as_ref())
+pub fn launch_options_mode(options: impl AsRef<str>) -> LaunchMode {
+    let options = options.as_ref();
+    let northstar = runs_launcher(options)
+        || options
+            .split_whitespace()
+            .any(|arg| arg == LaunchMode::Northstar.launch_arg());
+
+    if northstar {
+        LaunchMode::Northstar
+    } else {
+        LaunchMode::Vanilla
+    }
 }
 
-#[cfg(feature = "steam")]
-pub(crate) mod steam {
-    use std::path::PathBuf;
-    use steamlocate::SteamDir;
+/// Set the launch mode in a game's launch options, replacing any existing `-northstar` or `-vanilla`
+///
+/// Launch options that run `NorthstarLauncher.exe` directly are left as is, since they can't start vanilla.
+/// Everything else, including spacing and quoted arguments, is kept as it was
+#[must_use]
+pub fn set_launch_options_mode(options: impl AsRef<str>, mode: LaunchMode) -> String {
+    let options = options.as_ref();
+    if runs_launcher(options) {
+        return options.to_owned();
+    }
 
-    use crate::TITANFALL2_STEAM_ID;
+    let args = [LaunchMode::Vanilla, LaunchMode::Northstar].map(LaunchMode::launch_arg);
+    let mut out = String::with_capacity(options.len() + mode.launch_arg().len() + 1);
+    let mut last = 0;
+    for range in arg_ranges(options) {
+        if !args.contains(&&options[range.clone()]) {
+            continue;
+        }
 
-    /// Returns the path to the Steam installation if it exists
-    #[must_use]
-    #[inline]
-    pub fn steam_dir() -> Option<PathBuf> {
-        SteamDir::locate().map(|v| v.path)
+        // drop the argument along with the whitespace before it, or after it if it's the first one
+        let kept = options[last..range.start].trim_end();
+        out.push_str(kept);
+        last = range.end;
+        if out.is_empty() {
+            last += options[last..].len() - options[last..].trim_start().len();
+        }
     }
+    out.push_str(&options[last..]);
 
-    /// Returns paths to all known Steam libraries
-    #[must_use]
-    pub fn steam_libraries() -> Option<Vec<PathBuf>> {
-        let mut steamdir = SteamDir::locate()?;
-        let folders = steamdir.libraryfolders();
-        Some(folders.paths.clone())
+    if !out.is_empty() && !out.ends_with(char::is_whitespace) {
+        out.push(' ');
     }
+    out.push_str(mode.launch_arg());
 
-    /// Returns the path to the Titanfall installation if it exists
-    #[must_use]
-    pub fn titanfall() -> Option<PathBuf> {
-        let mut steamdir = SteamDir::locate()?;
-        Some(steamdir.app(&TITANFALL2_STEAM_ID)?.path.clone())
+    out
+}
+
+fn runs_launcher(options: &str) -> bool {
+    options.to_lowercase().contains("northstarlauncher")
+}
+
+/// The byte ranges of the whitespace separated arguments in launch options, keeping quoted
+/// arguments whole
+fn arg_ranges(options: &str) -> Vec<std::ops::Range<usize>> {
+    let mut ranges = vec![];
+    let mut start = None;
+    let mut quoted = false;
+    for (i, c) in options.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        if c.is_whitespace() && !quoted {
+            if let Some(start) = start.take() {
+                ranges.push(start..i);
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(start) = start {
+        ranges.push(start..options.len());
     }
+
+    ranges
 }
 
-#[cfg(all(target_os = "linux", feature = "proton"))]
-//#[deprecated(since = "0.8.0", note = "Northstar Proton is no longer required")]
-pub(crate) mod proton {
-    use flate2::read::GzDecoder;
-    use std::{
-        io::{Read, Write},
-        path::Path,
-    };
-    use tar::Archive;
-    use tracing::debug;
+/// Write to a temporary file next to `path` and rename it into place, so `path` is never left half written
+///
+/// The file is flushed to disk before it's renamed. Use [`write_atomic_with`] to skip that for files
+/// that are cheap to lose, like caches.
+///
+/// # Errors
+/// - IO Errors
+pub fn write_atomic(
+    path: impl AsRef<Path>,
+    contents: impl AsRef<[u8]>,
+) -> Result<(), ThermiteError> {
+    write_atomic_with(path, contents, true)
+}
 
-    use crate::{
-        core::manage::download,
-        error::{Result, ThermiteError},
+/// Write to a temporary file next to `path` and rename it into place
+///
+/// # Params
+/// * `path` - the file to replace
+/// * `contents` - the new contents of the file
+/// * `sync` - flush the file to disk before renaming it, and the directory after, so a crash or power
+///   loss leaves either the old or the new contents
+///
+/// # Errors
+/// - IO Errors
+pub fn write_atomic_with(
+    path: impl AsRef<Path>,
+    contents: impl AsRef<[u8]>,
+    sync: bool,
+) -> Result<(), ThermiteError> {
+    let path = path.as_ref();
+    let name = path
+        .file_name()
+        .ok_or_else(|| ThermiteError::MissingFile(Box::new(path.to_path_buf())))?;
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(name);
+    tmp_name.push(".tmp");
+    let tmp = path.with_file_name(tmp_name);
+
+    if let Some(p) = path.parent() {
+        fs::create_dir_all(p)?;
+    }
+    let res = fs::File::create(&tmp)
+        .and_then(|mut f| {
+            std::io::Write::write_all(&mut f, contents.as_ref())?;
+            if sync {
+                f.sync_all()?;
+            }
+            Ok(())
+        })
+        .and_then(|()| fs::rename(&tmp, path));
+    if res.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    res?;
+
+    // directories can't be opened as files on Windows, and the rename is already durable there
+    #[cfg(unix)]
+    if sync {
+        let parent = path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        if let Err(e) = fs::File::open(parent).and_then(|d| d.sync_all()) {
+            debug!("Unable to sync {}: {e}", parent.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that files can be created in `dir`, or in the closest parent of it that exists, before
+/// starting an install that would otherwise fail halfway through
+///
+/// Installs under `Program Files` on Windows can only be written to with administrator permissions
+///
+/// # Errors
+/// - `ThermiteError::NeedsElevation` if permission to write was denied
+/// - IO Errors
+pub fn check_write_access(dir: impl AsRef<Path>) -> Result<(), ThermiteError> {
+    let Some(existing) = dir.as_ref().ancestors().find(|d| d.is_dir()) else {
+        return Ok(());
     };
-    const BASE_URL: &str = "https://github.com/R2NorthstarTools/NorthstarProton/releases/";
+    let probe = existing.join(format!(".thermite-write-check-{}", std::process::id()));
+    match fs::File::create(&probe) {
+        Ok(_) => {
+            let _ = fs::remove_file(&probe);
+            Ok(())
+        }
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            Err(ThermiteError::NeedsElevation(existing.to_path_buf()))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
 
-    /// Returns the latest tag from the NorthstarProton repo
-    ///
-    /// # Errors
-    /// * Network error
-    /// * Unexpected URL format
-    pub fn latest_release() -> Result<String> {
-        let url = format!("{}latest", BASE_URL);
-        let res = ureq::get(&url).call()?;
-        let location = res.get_url();
-        debug!("{url} redirected to {location}");
+/// `path` made absolute with the `\\?\` prefix on Windows, which lifts the `MAX_PATH` limit that
+/// deeply nested mod files can run into. Other platforms don't have the limit, so `path` is
+/// returned as is
+///
+/// Extended paths aren't normalized by Windows, so only join components onto them, not strings
+/// containing `/`
+pub(crate) fn extended_path(path: &Path) -> PathBuf {
+    #[cfg(windows)]
+    {
+        use std::path::{Component, Prefix};
 
-        Ok(location
-            .split('/')
-            .last()
+        let Ok(absolute) = std::path::absolute(path) else {
+            return path.to_path_buf();
+        };
+        let Some(Component::Prefix(prefix)) = absolute.components().next() else {
+            return absolute;
+        };
+        let mut extended = match prefix.kind() {
+            Prefix::Disk(_) => std::ffi::OsString::from(r"\\?\"),
+            // \\server\share becomes \\?\UNC\server\share
+            Prefix::UNC(..) => {
+                let mut extended = std::ffi::OsString::from(r"\\?\UNC");
+                extended.push(&absolute.as_os_str().to_string_lossy()[1..]);
+                return PathBuf::from(extended);
+            }
+            _ => return absolute,
+        };
+        extended.push(absolute.as_os_str());
+        PathBuf::from(extended)
+    }
+    #[cfg(not(windows))]
+    {
+        path.to_path_buf()
+    }
+}
+
+/// Options for [`find_mods_with`]
+///
+/// The defaults match [`find_mods`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FindOptions {
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+    include_disabled: bool,
+    side: Option<Side>,
+}
+
+impl Default for FindOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: None,
+            follow_symlinks: false,
+            include_disabled: true,
+            side: None,
+        }
+    }
+}
+
+impl FindOptions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many folders below a package to look for `mod.json` files, e.g. `2` for `package/mods/Mod/mod.json`.
+    /// Unlimited by default
+    #[must_use]
+    pub const fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Follow symlinked packages and folders, e.g. for mods linked in from a development checkout
+    #[must_use]
+    pub const fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Include mods disabled in the `enabledmods.json` next to the searched directory. Defaults to `true`
+    #[must_use]
+    pub const fn include_disabled(mut self, include: bool) -> Self {
+        self.include_disabled = include;
+        self
+    }
+
+    /// Only include mods needed on this side, e.g. `Side::Server` to skip client-only mods on a
+    /// dedicated server. All mods are included by default
+    #[must_use]
+    pub const fn side(mut self, side: Side) -> Self {
+        self.side = Some(side);
+        self
+    }
+}
+
+/// Find the installed packages that have a newer version in the index
+///
+/// Mods from the same package are only reported once, and packages that aren't in the index are ignored
+#[must_use]
+pub fn which_outdated(installed: &[InstalledMod], index: &PackageIndex) -> Vec<Outdated> {
+    let mut outdated: Vec<Outdated> = vec![];
+    for m in installed {
+        let (author, name) = (
+            ModName::new(m.author.as_str()),
+            ModName::new(m.manifest.name.as_str()),
+        );
+        if m.version.is_empty()
+            || outdated
+                .iter()
+                .any(|o| author == o.author.as_str() && name == o.name.as_str())
+        {
+            continue;
+        }
+
+        let Some(latest) = index.get(&author, &name).and_then(Mod::get_latest) else {
+            continue;
+        };
+
+        if compare_versions(&latest.version, &m.version) == Ordering::Greater {
+            outdated.push(Outdated {
+                author: m.author.clone(),
+                name: m.manifest.name.clone(),
+                installed: m.version.clone(),
+                latest: latest.clone(),
+            });
+        }
+    }
+
+    outdated
+}
+
+/// Combine the index with the installed mods, producing the local state of every package in the index
+///
+/// Installed mods are matched to packages by author and manifest name, and mods from packages that aren't
+/// in the index are left out
+#[must_use]
+pub fn mod_states(index: &PackageIndex, installed: &[InstalledMod]) -> Vec<ModState> {
+    index
+        .iter()
+        .map(|m| {
+            let key = dep_key(&m.author, &m.name);
+            ModState {
+                remote: m.clone().into(),
+                installed: installed
+                    .iter()
+                    .filter(|i| dep_key(&i.author, &i.manifest.name) == key)
+                    .cloned()
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Search a directory for mod.json files in its children
+///
+/// Searches one level deep
+///
+/// # Errors
+/// - The path cannot be canonicalized
+/// - IO Errors reading the directory itself
+pub fn find_mods(dir: impl AsRef<Path>) -> Result<Vec<InstalledMod>, ThermiteError> {
+    find_mods_with(dir, &FindOptions::default())
+}
+
+/// Same as [`find_mods`], using the provided options
+///
+/// Folders that can't be read are logged and skipped, use [`discover_mods`] to get them as well
+///
+/// # Errors
+/// - The path cannot be canonicalized
+/// - IO Errors reading the directory itself
+pub fn find_mods_with(
+    dir: impl AsRef<Path>,
+    options: &FindOptions,
+) -> Result<Vec<InstalledMod>, ThermiteError> {
+    let report = discover_mods(dir, options)?;
+    for problem in &report.problems {
+        error!("{problem}");
+    }
+
+    Ok(report.mods)
+}
+
+/// Mods found by [`discover_mods`], along with everything that was skipped because it couldn't be read
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct DiscoveryReport {
+    pub mods: Vec<InstalledMod>,
+    pub problems: Vec<DiscoveryProblem>,
+}
+
+impl DiscoveryReport {
+    /// `true` if nothing was skipped
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// A file or folder that was skipped during discovery
+///
+/// The error is serialized as its message, and deserializes as a `ThermiteError::UnknownError`
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DiscoveryProblem {
+    #[serde(serialize_with = "serialize_path")]
+    pub path: PathBuf,
+    #[serde(
+        serialize_with = "serialize_error",
+        deserialize_with = "deserialize_error"
+    )]
+    pub error: ThermiteError,
+}
+
+fn serialize_error<S: serde::Serializer>(
+    error: &ThermiteError,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(error)
+}
+
+fn deserialize_error<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<ThermiteError, D::Error> {
+    String::deserialize(deserializer).map(ThermiteError::UnknownError)
+}
+
+impl DiscoveryProblem {
+    fn new(path: impl Into<PathBuf>, error: impl Into<ThermiteError>) -> Self {
+        Self {
+            path: path.into(),
+            error: error.into(),
+        }
+    }
+}
+
+impl Display for DiscoveryProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Skipped {}: {}", self.path.display(), self.error)
+    }
+}
+
+/// Same as [`find_mods_with`], but also returns every entry that couldn't be read instead of only logging it
+///
+/// A broken symlink, unreadable folder, or malformed `manifest.json` or `mod.json` only skips that entry,
+/// so the rest of the profile is still found
+///
+/// # Errors
+/// - The path cannot be canonicalized
+/// - IO Errors reading the directory itself
+pub fn discover_mods(
+    dir: impl AsRef<Path>,
+    options: &FindOptions,
+) -> Result<DiscoveryReport, ThermiteError> {
+    let mut report = DiscoveryReport::default();
+    let dir = dir.as_ref().canonicalize()?;
+    debug!("Finding mods in '{}'", dir.display());
+    for child in dir.read_dir()? {
+        let child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                report.problems.push(DiscoveryProblem::new(&dir, e));
+                continue;
+            }
+        };
+        match is_dir(&child, options.follow_symlinks) {
+            Ok(true) => {}
+            Ok(false) => {
+                debug!("Skipping file {}", child.path().display());
+                continue;
+            }
+            Err(e) => {
+                report.problems.push(DiscoveryProblem::new(child.path(), e));
+                continue;
+            }
+        }
+
+        let path = child.path().join("manifest.json");
+        let manifest = match read_manifest(&path) {
+            Ok(Some(manifest)) => manifest,
+            Ok(None) => continue,
+            Err(e) => {
+                report.problems.push(DiscoveryProblem::new(path, e));
+                continue;
+            }
+        };
+
+        let submods = find_submods(
+            &manifest,
+            &child.path(),
+            options,
+            0,
+            &mut HashSet::new(),
+            &mut report.problems,
+        );
+        if submods.is_empty() {
+            debug!("No mods in {}", child.path().display());
+            continue;
+        }
+
+        debug!(
+            "Found {} submods in {}",
+            submods.len(),
+            child.path().display()
+        );
+        trace!("{:#?}", submods);
+        let modstring = match child
+            .file_name()
+            .to_str()
+            .ok_or(ThermiteError::UTF8Error)
+            .and_then(parse_modstring)
+        {
+            Ok(modstring) => modstring,
+            Err(e) => {
+                report.problems.push(DiscoveryProblem::new(child.path(), e));
+                continue;
+            }
+        };
+        report.mods.extend(submods.into_iter().map(|mut m| {
+            m.author.clone_from(&modstring.0);
+            m.version.clone_from(&modstring.2);
+            m
+        }));
+    }
+
+    if let Some(side) = options.side {
+        report.mods.retain(|m| m.side().includes(side));
+    }
+
+    if !options.include_disabled {
+        if let Some(profile) = dir.parent() {
+            match get_enabled_mods(profile) {
+                Ok(enabled) => report
+                    .mods
+                    .retain(|m| enabled.get(&m.mod_json.name).unwrap_or(true)),
+                Err(ThermiteError::MissingFile(_)) => {}
+                Err(e) => report
+                    .problems
+                    .push(DiscoveryProblem::new(profile.join("enabledmods.json"), e)),
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Read a package's `manifest.json`, or `None` if it doesn't have one
+fn read_manifest(path: &Path) -> Result<Option<Manifest>, ThermiteError> {
+    if !path.try_exists()? {
+        return Ok(None);
+    }
+
+    let raw = fs::read_to_string(path)?;
+    Ok(Some(serde_json::from_str(&raw)?))
+}
+
+/// Find the Northstar core mods installed in a game directory
+///
+/// A folder in `R2Northstar/mods` is a core mod if it was extracted from a Northstar release according to
+/// [`NORTHSTAR_RECORD`](crate::core::manage::NORTHSTAR_RECORD), or if it's marked as coming from the `northstar` package by its [`AUTHOR_FILE`] or
+/// `manifest.json`. The names in [`CORE_MODS`] are always treated as core mods.
+///
+/// # Returns
+/// * the `Name` from each core mod's `mod.json`, or the folder name if it doesn't have one
+///
+/// # Errors
+/// - IO Errors
+/// - The Northstar record is malformed
+pub fn detect_core_mods(game_path: impl AsRef<Path>) -> Result<Vec<String>, ThermiteError> {
+    let profile = game_path.as_ref().join("R2Northstar");
+    if !profile.join("mods").try_exists()? {
+        return Ok(vec![]);
+    }
+
+    let recorded = read_northstar_record(&game_path)?
+        .map(|record| {
+            record
+                .files
+                .into_iter()
+                .filter_map(|f| record_core_folder(&f.path))
+                .collect::<HashSet<_>>()
+        })
+        .unwrap_or_default();
+
+    profile_core_mods(&profile, &recorded)
+}
+
+/// The core mods in a profile's `mods` folder, see [`detect_core_mods`]
+///
+/// `recorded` is the lowercase folder names from the Northstar record, if the profile is `R2Northstar`
+fn profile_core_mods(
+    profile: &Path,
+    recorded: &HashSet<String>,
+) -> Result<Vec<String>, ThermiteError> {
+    let mods = profile.join("mods");
+    if !mods.try_exists()? {
+        return Ok(vec![]);
+    }
+
+    let mut res = vec![];
+    for child in mods.read_dir()? {
+        let child = child?;
+        if !child.file_type()?.is_dir() {
+            continue;
+        }
+
+        let folder = child.file_name().to_string_lossy().into_owned();
+        let path = child.path();
+        if !(recorded.contains(&folder.to_lowercase()) || is_core_dir(&path, &folder)) {
+            continue;
+        }
+
+        let name = fs::read_to_string(path.join("mod.json"))
+            .ok()
+            .and_then(|raw| json5::from_str::<ModJSON>(&raw).ok())
+            .map_or(folder, |m| m.name);
+        trace!("Found core mod {name} at {}", path.display());
+        res.push(name);
+    }
+
+    res.sort();
+    Ok(res)
+}
+
+/// Whether an installed mod is a Northstar core mod, using the same markers as [`detect_core_mods`]
+#[must_use]
+pub fn is_core_mod(installed: &InstalledMod) -> bool {
+    is_core_dir(&installed.path, &installed.mod_json.name)
+        || (installed.author.eq_ignore_ascii_case("northstar")
+            && installed.manifest.name.eq_ignore_ascii_case("northstar"))
+}
+
+/// Check a mod folder's name and marker files for signs it's a core mod
+pub(crate) fn is_core_dir(dir: &Path, name: &str) -> bool {
+    if CORE_MODS.contains(&name.to_lowercase().as_str()) {
+        return true;
+    }
+
+    let author = fs::read_to_string(dir.join(AUTHOR_FILE)).unwrap_or_default();
+    if author.trim().eq_ignore_ascii_case("northstar") {
+        return true;
+    }
+
+    fs::read_to_string(dir.join("manifest.json"))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<Manifest>(&raw).ok())
+        .is_some_and(|m| m.name.eq_ignore_ascii_case("northstar"))
+}
+
+/// Same as [`find_mods`], paired with whether each mod is enabled in the `enabledmods.json` next to `dir`
+///
+/// Mods missing from `enabledmods.json` are enabled, as are all mods if the file doesn't exist
+///
+/// # Errors
+/// - The path cannot be canonicalized
+/// - IO Errors
+/// - Improperly formatted JSON files
+pub fn find_mods_with_state(
+    dir: impl AsRef<Path>,
+) -> Result<Vec<(InstalledMod, bool)>, ThermiteError> {
+    let mods = find_mods(&dir)?;
+    let profile = dir.as_ref().canonicalize()?;
+    let enabled = match profile.parent().map(get_enabled_mods) {
+        Some(Ok(enabled)) => Some(enabled),
+        Some(Err(ThermiteError::MissingFile(_))) | None => None,
+        Some(Err(e)) => return Err(e),
+    };
+
+    Ok(mods
+        .into_iter()
+        .map(|m| {
+            let state = enabled
+                .as_ref()
+                .and_then(|e| e.get(&m.mod_json.name))
+                .unwrap_or(true);
+            (m, state)
+        })
+        .collect())
+}
+
+/// Find every mod in a profile, including the core mods and mods installed by hand
+///
+/// Mods in the profile's `mods` directory are returned first, sorted by folder, followed by the packages in
+/// `packages` as found by [`find_mods`]. Packages that can't be read are logged and skipped.
+///
+/// # Params
+/// * `profile_dir` - the profile directory, e.g. `R2Northstar`
+///
+/// # Errors
+/// - IO Errors
+pub fn find_all_mods(profile_dir: impl AsRef<Path>) -> Result<Vec<ModEntry>, ThermiteError> {
+    let profile = profile_dir.as_ref();
+    let mut res = vec![];
+
+    let mods = profile.join("mods");
+    if mods.try_exists()? {
+        let mut dirs = vec![];
+        for child in mods.read_dir()? {
+            let child = child?;
+            if child.file_type()?.is_dir() {
+                dirs.push(child.path());
+            }
+        }
+        dirs.sort();
+
+        for path in dirs {
+            let raw = match fs::read_to_string(path.join("mod.json")) {
+                Ok(raw) => raw,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let mod_json: ModJSON = match json5::from_str(&raw) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    error!("Error parsing JSON in {}: {e}", path.display());
+                    continue;
+                }
+            };
+
+            let bare = BareMod { mod_json, path };
+            res.push(if is_core_dir(&bare.path, &bare.mod_json.name) {
+                ModEntry::Core(bare)
+            } else {
+                ModEntry::Bare(bare)
+            });
+        }
+    }
+
+    let packages = profile.join("packages");
+    if packages.try_exists()? {
+        res.extend(
+            find_mods(packages)?
+                .into_iter()
+                .map(|m| ModEntry::Package(Box::new(m))),
+        );
+    }
+
+    Ok(res)
+}
+
+/// Find the plugin DLLs in a profile's `plugins` directory
+///
+/// # Errors
+/// - IO Errors
+pub fn find_plugins(dir: impl AsRef<Path>) -> Result<Vec<Plugin>, ThermiteError> {
+    let mut res = vec![];
+    for child in dir.as_ref().read_dir()? {
+        let child = child?;
+        let path = child.path();
+        if !child.file_type()?.is_file()
+            || !path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("dll"))
+        {
+            continue;
+        }
+
+        let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+            trace!("Skipping plugin with non UTF-8 name {}", path.display());
+            continue;
+        };
+        let raw = fs::read(&path)?;
+        let version = pe_file_version(&raw);
+        if version.is_none() {
+            trace!("No version info in {}", path.display());
+        }
+
+        res.push(Plugin {
+            name: name.to_owned(),
+            size: raw.len() as u64,
+            version,
+            path,
+        });
+    }
+
+    res.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(res)
+}
+
+/// Read the file version from the `VS_FIXEDFILEINFO` in a PE file's version resource
+fn pe_file_version(raw: &[u8]) -> Option<String> {
+    const SIGNATURE: [u8; 4] = 0xFEEF_04BD_u32.to_le_bytes();
+    let key = "VS_VERSION_INFO"
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect::<Vec<_>>();
+
+    let start = raw.windows(key.len()).position(|w| w == key.as_slice())?;
+    let offset = raw[start..]
+        .windows(SIGNATURE.len())
+        .take(64)
+        .position(|w| w == SIGNATURE)?;
+    // signature, struct version, then the most and least significant halves of the file version
+    let info = raw.get(start + offset + 8..start + offset + 16)?;
+    let word = |i: usize| u32::from_le_bytes([info[i], info[i + 1], info[i + 2], info[i + 3]]);
+    let (ms, ls) = (word(0), word(4));
+
+    Some(format!(
+        "{}.{}.{}.{}",
+        ms >> 16,
+        ms & 0xFFFF,
+        ls >> 16,
+        ls & 0xFFFF
+    ))
+}
+
+pub(crate) fn get_submods(manifest: &Manifest, dir: impl AsRef<Path>) -> Option<Vec<InstalledMod>> {
+    let dir = dir.as_ref();
+    if !dir.is_dir() {
+        debug!("{} wasn't a directory, aborting", dir.display());
+        return None;
+    }
+
+    let mut problems = vec![];
+    let mods = find_submods(
+        manifest,
+        dir,
+        &FindOptions::default(),
+        0,
+        &mut HashSet::new(),
+        &mut problems,
+    );
+    for problem in problems {
+        error!("{problem}");
+    }
+    (!mods.is_empty()).then_some(mods)
+}
+
+/// Search a package for mod.json files. Folders containing a mod.json aren't searched any further,
+/// since Northstar doesn't load mods nested inside other mods
+fn find_submods(
+    manifest: &Manifest,
+    dir: &Path,
+    options: &FindOptions,
+    depth: usize,
+    visited: &mut HashSet<PathBuf>,
+    problems: &mut Vec<DiscoveryProblem>,
+) -> Vec<InstalledMod> {
+    debug!("Searching for submods in {}", dir.display());
+    if options.max_depth.is_some_and(|max| depth > max) {
+        trace!("Reached max depth");
+        return vec![];
+    }
+    // guard against symlink loops
+    if options.follow_symlinks && !dir.canonicalize().is_ok_and(|p| visited.insert(p)) {
+        return vec![];
+    }
+
+    let children = match dir.read_dir() {
+        Ok(children) => children,
+        Err(e) => {
+            problems.push(DiscoveryProblem::new(dir, e));
+            return vec![];
+        }
+    };
+    let mut mods = vec![];
+    let mut subdirs = vec![];
+    for child in children {
+        let child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                problems.push(DiscoveryProblem::new(dir, e));
+                continue;
+            }
+        };
+        match is_dir(&child, options.follow_symlinks) {
+            Ok(true) => subdirs.push(child.path()),
+            Ok(false) if child.file_name() == "mod.json" => {
+                let parsed = fs::read_to_string(child.path())
+                    .map_err(ThermiteError::from)
+                    .and_then(|file| Ok(json5::from_str(&file)?));
+                match parsed {
+                    Ok(mod_json) => mods.push(InstalledMod {
+                        author: String::new(),
+                        version: String::new(),
+                        manifest: manifest.clone(),
+                        mod_json,
+                        path: dir.to_path_buf(),
+                    }),
+                    Err(e) => problems.push(DiscoveryProblem::new(child.path(), e)),
+                }
+            }
+            Ok(false) => {}
+            Err(e) => problems.push(DiscoveryProblem::new(child.path(), e)),
+        }
+    }
+
+    if mods.is_empty() {
+        for sub in subdirs {
+            mods.append(&mut find_submods(
+                manifest,
+                &sub,
+                options,
+                depth + 1,
+                visited,
+                problems,
+            ));
+        }
+    }
+
+    mods
+}
+
+/// Whether an entry is a directory, or a symlink to one when following symlinks
+///
+/// Fails for symlinks that can't be followed, e.g. because their target was removed
+fn is_dir(entry: &fs::DirEntry, follow_symlinks: bool) -> io::Result<bool> {
+    let ty = entry.file_type()?;
+    if ty.is_symlink() {
+        Ok(follow_symlinks && fs::metadata(entry.path())?.is_dir())
+    } else {
+        Ok(ty.is_dir())
+    }
+}
+
+/// Matches the same modstrings as [`parse_modstring`]
+pub static RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\w+)-([\w.\-]*\w[\w.\-]*)-(\d+(?:\.\d+){2,3})$").expect("regex")
+});
+
+/// Returns the parts of a `author-name-X.Y.Z` string in (`author`, `name`, `version`) order
+///
+/// The string is parsed from both ends, so names may contain hyphens and dots (`author-My-Mod-1.0.0`),
+/// and versions may have a fourth component (`author-mod-1.0.0.1`). Parse a [`ModString`] instead to
+/// get the parts by name.
+///
+/// # Errors
+///
+/// Returns a `NameError` if the input string is not in the correct format
+pub fn parse_modstring(input: impl AsRef<str>) -> Result<(String, String, String), ThermiteError> {
+    debug!("Parsing modstring {}", input.as_ref());
+    input.as_ref().parse::<ModString>().map(Into::into)
+}
+
+/// Checks that a string is in `author-name-X.Y.Z` format
+#[inline]
+#[must_use]
+pub fn validate_modstring(input: impl AsRef<str>) -> bool {
+    split_modstring(input.as_ref()).is_some()
+}
+
+/// Split the version off the end, then the author off the front, leaving the name in between
+pub(crate) fn split_modstring(input: &str) -> Option<(&str, &str, &str)> {
+    let (rest, version) = input.rsplit_once('-')?;
+    let parts = version.split('.').collect::<Vec<_>>();
+    if !(3..=4).contains(&parts.len())
+        || parts
+            .iter()
+            .any(|p| p.is_empty() || !p.bytes().all(|b| b.is_ascii_digit()))
+    {
+        return None;
+    }
+
+    let (author, name) = rest.split_once('-')?;
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    if author.is_empty()
+        || !author.chars().all(is_word)
+        || !name.chars().any(is_word)
+        || !name.chars().all(|c| is_word(c) || c == '-' || c == '.')
+    {
+        return None;
+    }
+
+    Some((author, name, version))
+}
+
+#[cfg(feature = "ea")]
+pub(crate) mod ea {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+    use tracing::debug;
+
+    use crate::TITANFALL2_ORIGIN_IDS;
+
+    /// Keys under `HKEY_LOCAL_MACHINE` where Origin and the EA App record the Titanfall 2 install directory
+    #[cfg(windows)]
+    const TITANFALL2_KEYS: [&str; 2] = [
+        r"SOFTWARE\Respawn\Titanfall2",
+        r"SOFTWARE\WOW6432Node\Respawn\Titanfall2",
+    ];
+
+    /// Keys under `HKEY_LOCAL_MACHINE` and the value holding the EA App's or Origin's location, EA App first
+    #[cfg(windows)]
+    const CLIENT_KEYS: [(&str, &str); 4] = [
+        (r"SOFTWARE\Electronic Arts\EA Desktop", "InstallLocation"),
+        (r"SOFTWARE\Electronic Arts\EA Desktop", "DesktopAppPath"),
+        (r"SOFTWARE\WOW6432Node\Origin", "ClientPath"),
+        (r"SOFTWARE\Origin", "ClientPath"),
+    ];
+
+    /// Where the EA App and Origin put games by default, relative to `%ProgramFiles%` and
+    /// `%ProgramFiles(x86)%` respectively
+    const DEFAULT_DIRS: [(&str, &str); 2] = [
+        ("ProgramFiles", r"EA Games\Titanfall2"),
+        ("ProgramFiles(x86)", r"Origin Games\Titanfall2"),
+    ];
+
+    /// Returns the path to the EA App installation, or Origin if the EA App isn't installed
+    ///
+    /// Always `None` on platforms other than Windows
+    #[must_use]
+    pub fn ea_dir() -> Option<PathBuf> {
+        #[cfg(windows)]
+        for (key, value) in CLIENT_KEYS {
+            let Some(path) = read_hklm(key, value).map(PathBuf::from) else {
+                continue;
+            };
+            // the client paths point at the executable rather than the folder it's in
+            let dir = if path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("exe"))
+            {
+                path.parent().map(Path::to_path_buf)
+            } else {
+                Some(path)
+            };
+            if let Some(dir) = dir.filter(|d| d.is_dir()) {
+                return Some(dir);
+            }
+        }
+
+        None
+    }
+
+    /// Returns the path to the Titanfall 2 installation from the EA App or Origin if it exists
+    ///
+    /// The directory recorded in the registry is checked first, then the clients' default install locations
+    #[must_use]
+    pub fn titanfall2_ea_dir() -> Option<PathBuf> {
+        let mut candidates = vec![];
+        #[cfg(windows)]
+        candidates.extend(
+            TITANFALL2_KEYS
+                .iter()
+                .filter_map(|key| read_hklm(key, "Install Dir"))
+                .map(PathBuf::from),
+        );
+        candidates.extend(
+            DEFAULT_DIRS
+                .iter()
+                .filter_map(|(var, dir)| Some(Path::new(&std::env::var_os(var)?).join(dir))),
+        );
+
+        candidates.into_iter().find(|dir| is_ea_titanfall2(dir))
+    }
+
+    /// Whether `dir` is a Titanfall 2 installation from the EA App or Origin, according to the content ids in
+    /// `__Installer/installerdata.xml`
+    #[must_use]
+    pub fn is_ea_titanfall2(dir: impl AsRef<Path>) -> bool {
+        let dir = dir.as_ref();
+        if !dir.join("Titanfall2.exe").is_file() {
+            return false;
+        }
+
+        let Ok(installer_data) =
+            fs::read_to_string(dir.join("__Installer").join("installerdata.xml"))
+        else {
+            debug!("No EA installer data in {}", dir.display());
+            return false;
+        };
+        TITANFALL2_ORIGIN_IDS
+            .iter()
+            .any(|id| installer_data.contains(&format!("<contentID>{id}</contentID>")))
+    }
+
+    /// Returns every Wine prefix that might contain the EA App, for running it through Wine, Lutris or Heroic
+    ///
+    /// Looks at `$WINEPREFIX`, `~/.wine`, the prefixes in Lutris' game configs and Heroic's game configs
+    /// (including the Flatpak versions of both), and the launchers' default prefix folders
+    #[must_use]
+    pub fn wine_prefixes() -> Vec<PathBuf> {
+        let mut prefixes = vec![];
+        if let Some(prefix) = std::env::var_os("WINEPREFIX") {
+            prefixes.push(PathBuf::from(prefix));
+        }
+        if let Some(home) = std::env::var_os("HOME") {
+            prefixes.extend(wine_prefixes_in(Path::new(&home)));
+        }
+
+        let mut found: Vec<PathBuf> = vec![];
+        for prefix in prefixes {
+            if prefix.join("drive_c").is_dir() && !found.contains(&prefix) {
+                found.push(prefix);
+            }
+        }
+        found
+    }
+
+    /// Returns the path to the Titanfall 2 installation from the EA App or Origin in any of the
+    /// [`wine_prefixes`], if it exists
+    #[must_use]
+    pub fn titanfall2_wine_dir() -> Option<PathBuf> {
+        wine_prefixes().iter().find_map(titanfall2_in_prefix)
+    }
+
+    /// Returns the path to the Titanfall 2 installation from the EA App or Origin in a Wine prefix
+    ///
+    /// The install directory recorded in the prefix's registry is checked first, then the clients' default
+    /// install locations
+    #[must_use]
+    pub fn titanfall2_in_prefix(prefix: impl AsRef<Path>) -> Option<PathBuf> {
+        let prefix = prefix.as_ref();
+        let drive_c = prefix.join("drive_c");
+        let registered = fs::read_to_string(prefix.join("system.reg"))
+            .ok()
+            .and_then(|reg| registry_install_dir(&reg))
+            .map(|dir| windows_to_prefix_path(prefix, &dir));
+
+        registered
+            .into_iter()
+            .chain([
+                drive_c.join("Program Files/EA Games/Titanfall2"),
+                drive_c.join("Program Files (x86)/Origin Games/Titanfall2"),
+            ])
+            .find(|dir| is_ea_titanfall2(dir))
+    }
+
+    /// Candidate prefixes relative to the home directory. They aren't checked for existence
+    fn wine_prefixes_in(home: &Path) -> Vec<PathBuf> {
+        let mut prefixes = vec![home.join(".wine")];
+
+        let lutris = [
+            home.join(".config/lutris/games"),
+            home.join(".local/share/lutris/games"),
+            home.join(".var/app/net.lutris.Lutris/config/lutris/games"),
+            home.join(".var/app/net.lutris.Lutris/data/lutris/games"),
+        ];
+        for config in lutris.iter().flat_map(|dir| config_files(dir, "yml")) {
+            let Ok(raw) = fs::read_to_string(&config) else {
+                continue;
+            };
+            prefixes.extend(raw.lines().filter_map(|line| {
+                let value = line.trim().strip_prefix("prefix:")?.trim();
+                Some(expand_home(
+                    home,
+                    value.trim_matches(|c| c == '"' || c == '\''),
+                ))
+            }));
+        }
+
+        let heroic = [
+            home.join(".config/heroic/GamesConfig"),
+            home.join(".var/app/com.heroicgameslauncher.hgl/config/heroic/GamesConfig"),
+        ];
+        for config in heroic.iter().flat_map(|dir| config_files(dir, "json")) {
+            let Some(config) = fs::read_to_string(&config)
+                .ok()
+                .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+            else {
+                continue;
+            };
+            prefixes.extend(
+                config
+                    .as_object()
+                    .into_iter()
+                    .flat_map(|games| games.values())
+                    .filter_map(|game| game.get("winePrefix")?.as_str())
+                    .map(|prefix| expand_home(home, prefix)),
+            );
+        }
+
+        for dir in [home.join("Games"), home.join("Games/Heroic/Prefixes")] {
+            let Ok(entries) = dir.read_dir() else {
+                continue;
+            };
+            let mut found = entries.flatten().map(|e| e.path()).collect::<Vec<_>>();
+            found.sort();
+            prefixes.extend(found);
+        }
+
+        prefixes
+    }
+
+    /// Files in `dir` with the extension `ext`, sorted
+    fn config_files(dir: &Path, ext: &str) -> Vec<PathBuf> {
+        let mut files = dir
+            .read_dir()
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == ext))
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+
+    fn expand_home(home: &Path, path: &str) -> PathBuf {
+        path.strip_prefix("~/")
+            .map_or_else(|| PathBuf::from(path), |rest| home.join(rest))
+    }
+
+    /// Read Titanfall 2's `Install Dir` from the contents of a Wine prefix's `system.reg`
+    fn registry_install_dir(reg: &str) -> Option<String> {
+        let mut in_section = false;
+        for line in reg.lines() {
+            let line = line.trim();
+            if line.starts_with('[') {
+                let key = line.to_lowercase();
+                in_section = key.starts_with(r"[software\\respawn\\titanfall2]")
+                    || key.starts_with(r"[software\\wow6432node\\respawn\\titanfall2]");
+            } else if in_section {
+                let Some(dir) = line.strip_prefix("\"Install Dir\"=") else {
+                    continue;
+                };
+                return Some(dir.trim_matches('"').replace(r"\\", r"\"));
+            }
+        }
+
+        None
+    }
+
+    /// Convert a path like `C:\Games\Titanfall2` to the same folder in a Wine prefix
+    fn windows_to_prefix_path(prefix: &Path, path: &str) -> PathBuf {
+        let (drive, rest) = path.split_once(':').unwrap_or(("c", path));
+        let mut out = prefix.join(format!("drive_{}", drive.to_lowercase()));
+        out.extend(rest.split('\\').filter(|p| !p.is_empty()));
+        out
+    }
+
+    #[cfg(windows)]
+    fn read_hklm(key: &str, value: &str) -> Option<String> {
+        use winreg::{enums::HKEY_LOCAL_MACHINE, RegKey};
+
+        RegKey::predef(HKEY_LOCAL_MACHINE)
+            .open_subkey(key)
+            .and_then(|k| k.get_value(value))
+            .inspect_err(|e| debug!("Unable to read {key}\\{value}: {e}"))
+            .ok()
+    }
+
+    #[cfg(test)]
+    mod test {
+        use std::fs;
+
+        use crate::core::utils::TempDir;
+
+        use super::{is_ea_titanfall2, titanfall2_in_prefix, wine_prefixes_in};
+
+        const INSTALLER_DATA: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<DiPManifest version="4.0">
+  <contentIDs>
+    <contentID>Origin.OFR.50.0001452</contentID>
+    <contentID>Origin.OFR.50.0001456</contentID>
+  </contentIDs>
+</DiPManifest>"#;
+
+        #[test]
+        fn detect_ea_install() {
+            let dir = TempDir::create("./ea_install").expect("Unable to create temp dir");
+            fs::write(dir.join("Titanfall2.exe"), "").expect("write exe");
+            assert!(!is_ea_titanfall2(&dir));
+
+            fs::create_dir_all(dir.join("__Installer")).expect("create dir");
+            fs::write(
+                dir.join("__Installer/installerdata.xml"),
+                INSTALLER_DATA
+                    .replace("0001452", "0000000")
+                    .replace("0001456", "0000001"),
+            )
+            .expect("write installer data");
+            assert!(!is_ea_titanfall2(&dir));
+
+            fs::write(dir.join("__Installer/installerdata.xml"), INSTALLER_DATA)
+                .expect("write installer data");
+            assert!(is_ea_titanfall2(&dir));
+        }
+
+        #[test]
+        fn detect_wine_install() {
+            let home = TempDir::create("./ea_wine_home").expect("Unable to create temp dir");
+            let prefix = home.join("Games/Heroic/Prefixes/ea-app");
+            let heroic = home.join(".config/heroic/GamesConfig");
+            fs::create_dir_all(&heroic).expect("create dir");
+            fs::write(
+                heroic.join("ea-app.json"),
+                r#"{ "ea-app": { "winePrefix": "~/Games/Heroic/Prefixes/ea-app" } }"#,
+            )
+            .expect("write heroic config");
+            let lutris = home.join(".config/lutris/games");
+            fs::create_dir_all(&lutris).expect("create dir");
+            fs::write(
+                lutris.join("ea-app-1.yml"),
+                "game:\n  exe: drive_c/EA/EADesktop.exe\n  prefix: /opt/lutris/ea-app\n",
+            )
+            .expect("write lutris config");
+
+            let prefixes = wine_prefixes_in(&home);
+            assert!(prefixes.contains(&prefix));
+            assert!(prefixes.contains(&"/opt/lutris/ea-app".into()));
+
+            let install = prefix.join("drive_c/Games/Titanfall2");
+            fs::create_dir_all(install.join("__Installer")).expect("create dir");
+            fs::write(install.join("Titanfall2.exe"), "").expect("write exe");
+            fs::write(
+                install.join("__Installer/installerdata.xml"),
+                INSTALLER_DATA,
+            )
+            .expect("write installer data");
+            assert_eq!(titanfall2_in_prefix(&prefix), None);
+
+            fs::write(
+                prefix.join("system.reg"),
+                "[Software\\\\Respawn\\\\Titanfall2] 1700000000\n\"Install Dir\"=\"C:\\\\Games\\\\Titanfall2\\\\\"\n",
+            )
+            .expect("write registry");
+            assert_eq!(titanfall2_in_prefix(&prefix), Some(install));
+        }
+    }
+}
+
+/// Executables that mean the game or a Northstar server is running
+pub const GAME_PROCESSES: [&str; 2] = ["Titanfall2.exe", "NorthstarLauncher.exe"];
+
+/// `true` if Titanfall 2 or Northstar, including a dedicated server, is running, e.g. to block
+/// installing or updating while the game holds its files open
+///
+/// Processes are found by name, so this includes the game running under Wine or Proton on Linux
+#[must_use]
+pub fn is_game_running() -> bool {
+    running_game_process().is_some()
+}
+
+/// The name of a running process from [`GAME_PROCESSES`], if there is one
+pub(crate) fn running_game_process() -> Option<&'static str> {
+    let running = running_processes();
+    GAME_PROCESSES
+        .into_iter()
+        .find(|exe| running.iter().any(|p| p.eq_ignore_ascii_case(exe)))
+}
+
+/// The name of a running process from [`GAME_PROCESSES`] that was started from `game_path`, if
+/// there is one
+///
+/// A process counts if its executable, working directory or `argv[0]` is inside `game_path`, which
+/// covers the game running natively as well as under Wine or Proton. Always `None` on platforms
+/// other than Linux
+#[cfg(not(windows))]
+pub(crate) fn running_game_process_in(game_path: &Path) -> Option<&'static str> {
+    #[cfg(target_os = "linux")]
+    {
+        let game_path = game_path
+            .canonicalize()
+            .unwrap_or_else(|_| game_path.to_path_buf());
+        proc_dirs().find_map(|dir| {
+            let cmdline = fs::read(dir.join("cmdline")).ok()?;
+            let name = process_name(&cmdline)?;
+            let exe = GAME_PROCESSES
+                .into_iter()
+                .find(|exe| exe.eq_ignore_ascii_case(name))?;
+            let argv0 = cmdline
+                .split(|b| *b == 0)
+                .next()
+                .and_then(|arg| std::str::from_utf8(arg).ok())
+                .and_then(argv0_path);
+
+            [
+                fs::read_link(dir.join("exe")).ok(),
+                fs::read_link(dir.join("cwd")).ok(),
+                argv0,
+            ]
+            .into_iter()
+            .flatten()
+            .any(|p| p.canonicalize().unwrap_or(p).starts_with(&game_path))
+            .then_some(exe)
+        })
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = game_path;
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn running_processes() -> Vec<String> {
+    proc_dirs()
+        .filter_map(|dir| fs::read(dir.join("cmdline")).ok())
+        .filter_map(|cmdline| process_name(&cmdline).map(str::to_owned))
+        .collect()
+}
+
+/// The `/proc/<pid>` folder of every process
+#[cfg(target_os = "linux")]
+fn proc_dirs() -> impl Iterator<Item = PathBuf> {
+    fs::read_dir("/proc")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|p| {
+            p.file_name()
+                .to_string_lossy()
+                .bytes()
+                .all(|b| b.is_ascii_digit())
+        })
+        .map(|p| p.path())
+}
+
+#[cfg(windows)]
+fn running_processes() -> Vec<String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let output = match std::process::Command::new("tasklist")
+        .args(["/FO", "CSV", "/NH"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) => {
+            debug!("Unable to run tasklist: {e}");
+            return vec![];
+        }
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| tasklist_name(line).map(str::to_owned))
+        .collect()
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn running_processes() -> Vec<String> {
+    vec![]
+}
+
+/// The file name of the executable from a `/proc/<pid>/cmdline`, which is a Windows path for
+/// processes running under Wine
+fn process_name(cmdline: &[u8]) -> Option<&str> {
+    let exe = cmdline.split(|b| *b == 0).next()?;
+    let exe = std::str::from_utf8(exe).ok()?;
+    exe.rsplit(['/', '\\'])
+        .next()
+        .filter(|name| !name.is_empty())
+}
+
+/// `argv[0]` of a process as a path on this system. Wine maps its `Z:` drive to `/`, and other
+/// drives can't be resolved without knowing the prefix
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn argv0_path(argv0: &str) -> Option<PathBuf> {
+    if argv0.starts_with('/') {
+        Some(PathBuf::from(argv0))
+    } else {
+        argv0
+            .strip_prefix("Z:")
+            .or_else(|| argv0.strip_prefix("z:"))
+            .map(|path| PathBuf::from(path.replace('\\', "/")))
+    }
+}
+
+/// The image name from a line of `tasklist /FO CSV` output
+fn tasklist_name(line: &str) -> Option<&str> {
+    line.strip_prefix('"')?.split('"').next()
+}
+
+/// `true` if any flavor of Steam recorded a pid in the home directory that's still running
+#[cfg(unix)]
+pub(crate) fn steam_pid_running(home: &Path) -> bool {
+    [
+        ".steam",
+        ".var/app/com.valvesoftware.Steam/.steam",
+        "snap/steam/common/.steam",
+    ]
+    .iter()
+    .filter_map(|dir| fs::read_to_string(home.join(dir).join("steam.pid")).ok())
+    .filter_map(|pid| pid.trim().parse::<u32>().ok())
+    .any(|pid| Path::new("/proc").join(pid.to_string()).exists())
+}
+
+/// Where each flavor of Steam keeps its files on Linux, relative to the home directory. The
+/// Flatpak keeps them in `~/.var/app` and the Snap in `~/snap`
+const LINUX_STEAM_DIRS: [&str; 7] = [
+    ".local/share/Steam",
+    ".steam/steam",
+    ".steam/root",
+    ".var/app/com.valvesoftware.Steam/.local/share/Steam",
+    ".var/app/com.valvesoftware.Steam/data/Steam",
+    "snap/steam/common/.local/share/Steam",
+    "snap/steam/common/.steam/steam",
+];
+
+/// The first Steam directory in `home` that has a `steamapps` folder, for any flavor of Steam
+#[cfg_attr(not(feature = "steam"), allow(dead_code))]
+pub(crate) fn find_steam_in(home: &Path) -> Option<PathBuf> {
+    LINUX_STEAM_DIRS
+        .iter()
+        .map(|dir| home.join(dir))
+        .find(|dir| dir.join("steamapps").is_dir())
+}
+
+/// The Steam directory and every library in its `libraryfolders.vdf`
+#[cfg_attr(not(feature = "steam"), allow(dead_code))]
+pub(crate) fn library_paths(steam: &Path) -> Vec<PathBuf> {
+    use crate::core::vdf::VdfDocument;
+
+    let mut libraries = vec![steam.to_path_buf()];
+    let Ok(vdf) = VdfDocument::load(steam.join("steamapps").join("libraryfolders.vdf")) else {
+        return libraries;
+    };
+    for key in vdf.keys(&["libraryfolders"]) {
+        if let Some(path) = vdf
+            .get(&["libraryfolders", &key, "path"])
+            .map(PathBuf::from)
+        {
+            if !libraries.contains(&path) {
+                libraries.push(path);
+            }
+        }
+    }
+
+    libraries
+}
+
+#[cfg(feature = "steam")]
+pub(crate) mod steam {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+    use steamlocate::SteamDir;
+
+    use crate::{
+        core::vdf::VdfDocument,
+        error::{Result, ThermiteError},
+        TITANFALL2_STEAM_ID,
+    };
+
+    /// How Steam was installed, which changes where it keeps its files and how it starts games
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum SteamFlavor {
+        /// Installed from distro packages or Valve's installer, or on Windows and macOS
+        Native,
+        /// The `com.valvesoftware.Steam` Flatpak, which keeps its files in `~/.var/app`
+        Flatpak,
+        /// The `steam` Snap, which keeps its files in `~/snap`
+        Snap,
+    }
+
+    impl SteamFlavor {
+        /// The command that runs this Steam, e.g. to open a `steam://` url
+        #[must_use]
+        pub const fn command(self) -> &'static [&'static str] {
+            match self {
+                Self::Native => &["steam"],
+                Self::Flatpak => &["flatpak", "run", "com.valvesoftware.Steam"],
+                Self::Snap => &["snap", "run", "steam"],
+            }
+        }
+
+        fn of(path: &Path) -> Self {
+            let path = path.to_string_lossy();
+            if path.contains("/.var/app/com.valvesoftware.Steam/") {
+                Self::Flatpak
+            } else if path.contains("/snap/steam/") {
+                Self::Snap
+            } else {
+                Self::Native
+            }
+        }
+    }
+
+    /// Returns the path to the Steam installation if it exists
+    ///
+    /// Also finds Steam installed through Flatpak or Snap, see [`steam_install`]
+    #[must_use]
+    #[inline]
+    pub fn steam_dir() -> Option<PathBuf> {
+        steam_install().map(|(path, _)| path)
+    }
+
+    /// Returns the path to the Steam installation and how it was installed
+    #[must_use]
+    pub fn steam_install() -> Option<(PathBuf, SteamFlavor)> {
+        if let Some(steamdir) = SteamDir::locate() {
+            let flavor = SteamFlavor::of(&steamdir.path);
+            return Some((steamdir.path, flavor));
+        }
+
+        let home = std::env::var_os("HOME")?;
+        let path = super::find_steam_in(Path::new(&home))?;
+        let flavor = SteamFlavor::of(&path);
+        Some((path, flavor))
+    }
+
+    /// Returns paths to all known Steam libraries
+    #[must_use]
+    pub fn steam_libraries() -> Option<Vec<PathBuf>> {
+        if let Some(mut steamdir) = SteamDir::locate() {
+            let folders = steamdir.libraryfolders();
+            return Some(folders.paths.clone());
+        }
+
+        steam_install().map(|(path, _)| super::library_paths(&path))
+    }
+
+    /// Returns the path to the Titanfall installation if it exists
+    #[must_use]
+    pub fn titanfall() -> Option<PathBuf> {
+        if let Some(mut steamdir) = SteamDir::locate() {
+            return Some(steamdir.app(&TITANFALL2_STEAM_ID)?.path.clone());
+        }
+
+        steam_libraries()?
+            .iter()
+            .find_map(|library| app_dir(library, TITANFALL2_STEAM_ID))
+    }
+
+    /// Returns Titanfall 2's `steamapps/compatdata/1237970` folder, where Proton keeps its prefix and
+    /// the game's shader caches
+    ///
+    /// The library Titanfall 2 is installed in is checked first, then every other library
+    #[must_use]
+    pub fn titanfall2_compatdata() -> Option<PathBuf> {
+        let mut libraries = steam_libraries()?;
+        // titanfall() is `<library>/steamapps/common/Titanfall2`
+        if let Some(library) =
+            titanfall().and_then(|dir| dir.ancestors().nth(3).map(Path::to_path_buf))
+        {
+            libraries.retain(|l| *l != library);
+            libraries.insert(0, library);
+        }
+
+        libraries
+            .iter()
+            .find_map(|library| compatdata_dir(library, TITANFALL2_STEAM_ID))
+    }
+
+    /// Returns Titanfall 2's Proton prefix, `steamapps/compatdata/1237970/pfx`, if the game has been run
+    /// with Proton
+    ///
+    /// See [`check_wine_prefix`](crate::core::doctor::check_wine_prefix) to check it for problems
+    #[must_use]
+    pub fn titanfall2_prefix() -> Option<PathBuf> {
+        titanfall2_compatdata()
+            .map(|dir| dir.join("pfx"))
+            .filter(|pfx| pfx.is_dir())
+    }
+
+    fn compatdata_dir(library: &Path, app_id: u32) -> Option<PathBuf> {
+        let dir = library
+            .join("steamapps")
+            .join("compatdata")
+            .join(app_id.to_string());
+        dir.is_dir().then_some(dir)
+    }
+
+    /// Every Steam user's `localconfig.vdf`, which holds their launch options for each game
+    #[must_use]
+    pub fn local_configs() -> Vec<PathBuf> {
+        steam_dir()
+            .map(|steam| local_configs_in(&steam))
+            .unwrap_or_default()
+    }
+
+    /// Read Titanfall 2's launch options from a user's `localconfig.vdf`
+    ///
+    /// # Returns
+    /// * `None` if no launch options have been set
+    ///
+    /// # Errors
+    /// - IO Errors
+    /// - The file can't be parsed
+    pub fn titanfall2_launch_options(localconfig: impl AsRef<Path>) -> Result<Option<String>> {
+        let doc = VdfDocument::load(localconfig)?;
+        Ok(doc.get(&launch_options_path(TITANFALL2_STEAM_ID)))
+    }
+
+    /// Set Titanfall 2's launch options in a user's `localconfig.vdf`, e.g. to `-northstar`
+    ///
+    /// Steam rewrites this file when it exits, which would undo the change, so this refuses to run
+    /// while Steam is running. The previous file is kept as `localconfig.vdf.bak`.
+    ///
+    /// # Errors
+    /// - Steam is running
+    /// - IO Errors
+    /// - The file can't be parsed
+    pub fn set_titanfall2_launch_options(
+        localconfig: impl AsRef<Path>,
+        options: &str,
+    ) -> Result<()> {
+        if is_steam_running() {
+            return Err(ThermiteError::SteamRunning);
+        }
+
+        write_launch_options(localconfig.as_ref(), TITANFALL2_STEAM_ID, options)
+    }
+
+    /// `true` if Steam is running, going by the pid it records in the registry on Windows or in
+    /// `~/.steam/steam.pid` on Linux
+    #[must_use]
+    pub fn is_steam_running() -> bool {
+        #[cfg(windows)]
+        {
+            use winreg::{enums::HKEY_CURRENT_USER, RegKey};
+
+            RegKey::predef(HKEY_CURRENT_USER)
+                .open_subkey("Software\\Valve\\Steam\\ActiveProcess")
+                .and_then(|k| k.get_value::<u32, _>("pid"))
+                .is_ok_and(|pid| pid != 0)
+        }
+        #[cfg(unix)]
+        {
+            std::env::var_os("HOME").is_some_and(|home| super::steam_pid_running(Path::new(&home)))
+        }
+        #[cfg(not(any(windows, unix)))]
+        {
+            false
+        }
+    }
+
+    fn local_configs_in(steam: &Path) -> Vec<PathBuf> {
+        let Ok(users) = fs::read_dir(steam.join("userdata")) else {
+            return vec![];
+        };
+        let mut configs = users
+            .flatten()
+            .map(|user| user.path().join("config").join("localconfig.vdf"))
+            .filter(|path| path.is_file())
+            .collect::<Vec<_>>();
+        configs.sort();
+
+        configs
+    }
+
+    fn write_launch_options(localconfig: &Path, app_id: u32, options: &str) -> Result<()> {
+        let mut doc = VdfDocument::load(localconfig)?;
+        doc.set(&launch_options_path(app_id), options)?;
+        fs::copy(localconfig, localconfig.with_extension("vdf.bak"))?;
+        doc.save(localconfig)
+    }
+
+    fn launch_options_path(app_id: u32) -> [String; 7] {
+        [
+            "UserLocalConfigStore",
+            "Software",
+            "Valve",
+            "Steam",
+            "apps",
+            &app_id.to_string(),
+            "LaunchOptions",
+        ]
+        .map(String::from)
+    }
+
+    /// The install folder of an app in a library, if it's installed there
+    fn app_dir(library: &Path, app_id: u32) -> Option<PathBuf> {
+        let steamapps = library.join("steamapps");
+        let manifest =
+            fs::read_to_string(steamapps.join(format!("appmanifest_{app_id}.acf"))).ok()?;
+        let dir = manifest
+            .lines()
+            .find_map(|line| vdf_value(line, "installdir"))?;
+
+        Some(steamapps.join("common").join(dir))
+    }
+
+    /// The value of a `"key"  "value"` line from a VDF file
+    fn vdf_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+        let rest = line.trim().strip_prefix(&format!("\"{key}\""))?;
+        rest.trim().strip_prefix('"')?.strip_suffix('"')
+    }
+
+    #[cfg(test)]
+    mod test {
+        use std::fs;
+
+        use crate::{
+            core::utils::{find_steam_in, library_paths, TempDir},
+            TITANFALL2_STEAM_ID,
+        };
+
+        use super::{
+            app_dir, compatdata_dir, local_configs_in, titanfall2_launch_options,
+            write_launch_options, SteamFlavor,
+        };
+
+        #[test]
+        fn find_flatpak_steam() {
+            let home = TempDir::create("./steam_flatpak_home").expect("Unable to create temp dir");
+            assert_eq!(find_steam_in(&home), None);
+
+            let steam = home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam");
+            let library = home.join("Games/SteamLibrary");
+            fs::create_dir_all(steam.join("steamapps")).expect("create dir");
+            fs::create_dir_all(library.join("steamapps")).expect("create dir");
+            fs::write(
+                steam.join("steamapps/libraryfolders.vdf"),
+                format!(
+                    "\"libraryfolders\"\n{{\n\t\"0\"\n\t{{\n\t\t\"path\"\t\t\"{}\"\n\t}}\n\t\"1\"\n\t{{\n\t\t\"path\"\t\t\"{}\"\n\t}}\n}}\n",
+                    steam.display(),
+                    library.display()
+                ),
+            )
+            .expect("write libraryfolders.vdf");
+            fs::write(
+                library.join(format!("steamapps/appmanifest_{TITANFALL2_STEAM_ID}.acf")),
+                "\"AppState\"\n{\n\t\"appid\"\t\t\"1237970\"\n\t\"installdir\"\t\t\"Titanfall2\"\n}\n",
+            )
+            .expect("write app manifest");
+
+            let found = find_steam_in(&home).expect("find steam");
+            assert_eq!(found, steam);
+            assert_eq!(SteamFlavor::of(&found), SteamFlavor::Flatpak);
+
+            let libraries = library_paths(&found);
+            assert_eq!(libraries, [steam.clone(), library.clone()]);
+            assert_eq!(app_dir(&steam, TITANFALL2_STEAM_ID), None);
+            assert_eq!(
+                app_dir(&library, TITANFALL2_STEAM_ID),
+                Some(library.join("steamapps/common/Titanfall2"))
+            );
+
+            let compatdata = library.join(format!("steamapps/compatdata/{TITANFALL2_STEAM_ID}"));
+            fs::create_dir_all(compatdata.join("pfx")).expect("create dir");
+            assert_eq!(compatdata_dir(&steam, TITANFALL2_STEAM_ID), None);
+            assert_eq!(
+                compatdata_dir(&library, TITANFALL2_STEAM_ID),
+                Some(compatdata)
+            );
+        }
+
+        #[test]
+        fn edit_launch_options() {
+            let steam =
+                TempDir::create("./steam_launch_options").expect("Unable to create temp dir");
+            let config = steam.join("userdata/12345/config");
+            fs::create_dir_all(&config).expect("create dir");
+            fs::create_dir_all(steam.join("userdata/0")).expect("create dir");
+            let localconfig = config.join("localconfig.vdf");
+            let original = "\"UserLocalConfigStore\"\n{\n\t\"Software\"\n\t{\n\t\t\"Valve\"\n\t\t{\n\t\t\t\"Steam\"\n\t\t\t{\n\t\t\t\t\"apps\"\n\t\t\t\t{\n\t\t\t\t}\n\t\t\t}\n\t\t}\n\t}\n}\n";
+            fs::write(&localconfig, original).expect("write localconfig.vdf");
+
+            assert_eq!(local_configs_in(&steam), std::slice::from_ref(&localconfig));
+            assert_eq!(
+                titanfall2_launch_options(&localconfig).expect("read launch options"),
+                None
+            );
+
+            write_launch_options(&localconfig, TITANFALL2_STEAM_ID, "-northstar")
+                .expect("write launch options");
+            assert_eq!(
+                titanfall2_launch_options(&localconfig)
+                    .expect("read launch options")
+                    .as_deref(),
+                Some("-northstar")
+            );
+            assert_eq!(
+                fs::read_to_string(localconfig.with_extension("vdf.bak")).expect("read backup"),
+                original
+            );
+
+            #[cfg(unix)]
+            {
+                use crate::core::utils::steam_pid_running;
+
+                let pid = steam.join(".steam/steam.pid");
+                fs::create_dir_all(steam.join(".steam")).expect("create dir");
+                assert!(!steam_pid_running(&steam));
+                fs::write(&pid, std::process::id().to_string()).expect("write pid");
+                assert!(steam_pid_running(&steam));
+                fs::write(&pid, u32::MAX.to_string()).expect("write pid");
+                assert!(!steam_pid_running(&steam));
+            }
+        }
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "proton"))]
+//#[deprecated(since = "0.8.0", note = "Northstar Proton is no longer required")]
+pub(crate) mod proton {
+    use flate2::read::GzDecoder;
+    use serde::Deserialize;
+    use std::{
+        fs,
+        io::{Read, Write},
+        path::{Component, Path, PathBuf},
+    };
+    use tar::Archive;
+    use tracing::{debug, warn};
+
+    use crate::{
+        core::{
+            http,
+            manage::{download, download_with_checksum},
+            utils::TempDir,
+            vdf::VdfDocument,
+        },
+        error::{Result, ThermiteError},
+        model::ReleaseAsset,
+    };
+    const BASE_URL: &str = "https://github.com/R2NorthstarTools/NorthstarProton/releases/";
+    const API_URL: &str = "https://api.github.com/repos/R2NorthstarTools/NorthstarProton/releases";
+
+    #[derive(Deserialize)]
+    struct Release {
+        assets: Vec<ReleaseAsset>,
+    }
+
+    /// Returns the latest tag from the NorthstarProton repo
+    ///
+    /// # Errors
+    /// * Network error
+    /// * Unexpected URL format
+    pub fn latest_release() -> Result<String> {
+        let url = format!("{}latest", BASE_URL);
+        let res = http::agent().get(&url).call()?;
+        let location = res.get_url();
+        debug!("{url} redirected to {location}");
+
+        Ok(location
+            .split('/')
+            .last()
             .ok_or_else(|| ThermiteError::UnknownError("Malformed location URL".into()))?
             .to_owned())
     }
 
-    /// Convinience function for downloading a given tag from the NorthstarProton repo.
-    /// If you have a URL already, just use `thermite::manage::download`
-    pub fn download_ns_proton(tag: impl AsRef<str>, output: impl Write) -> Result<u64> {
-        let url = format!(
-            "{}download/{}/NorthstarProton{}.tar.gz",
-            BASE_URL,
-            tag.as_ref(),
-            tag.as_ref().trim_matches('v')
-        );
-        download(output, url)
+    /// Convinience function for downloading a given tag from the NorthstarProton repo.
+    /// If you have a URL already, just use `thermite::manage::download`
+    ///
+    /// The download is checked against the size and hash GitHub reports for the release asset, so a
+    /// truncated tarball is never handed to [`install_ns_proton`]. Older releases only have their size
+    /// checked because GitHub doesn't have a hash for them. If GitHub's API can't be reached, e.g.
+    /// because of its rate limit, the tarball is downloaded without being checked.
+    ///
+    /// # Errors
+    /// * Network error
+    /// * `ThermiteError::ChecksumMismatch` if the download doesn't match the release asset
+    pub fn download_ns_proton(tag: impl AsRef<str>, output: impl Write) -> Result<u64> {
+        let tag = tag.as_ref();
+        let name = format!("NorthstarProton{}.tar.gz", tag.trim_matches('v'));
+        let url = format!("{BASE_URL}download/{tag}/{name}");
+        let asset = match release_asset(tag, &name) {
+            Ok(asset) => asset,
+            Err(e) => {
+                warn!("Unable to get release {tag} from GitHub, downloading {name} unchecked: {e}");
+                return download(output, url);
+            }
+        };
+
+        let size = match asset.sha256() {
+            Some(sha256) => download_with_checksum(output, url, sha256)?,
+            None => {
+                debug!("No digest for {name}, only checking its size");
+                download(output, url)?
+            }
+        };
+        check_size(&asset, size)?;
+
+        Ok(size)
+    }
+
+    fn release_asset(tag: &str, name: &str) -> Result<ReleaseAsset> {
+        let raw = http::agent()
+            .get(&format!("{API_URL}/tags/{tag}"))
+            .set("accept", "application/vnd.github+json")
+            .call()?;
+        let release: Release = serde_json::from_str(&raw.into_string()?)?;
+
+        release
+            .assets
+            .into_iter()
+            .find(|a| a.name == name)
+            .ok_or_else(|| ThermiteError::UnknownError(format!("Release {tag} has no {name}")))
+    }
+
+    fn check_size(asset: &ReleaseAsset, size: u64) -> Result<()> {
+        if asset.size == 0 || asset.size == size {
+            Ok(())
+        } else {
+            Err(ThermiteError::ChecksumMismatch {
+                expected: format!("{} bytes", asset.size),
+                actual: format!("{size} bytes"),
+            })
+        }
+    }
+
+    /// Extract the NorthstarProton tarball into a given directory.
+    /// Only supports extracting to a filesystem path.
+    ///
+    /// # Errors
+    /// * IO errors
+    pub fn install_ns_proton(archive: impl Read, dest: impl AsRef<Path>) -> Result<()> {
+        let mut tarball = Archive::new(GzDecoder::new(archive));
+        tarball.unpack(dest)?;
+
+        Ok(())
+    }
+
+    /// Extract the NorthstarProton tarball into Steam's `compatibilitytools.d`, which is where Steam
+    /// looks for compatibility tools
+    ///
+    /// # Returns
+    /// * the tool's internal name from its `compatibilitytool.vdf`, to pass to [`set_compat_tool`]
+    ///
+    /// # Errors
+    /// * Steam can't be found
+    /// * The tarball doesn't contain a `compatibilitytool.vdf`
+    /// * IO errors
+    pub fn install_ns_proton_to_steam(archive: impl Read) -> Result<String> {
+        let steam = steam_root().ok_or_else(|| {
+            ThermiteError::UnknownError("Unable to find the Steam directory".into())
+        })?;
+
+        install_compat_tool(archive, steam.join("compatibilitytools.d"))
+    }
+
+    /// Extracts into a staging folder in `tools` first, so Steam never sees a half-extracted tool and
+    /// nothing is left behind if the archive isn't a compatibility tool
+    fn install_compat_tool(archive: impl Read, tools: impl AsRef<Path>) -> Result<String> {
+        let tools = tools.as_ref();
+        let staging = TempDir::new_in(tools)?;
+
+        let mut tarball = Archive::new(GzDecoder::new(archive));
+        let mut extracted = vec![];
+        for entry in tarball.entries()? {
+            let mut entry = entry?;
+            // the first real folder, since tarballs made with `tar -C dir .` start with `./`
+            if let Some(Component::Normal(top)) = entry
+                .path()?
+                .components()
+                .find(|c| !matches!(c, Component::CurDir))
+            {
+                let top = PathBuf::from(top);
+                if !extracted.contains(&top) {
+                    extracted.push(top);
+                }
+            }
+            entry.unpack_in(&staging)?;
+        }
+
+        let name = extracted
+            .iter()
+            .find_map(|dir| {
+                let vdf =
+                    VdfDocument::load(staging.join(dir).join("compatibilitytool.vdf")).ok()?;
+                vdf.keys(&["compatibilitytools", "compat_tools"])
+                    .into_iter()
+                    .next()
+            })
+            .ok_or_else(|| {
+                ThermiteError::UnknownError(
+                    "Archive doesn't contain a compatibilitytool.vdf".into(),
+                )
+            })?;
+
+        for top in extracted {
+            let from = staging.join(&top);
+            if !from.exists() {
+                continue;
+            }
+            let to = tools.join(&top);
+            if to.is_dir() {
+                debug!("Replacing {}", to.display());
+                fs::remove_dir_all(&to)?;
+            } else if to.exists() {
+                fs::remove_file(&to)?;
+            }
+            fs::rename(from, to)?;
+        }
+
+        Ok(name)
+    }
+
+    /// The versions of NorthstarProton in Steam's `compatibilitytools.d`, oldest first, formatted
+    /// like the release tags without the `v`, e.g. `8-28`
+    ///
+    /// # Errors
+    /// * Steam can't be found
+    /// * IO errors
+    pub fn installed_ns_proton_versions() -> Result<Vec<String>> {
+        let steam = steam_root().ok_or_else(|| {
+            ThermiteError::UnknownError("Unable to find the Steam directory".into())
+        })?;
+
+        ns_proton_versions_in(&steam.join("compatibilitytools.d"))
+    }
+
+    /// Delete a version of NorthstarProton from Steam's `compatibilitytools.d`
+    ///
+    /// Steam falls back to its default Proton for any game still mapped to the removed version, so
+    /// make sure to [`set_compat_tool`] to a version that's still installed.
+    ///
+    /// # Params
+    /// * `version` - the version like [`installed_ns_proton_versions`] returns it, or a release tag
+    ///
+    /// # Errors
+    /// * Steam can't be found
+    /// * The version isn't installed
+    /// * IO errors
+    pub fn remove_ns_proton(version: impl AsRef<str>) -> Result<()> {
+        let steam = steam_root().ok_or_else(|| {
+            ThermiteError::UnknownError("Unable to find the Steam directory".into())
+        })?;
+
+        remove_ns_proton_in(&steam.join("compatibilitytools.d"), version.as_ref())
+    }
+
+    fn ns_proton_versions_in(tools: &Path) -> Result<Vec<String>> {
+        let dirs = match fs::read_dir(tools) {
+            Ok(dirs) => dirs,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut versions = vec![];
+        for dir in dirs {
+            let dir = dir?;
+            if !dir.file_type()?.is_dir() {
+                continue;
+            }
+            if let Some(version) = dir
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix("NorthstarProton"))
+                .filter(|v| !v.is_empty())
+            {
+                versions.push(version.to_owned());
+            }
+        }
+        // compare the numbers in the version, so 10-1 comes after 9-1
+        versions.sort_by_cached_key(|v| {
+            v.split(|c: char| !c.is_ascii_digit())
+                .map(|n| n.parse::<u32>().unwrap_or_default())
+                .collect::<Vec<_>>()
+        });
+
+        Ok(versions)
+    }
+
+    fn remove_ns_proton_in(tools: &Path, version: &str) -> Result<()> {
+        let version = version.trim_start_matches('v');
+        if version.is_empty()
+            || !version
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_'))
+        {
+            return Err(ThermiteError::UnknownError(format!(
+                "Invalid NorthstarProton version {version:?}"
+            )));
+        }
+
+        let dir = tools.join(format!("NorthstarProton{version}"));
+        if !dir.is_dir() {
+            return Err(ThermiteError::MissingFile(Box::new(dir)));
+        }
+        debug!("Removing {}", dir.display());
+        fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+
+    /// Make Steam run an app with a compatibility tool, e.g. NorthstarProton for Titanfall 2
+    ///
+    /// This is the same as picking the tool under "Force the use of a specific Steam Play
+    /// compatibility tool" in the game's properties
+    ///
+    /// # Params
+    /// * `app_id` - the Steam app id, like [`TITANFALL2_STEAM_ID`](crate::TITANFALL2_STEAM_ID)
+    /// * `tool_name` - the tool's internal name, as returned by [`install_ns_proton_to_steam`]
+    ///
+    /// # Errors
+    /// * Steam can't be found or is running, in which case it would overwrite the change when it exits
+    /// * The tool isn't installed in `compatibilitytools.d`
+    /// * IO errors
+    pub fn set_compat_tool(app_id: u32, tool_name: impl AsRef<str>) -> Result<()> {
+        let steam = steam_root().ok_or_else(|| {
+            ThermiteError::UnknownError("Unable to find the Steam directory".into())
+        })?;
+        if std::env::var_os("HOME").is_some_and(|home| super::steam_pid_running(Path::new(&home))) {
+            return Err(ThermiteError::SteamRunning);
+        }
+
+        set_compat_tool_in(&steam, app_id, tool_name.as_ref())
+    }
+
+    /// Like [`set_compat_tool`] for a Steam installation at `steam`, without checking if it's running
+    ///
+    /// The previous `config.vdf` is kept as `config.vdf.bak`
+    ///
+    /// # Errors
+    /// * The tool isn't installed in `compatibilitytools.d`
+    /// * IO errors
+    pub fn set_compat_tool_in(steam: impl AsRef<Path>, app_id: u32, tool_name: &str) -> Result<()> {
+        let steam = steam.as_ref();
+        let tools = steam.join("compatibilitytools.d");
+        if !has_compat_tool(&tools, tool_name) {
+            return Err(ThermiteError::MissingFile(Box::new(tools.join(tool_name))));
+        }
+
+        let config = steam.join("config").join("config.vdf");
+        let mut doc = VdfDocument::load(&config)?;
+        let app_id = app_id.to_string();
+        let mapping = [
+            "InstallConfigStore",
+            "Software",
+            "Valve",
+            "Steam",
+            "CompatToolMapping",
+            &app_id,
+        ];
+        for (key, value) in [("name", tool_name), ("config", ""), ("priority", "250")] {
+            let mut path = mapping.to_vec();
+            path.push(key);
+            doc.set(&path, value)?;
+        }
+
+        fs::copy(&config, config.with_extension("vdf.bak"))?;
+        doc.save(&config)
+    }
+
+    /// `true` if a tool in `compatibilitytools.d` declares `tool_name` in its `compatibilitytool.vdf`
+    ///
+    /// Steam only knows tools by their internal name, so a folder by that name isn't enough
+    fn has_compat_tool(tools: &Path, tool_name: &str) -> bool {
+        if tool_name.is_empty() || tool_name.contains(['/', '\\']) || tool_name == ".." {
+            return false;
+        }
+        let Ok(dirs) = fs::read_dir(tools) else {
+            return false;
+        };
+
+        dirs.flatten().any(|dir| {
+            VdfDocument::load(dir.path().join("compatibilitytool.vdf"))
+                .is_ok_and(|vdf| vdf.contains(&["compatibilitytools", "compat_tools", tool_name]))
+        })
+    }
+
+    /// The oldest Proton that runs Northstar without NorthstarProton's patches
+    pub const MIN_PROTON_VERSION: (u32, u32) = (8, 0);
+
+    /// Compatibility tools installed system-wide by distro packages, e.g. `proton-ge-custom`
+    const SYSTEM_COMPAT_TOOLS: [&str; 2] = [
+        "/usr/share/steam/compatibilitytools.d",
+        "/usr/local/share/steam/compatibilitytools.d",
+    ];
+
+    /// Who built a Proton install
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ProtonKind {
+        /// A numbered Proton release from Valve, e.g. `Proton 8.0`
+        Valve,
+        /// `Proton - Experimental` or `Proton Hotfix`, which track Valve's latest changes
+        Experimental,
+        /// GE-Proton, or Proton-GE for older releases
+        GloriousEggroll,
+        Northstar,
+        Other,
+    }
+
+    /// A Proton version installed in a Steam library or in `compatibilitytools.d`
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ProtonInstall {
+        /// The folder name, e.g. `GE-Proton9-1` or `Proton 8.0`
+        pub name: String,
+        pub path: PathBuf,
+        pub kind: ProtonKind,
+        /// The major and minor version from the name, if it has one
+        pub version: Option<(u32, u32)>,
+        /// The internal name to pass to [`set_compat_tool`], for tools in `compatibilitytools.d`.
+        /// Steam knows Valve's builds by names like `proton_8` that aren't recorded in their files
+        pub tool_name: Option<String>,
+    }
+
+    impl ProtonInstall {
+        /// `true` if this Proton is new enough to run Northstar, see [`MIN_PROTON_VERSION`]
+        #[must_use]
+        pub fn is_suitable(&self) -> bool {
+            match self.kind {
+                ProtonKind::Experimental | ProtonKind::Northstar => true,
+                _ => self.version.is_some_and(|v| v >= MIN_PROTON_VERSION),
+            }
+        }
+
+        fn new(path: PathBuf, tool_name: Option<String>) -> Option<Self> {
+            let name = path.file_name()?.to_str()?.to_owned();
+            let kind = if name.starts_with("NorthstarProton") {
+                ProtonKind::Northstar
+            } else if name.starts_with("GE-Proton") || name.contains("-GE-") {
+                ProtonKind::GloriousEggroll
+            } else if name == "Proton - Experimental" || name == "Proton Hotfix" {
+                ProtonKind::Experimental
+            } else if name.starts_with("Proton ") {
+                ProtonKind::Valve
+            } else {
+                ProtonKind::Other
+            };
+            let mut numbers = name
+                .split(|c: char| !c.is_ascii_digit())
+                .filter_map(|n| n.parse::<u32>().ok());
+            let version = numbers
+                .next()
+                .map(|major| (major, numbers.next().unwrap_or_default()));
+
+            Some(Self {
+                name,
+                path,
+                kind,
+                version,
+                tool_name,
+            })
+        }
+    }
+
+    /// Every Proton in Steam's libraries and `compatibilitytools.d`, including ones installed
+    /// system-wide
+    ///
+    /// Versions that can run Northstar come first, newest first
+    #[must_use]
+    pub fn installed_protons() -> Vec<ProtonInstall> {
+        let mut dirs = SYSTEM_COMPAT_TOOLS.map(PathBuf::from).to_vec();
+        let libraries = match steam_root() {
+            Some(steam) => {
+                dirs.insert(0, steam.join("compatibilitytools.d"));
+                super::library_paths(&steam)
+            }
+            None => vec![],
+        };
+
+        protons_in(&libraries, &dirs)
+    }
+
+    /// The Proton that [`installed_protons`] would pick first, if any of them can run Northstar
+    #[must_use]
+    pub fn recommended_proton() -> Option<ProtonInstall> {
+        installed_protons()
+            .into_iter()
+            .find(ProtonInstall::is_suitable)
     }
 
-    /// Extract the NorthstarProton tarball into a given directory.
-    /// Only supports extracting to a filesystem path.
-    ///
-    /// # Errors
-    /// * IO errors
-    pub fn install_ns_proton(archive: impl Read, dest: impl AsRef<Path>) -> Result<()> {
-        let mut tarball = Archive::new(GzDecoder::new(archive));
-        tarball.unpack(dest)?;
+    fn protons_in(libraries: &[PathBuf], compat_tools: &[PathBuf]) -> Vec<ProtonInstall> {
+        let mut protons = vec![];
+        for library in libraries {
+            let Ok(dirs) = fs::read_dir(library.join("steamapps").join("common")) else {
+                continue;
+            };
+            protons.extend(dirs.flatten().filter_map(|dir| {
+                let path = dir.path();
+                let is_proton = path.join("proton").is_file()
+                    && dir.file_name().to_string_lossy().starts_with("Proton");
+                is_proton.then(|| ProtonInstall::new(path, None)).flatten()
+            }));
+        }
+        for tools in compat_tools {
+            let Ok(dirs) = fs::read_dir(tools) else {
+                continue;
+            };
+            protons.extend(dirs.flatten().filter_map(|dir| {
+                let path = dir.path();
+                if !path.join("proton").is_file() {
+                    return None;
+                }
+                let tool_name = VdfDocument::load(path.join("compatibilitytool.vdf"))
+                    .ok()
+                    .and_then(|vdf| {
+                        vdf.keys(&["compatibilitytools", "compat_tools"])
+                            .into_iter()
+                            .next()
+                    });
+                ProtonInstall::new(path, tool_name)
+            }));
+        }
 
-        Ok(())
+        protons.sort_by(|a, b| {
+            b.is_suitable()
+                .cmp(&a.is_suitable())
+                .then(b.version.cmp(&a.version))
+                .then(a.name.cmp(&b.name))
+        });
+        protons
+    }
+
+    /// The Steam directory, for any flavor of Steam
+    fn steam_root() -> Option<PathBuf> {
+        let home = std::env::var_os("HOME")?;
+        super::find_steam_in(Path::new(&home))
     }
 
     #[cfg(test)]
     mod test {
         use std::io::Cursor;
 
-        use crate::core::utils::TempDir;
+        use crate::{
+            core::{
+                utils::{library_paths, TempDir},
+                vdf::VdfDocument,
+            },
+            error::ThermiteError,
+            model::ReleaseAsset,
+        };
 
-        use super::latest_release;
+        use super::{
+            check_size, install_compat_tool, latest_release, ns_proton_versions_in, protons_in,
+            remove_ns_proton_in, set_compat_tool_in, ProtonKind,
+        };
 
         #[test]
         fn get_latest_proton_version() {
@@ -389,6 +2949,214 @@ pub(crate) mod proton {
                 "The real proton was too big to use as test media\n"
             );
         }
+
+        #[test]
+        fn install_into_compat_tools() {
+            let tools = TempDir::create("./proton_compat_tools").expect("temp dir");
+            let vdf = "\"compatibilitytools\"\n{\n  \"compat_tools\"\n  {\n    \"NorthstarProton8-28\" // Internal name\n    {\n      \"install_path\" \".\"\n    }\n  }\n}\n";
+            let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+                vec![],
+                flate2::Compression::fast(),
+            ));
+            for (path, contents) in [
+                ("NorthstarProton8-28/compatibilitytool.vdf", vdf),
+                ("NorthstarProton8-28/proton", "#!/usr/bin/env python3\n"),
+            ] {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(contents.len() as u64);
+                header.set_mode(0o755);
+                header.set_cksum();
+                builder
+                    .append_data(&mut header, path, contents.as_bytes())
+                    .expect("append file");
+            }
+            let archive = builder
+                .into_inner()
+                .and_then(|gz| gz.finish())
+                .expect("finish archive");
+
+            let name = install_compat_tool(Cursor::new(&archive), &tools).expect("install tool");
+            assert_eq!(name, "NorthstarProton8-28");
+            assert!(tools.join("NorthstarProton8-28/proton").is_file());
+
+            let media = include_bytes!("test_media/NorthstarProton8-28.tar.gz");
+            assert!(install_compat_tool(Cursor::new(media), &tools).is_err());
+            assert!(install_compat_tool(Cursor::new(&b"not a tarball"[..]), &tools).is_err());
+            let left = std::fs::read_dir(&tools)
+                .expect("read dir")
+                .map(|e| e.expect("dir entry").file_name())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                left,
+                ["NorthstarProton8-28"],
+                "failed installs left files behind"
+            );
+
+            let name = install_compat_tool(Cursor::new(&archive), &tools).expect("reinstall tool");
+            assert_eq!(name, "NorthstarProton8-28");
+            assert!(tools.join("NorthstarProton8-28/proton").is_file());
+        }
+
+        #[test]
+        fn find_suitable_protons() {
+            let steam = TempDir::create("./proton_installs").expect("temp dir");
+            let library = steam.join("library");
+            let system = steam.join("system");
+            std::fs::create_dir_all(steam.join("steamapps")).expect("create dir");
+            std::fs::write(
+                steam.join("steamapps/libraryfolders.vdf"),
+                format!(
+                    "\"libraryfolders\"\n{{\n\t\"0\"\n\t{{\n\t\t\"path\"\t\t\"{}\"\n\t}}\n\t\"1\"\n\t{{\n\t\t\"path\"\t\t\"{}\"\n\t}}\n}}\n",
+                    steam.display(),
+                    library.display()
+                ),
+            )
+            .expect("write libraryfolders.vdf");
+            for dir in [
+                steam.join("steamapps/common/Proton 7.0"),
+                steam.join("steamapps/common/Proton - Experimental"),
+                library.join("steamapps/common/Proton 9.0 (Beta)"),
+                steam.join("compatibilitytools.d/GE-Proton8-16"),
+                steam.join("compatibilitytools.d/NorthstarProton8-28"),
+                system.join("Proton-6.21-GE-2"),
+            ] {
+                std::fs::create_dir_all(&dir).expect("create dir");
+                std::fs::write(dir.join("proton"), "").expect("write proton");
+            }
+            std::fs::create_dir_all(steam.join("steamapps/common/Titanfall2")).expect("create dir");
+            std::fs::write(
+                steam.join("compatibilitytools.d/GE-Proton8-16/compatibilitytool.vdf"),
+                "\"compatibilitytools\" { \"compat_tools\" { \"GE-Proton8-16\" { } } }",
+            )
+            .expect("write compatibilitytool.vdf");
+
+            let libraries = library_paths(&steam);
+            assert_eq!(libraries, [steam.to_path_buf(), library]);
+            let protons = protons_in(&libraries, &[steam.join("compatibilitytools.d"), system]);
+            let names = protons.iter().map(|p| p.name.as_str()).collect::<Vec<_>>();
+            assert_eq!(
+                names,
+                [
+                    "Proton 9.0 (Beta)",
+                    "NorthstarProton8-28",
+                    "GE-Proton8-16",
+                    "Proton - Experimental",
+                    "Proton 7.0",
+                    "Proton-6.21-GE-2"
+                ]
+            );
+            assert_eq!(protons[0].version, Some((9, 0)));
+            assert_eq!(protons[2].kind, ProtonKind::GloriousEggroll);
+            assert_eq!(protons[2].tool_name.as_deref(), Some("GE-Proton8-16"));
+            assert_eq!(protons[3].kind, ProtonKind::Experimental);
+            assert!(!protons[4].is_suitable());
+            assert_eq!(protons[5].version, Some((6, 21)));
+        }
+
+        #[test]
+        fn reject_truncated_download() {
+            let asset: ReleaseAsset = serde_json::from_str(
+                r#"{"name": "NorthstarProton8-28.tar.gz", "browser_download_url": "", "size": 1024}"#,
+            )
+            .expect("parse asset");
+
+            assert!(check_size(&asset, 1024).is_ok());
+            assert!(matches!(
+                check_size(&asset, 512),
+                Err(ThermiteError::ChecksumMismatch { expected, .. }) if expected == "1024 bytes"
+            ));
+        }
+
+        #[test]
+        fn prune_ns_proton() {
+            let tools = TempDir::create("./proton_prune").expect("temp dir");
+            assert!(ns_proton_versions_in(&tools.join("missing"))
+                .expect("list missing dir")
+                .is_empty());
+
+            for dir in [
+                "NorthstarProton9-1",
+                "NorthstarProton8-28",
+                "NorthstarProton10-1",
+                "GE-Proton9-1",
+            ] {
+                std::fs::create_dir_all(tools.join(dir)).expect("create dir");
+            }
+            std::fs::write(tools.join("NorthstarProton7-1"), "").expect("write file");
+            assert_eq!(
+                ns_proton_versions_in(&tools).expect("list versions"),
+                ["8-28", "9-1", "10-1"]
+            );
+
+            remove_ns_proton_in(&tools, "v8-28").expect("remove by tag");
+            remove_ns_proton_in(&tools, "9-1").expect("remove by version");
+            assert!(matches!(
+                remove_ns_proton_in(&tools, "9-1"),
+                Err(ThermiteError::MissingFile(_))
+            ));
+            assert!(remove_ns_proton_in(&tools, "../GE-Proton9-1").is_err());
+            assert!(tools.join("GE-Proton9-1").is_dir());
+            assert_eq!(
+                ns_proton_versions_in(&tools).expect("list versions"),
+                ["10-1"]
+            );
+        }
+
+        #[test]
+        fn assign_compat_tool() {
+            let steam = TempDir::create("./proton_compat_tool").expect("temp dir");
+            let tool = steam.join("compatibilitytools.d/NorthstarProton8-28");
+            std::fs::create_dir_all(&tool).expect("create dir");
+            std::fs::create_dir_all(steam.join("config")).expect("create dir");
+            std::fs::write(
+                tool.join("compatibilitytool.vdf"),
+                "\"compatibilitytools\"\n{\n  \"compat_tools\"\n  {\n    \"NorthstarProton\"\n    {\n      \"install_path\" \".\"\n    }\n  }\n}\n",
+            )
+            .expect("write compatibilitytool.vdf");
+            let config = steam.join("config/config.vdf");
+            std::fs::write(
+                &config,
+                "\"InstallConfigStore\"\n{\n\t\"Software\"\n\t{\n\t\t\"Valve\"\n\t\t{\n\t\t\t\"Steam\"\n\t\t\t{\n\t\t\t}\n\t\t}\n\t}\n}\n",
+            )
+            .expect("write config.vdf");
+
+            assert!(matches!(
+                set_compat_tool_in(&steam, crate::TITANFALL2_STEAM_ID, "GE-Proton9-1"),
+                Err(ThermiteError::MissingFile(_))
+            ));
+            for name in ["NorthstarProton8-28", "../compatibilitytools.d", "..", ""] {
+                assert!(
+                    matches!(
+                        set_compat_tool_in(&steam, crate::TITANFALL2_STEAM_ID, name),
+                        Err(ThermiteError::MissingFile(_))
+                    ),
+                    "{name:?} isn't a declared tool"
+                );
+            }
+            set_compat_tool_in(&steam, crate::TITANFALL2_STEAM_ID, "NorthstarProton")
+                .expect("set compat tool");
+            set_compat_tool_in(&steam, crate::TITANFALL2_STEAM_ID, "NorthstarProton")
+                .expect("set compat tool again");
+
+            let doc = VdfDocument::load(&config).expect("load config.vdf");
+            let mapping = [
+                "InstallConfigStore",
+                "Software",
+                "Valve",
+                "Steam",
+                "CompatToolMapping",
+                "1237970",
+            ];
+            let get = |key| doc.get(&[&mapping[..], &[key]].concat());
+            assert_eq!(get("name").as_deref(), Some("NorthstarProton"));
+            assert_eq!(get("priority").as_deref(), Some("250"));
+            assert_eq!(
+                doc.as_str().matches("\"1237970\"").count(),
+                1,
+                "mapping was added twice"
+            );
+            assert!(steam.join("config/config.vdf.bak").is_file());
+        }
     }
 }
 
@@ -400,10 +3168,21 @@ mod test {
         path::{Path, PathBuf},
     };
 
-    use crate::{error::ThermiteError, model::Mod};
+    use crate::{
+        core::manage::AUTHOR_FILE,
+        error::ThermiteError,
+        model::{EnabledMods, LaunchMode, Mod, ModEntry, ModVersion, PackageIndex, Side},
+    };
 
     use super::{
-        find_mods, get_enabled_mods, parse_modstring, resolve_deps, validate_modstring, TempDir,
+        argv0_path, check_write_access, compare_versions, detect_core_mods, extended_path,
+        find_all_mods, find_incompatibilities, find_mods, find_mods_with, find_mods_with_state,
+        find_plugins, find_steam_in, get_enabled_mods, get_enabled_mods_or_default,
+        get_launch_mode, get_startup_args, launch_options_mode, library_paths, mod_states,
+        parse_modstring, process_name, resolve_deps, resolve_deps_checked, resolve_deps_recursive,
+        set_launch_mode, set_launch_options_mode, tasklist_name, validate_modstring,
+        which_outdated, write_atomic, write_atomic_with, DiscoveryReport, FindOptions, TempDir, RE,
+        RUN_NORTHSTAR_FILE, STARTUP_ARGS_DEDI_FILE, STARTUP_ARGS_FILE,
     };
 
     #[test]
@@ -428,6 +3207,16 @@ mod test {
         assert!(!exists);
     }
 
+    #[test]
+    fn temp_dir_refuses_existing() {
+        let parent = TempDir::create("./temp_dir_existing").expect("Temp dir");
+        fs::write(parent.join("keep.txt"), "").expect("write file");
+
+        let err = TempDir::create(&*parent).expect_err("existing dir");
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        assert!(parent.join("keep.txt").is_file());
+    }
+
     #[test]
     fn fail_find_enabledmods() {
         let test_folder = "fail_enabled_mods_test";
@@ -447,7 +3236,7 @@ mod test {
         let test_folder = "parse_enabled_mods_test";
         let temp_dir = TempDir::create(test_folder).unwrap();
         fs::write(temp_dir.join("enabledmods.json"), b"invalid json").unwrap();
-        if let Err(ThermiteError::JsonError(_)) = get_enabled_mods(temp_dir) {
+        if let Err(ThermiteError::JsonError(_)) = get_enabled_mods(&temp_dir) {
         } else {
             panic!("enabledmods.json should not be valid json");
         }
@@ -458,26 +3247,223 @@ mod test {
         let test_folder = "pass_enabled_mods_test";
         let temp_dir = TempDir::create(test_folder).unwrap();
         fs::write(temp_dir.join("enabledmods.json"), b"{}").unwrap();
-        if let Ok(mods) = get_enabled_mods(temp_dir) {
-            assert!(mods.client);
-            assert!(mods.custom);
-            assert!(mods.servers);
-            assert!(mods.mods.is_empty());
+        if let Ok(mods) = get_enabled_mods(&temp_dir) {
+            assert_eq!(mods.get("Northstar.Client"), Some(true));
+            assert_eq!(mods.get("Northstar.Custom"), Some(true));
+            assert_eq!(mods.get("Northstar.CustomServers"), Some(true));
+            assert_eq!(mods.len(), 3);
         } else {
             panic!("enabledmods.json should be valid but empty");
         }
     }
 
+    #[test]
+    fn unique_temp_dirs() {
+        let parent = TempDir::create("./unique_temp_dirs").expect("Temp dir");
+        let a = TempDir::new_in(&parent).expect("temp dir");
+        let b = TempDir::new_in(&parent).expect("temp dir");
+        assert_ne!(a.path(), b.path());
+        assert!(a.starts_with(&*parent));
+
+        let a_path = a.path().to_path_buf();
+        fs::create_dir_all(a.join("nested/deeper")).expect("create dir");
+        drop(a);
+        assert!(!a_path.exists());
+
+        let mut b = b;
+        b.keep();
+        let b_path = b.path().to_path_buf();
+        drop(b);
+        assert!(b_path.exists());
+
+        let c = TempDir::new_in(&parent).expect("temp dir").into_path();
+        assert!(c.exists());
+    }
+
+    #[test]
+    fn drop_removed_temp_dir() {
+        let dir = TempDir::create("./removed_temp_dir").expect("Temp dir");
+        fs::remove_dir_all(&dir).expect("remove dir");
+    }
+
+    #[test]
+    fn detect_core_mods_by_marker() {
+        let dir = TempDir::create("./detect_core_mods").expect("Temp dir");
+        let mods = dir.join("R2Northstar").join("mods");
+        for folder in ["Northstar.Client", "Northstar.Coop", "Foo.Bar"] {
+            fs::create_dir_all(mods.join(folder)).expect("create dir");
+        }
+        fs::write(mods.join("Northstar.Coop").join(AUTHOR_FILE), "northstar")
+            .expect("write author");
+        fs::write(
+            mods.join("Northstar.Coop").join("mod.json"),
+            r#"{"Name": "Northstar.Coop", "Description": "", "Version": "1.0.0"}"#,
+        )
+        .expect("write mod.json");
+        fs::write(mods.join("Foo.Bar").join(AUTHOR_FILE), "foo").expect("write author");
+
+        assert_eq!(
+            detect_core_mods(&dir).expect("detect core mods"),
+            ["Northstar.Client", "Northstar.Coop"]
+        );
+        assert!(detect_core_mods(dir.join("missing"))
+            .expect("detect core mods")
+            .is_empty());
+
+        let profile = dir.join("R2Northstar");
+        let mut enabled = get_enabled_mods_or_default(&profile, false).expect("enabled mods");
+        assert!(enabled.is_core("northstar.coop"));
+        assert!(!enabled.is_core("Northstar.Custom"));
+        assert_eq!(enabled.get("Northstar.Coop"), Some(true));
+        enabled.set("Northstar.Custom", true);
+        enabled.save().expect("save enabled mods");
+        let mut enabled = get_enabled_mods(&profile).expect("enabled mods");
+        enabled.dont_save();
+        assert_eq!(enabled.remove("Northstar.Coop"), None);
+
+        let empty = dir.join("Empty");
+        fs::create_dir_all(&empty).expect("create dir");
+        let mut enabled = get_enabled_mods_or_default(&empty, false).expect("enabled mods");
+        enabled.dont_save();
+        assert!(enabled.is_core("Northstar.Custom"));
+    }
+
+    #[test]
+    fn default_missing_enabledmods() {
+        let temp_dir = TempDir::create("default_enabled_mods_test").expect("Temp dir");
+        let path = temp_dir
+            .canonicalize()
+            .expect("canonicalize")
+            .join("enabledmods.json");
+
+        let mut mods = get_enabled_mods_or_default(&temp_dir, false).expect("default mods");
+        mods.set("Foo.Bar", true);
+        assert_eq!(mods.path(), Some(&path));
+        drop(mods);
+        assert!(!path.exists(), "default was saved on drop");
+
+        let mut mods = get_enabled_mods_or_default(&temp_dir, true).expect("default mods");
+        mods.dont_save();
+        assert!(path.exists());
+
+        fs::write(&path, b"invalid json").expect("write enabledmods");
+        assert!(matches!(
+            get_enabled_mods_or_default(&temp_dir, true),
+            Err(ThermiteError::JsonError(_))
+        ));
+    }
+
+    #[test]
+    fn startup_args_defaults_to_empty() {
+        let temp_dir = TempDir::create("startup_args_test").unwrap();
+        let args = get_startup_args(&temp_dir, true).unwrap();
+        assert_eq!(args.iter().count(), 0);
+        assert_eq!(args.path(), Some(&temp_dir.join(STARTUP_ARGS_DEDI_FILE)));
+
+        fs::write(temp_dir.join(STARTUP_ARGS_FILE), "-multiple").unwrap();
+        assert!(get_startup_args(&temp_dir, false)
+            .unwrap()
+            .contains("-multiple"));
+    }
+
+    #[test]
+    fn read_write_launch_mode() {
+        let temp_dir = TempDir::create("launch_mode_test").unwrap();
+        assert_eq!(get_launch_mode(&temp_dir).unwrap(), None);
+
+        set_launch_mode(&temp_dir, LaunchMode::Vanilla).unwrap();
+        assert_eq!(
+            fs::read_to_string(temp_dir.join(RUN_NORTHSTAR_FILE)).unwrap(),
+            "0"
+        );
+        assert_eq!(
+            get_launch_mode(&temp_dir).unwrap(),
+            Some(LaunchMode::Vanilla)
+        );
+
+        set_launch_mode(&temp_dir, LaunchMode::Northstar).unwrap();
+        assert_eq!(
+            get_launch_mode(&temp_dir).unwrap(),
+            Some(LaunchMode::Northstar)
+        );
+    }
+
+    #[test]
+    fn detect_launch_options() {
+        assert_eq!(
+            launch_options_mode(r#""C:\Titanfall2\NorthstarLauncher.exe" %command%"#),
+            LaunchMode::Northstar
+        );
+        assert_eq!(
+            launch_options_mode("-multiple -northstar"),
+            LaunchMode::Northstar
+        );
+        assert_eq!(launch_options_mode("-novid"), LaunchMode::Vanilla);
+
+        assert_eq!(
+            set_launch_options_mode("-novid -vanilla %command%", LaunchMode::Northstar),
+            "-novid %command% -northstar"
+        );
+        assert_eq!(set_launch_options_mode("", LaunchMode::Vanilla), "-vanilla");
+        assert_eq!(
+            set_launch_options_mode("-northstar  -novid", LaunchMode::Vanilla),
+            "-novid -vanilla"
+        );
+        assert_eq!(
+            set_launch_options_mode(r#"+exec "my  config.cfg" -vanilla"#, LaunchMode::Northstar),
+            r#"+exec "my  config.cfg" -northstar"#
+        );
+        assert_eq!(
+            set_launch_options_mode(r#""-vanilla  x" -multiple"#, LaunchMode::Northstar),
+            r#""-vanilla  x" -multiple -northstar"#
+        );
+
+        // can't be switched to vanilla without replacing the launcher
+        let launcher = r#""C:\Titanfall2\NorthstarLauncher.exe" %command%"#;
+        assert_eq!(
+            set_launch_options_mode(launcher, LaunchMode::Vanilla),
+            launcher
+        );
+    }
+
+    #[test]
+    fn write_file_atomically() {
+        let temp_dir = TempDir::create("write_atomic_test").unwrap();
+        let path = temp_dir.join("nested").join("file.txt");
+        write_atomic(&path, "first").unwrap();
+        write_atomic(&path, "second").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(temp_dir.join("nested").read_dir().unwrap().count(), 1);
+
+        write_atomic_with(&path, "third", false).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "third");
+
+        let enabled_path = temp_dir.join("enabledmods.json");
+        let mut enabled = EnabledMods::default_with_path(&enabled_path);
+        enabled.dont_save();
+        enabled.set("Foo.Bar", false);
+        enabled.save().unwrap();
+        assert_eq!(
+            EnabledMods::load(&enabled_path).unwrap().get("Foo.Bar"),
+            Some(false)
+        );
+        assert_eq!(temp_dir.read_dir().unwrap().count(), 2);
+    }
+
     #[test]
     fn reolve_dependencies() {
         let test_index: &[Mod] = &[Mod {
             name: "test".into(),
             latest: "0.1.0".into(),
-            upgradable: false,
-            global: false,
-            installed: false,
             versions: BTreeMap::new(),
             author: "Foo".into(),
+            rating_score: 0,
+            deprecated: false,
+            categories: vec![],
+            date_created: String::new(),
+            date_updated: String::new(),
+            ..Default::default()
         }];
 
         let test_deps = &["foo-test-0.1.0"];
@@ -493,11 +3479,14 @@ mod test {
         let test_index: &[Mod] = &[Mod {
             name: "Northstar".into(),
             latest: "0.1.0".into(),
-            upgradable: false,
-            global: false,
-            installed: false,
             versions: BTreeMap::new(),
             author: "Northstar".into(),
+            rating_score: 0,
+            deprecated: false,
+            categories: vec![],
+            date_created: String::new(),
+            date_updated: String::new(),
+            ..Default::default()
         }];
 
         let test_deps = &["Northstar-Northstar-0.1.0"];
@@ -513,11 +3502,14 @@ mod test {
         let test_index: &[Mod] = &[Mod {
             name: "test".into(),
             latest: "0.1.0".into(),
-            upgradable: false,
-            global: false,
-            installed: false,
             versions: BTreeMap::new(),
             author: "Foo".into(),
+            rating_score: 0,
+            deprecated: false,
+            categories: vec![],
+            date_created: String::new(),
+            date_updated: String::new(),
+            ..Default::default()
         }];
 
         let test_deps = &["foo-test@0.1.0"];
@@ -557,6 +3549,46 @@ mod test {
         }
     }
 
+    #[test]
+    fn parse_messy_modstrings() {
+        let cases = [
+            ("S2Mods-Mod_2.0-1.0.0", ("S2Mods", "Mod_2.0", "1.0.0")),
+            (
+                "author-My-Hyphenated-Mod-2.1.3",
+                ("author", "My-Hyphenated-Mod", "2.1.3"),
+            ),
+            ("author-mod-1.0.0.1", ("author", "mod", "1.0.0.1")),
+            (
+                "Author123-Mod456-10.20.30",
+                ("Author123", "Mod456", "10.20.30"),
+            ),
+            ("_under_-__name__-0.0.1", ("_under_", "__name__", "0.0.1")),
+        ];
+        for (input, (author, name, version)) in cases {
+            assert!(validate_modstring(input), "{input} should be valid");
+            assert_eq!(
+                parse_modstring(input).expect("parse modstring"),
+                (author.into(), name.into(), version.into())
+            );
+            assert!(RE.is_match(input), "{input} should match RE");
+        }
+
+        for input in [
+            "author-mod-1.0",
+            "author-mod-1.0.0.0.0",
+            "author-mod-1.0.0-beta",
+            "author-mod-v1.0.0",
+            "-mod-1.0.0",
+            "author--1.0.0",
+            "author-mod-1..0",
+            "author-../mod-1.0.0",
+            "author-mod name-1.0.0",
+        ] {
+            assert!(!validate_modstring(input), "{input} should be invalid");
+            assert!(!RE.is_match(input), "{input} shouldn't match RE");
+        }
+    }
+
     #[test]
     fn fail_parse_modstring() {
         let test_string = "invalid";
@@ -608,9 +3640,482 @@ mod test {
             assert_eq!(mods.len(), 1, "Should be one mod");
             assert_eq!(mods[0].manifest.name, "Northstar");
             assert_eq!(mods[0].author, "northstar");
+            assert_eq!(mods[0].version, "1.2.3");
             assert_eq!(mods[0].mod_json.name, "Yourname.Modname");
         } else {
             panic!("Mod discovery failed: {res:?}");
         }
     }
+
+    #[test]
+    fn discover_nested_mods() {
+        let dir = TempDir::create("./nested_mod_discovery").expect("Temp dir");
+        let root = dir.join("packages").join("northstar-mod-1.2.3");
+        let nested = root.join("extra").join("mods").join("RealMod");
+        fs::create_dir_all(&nested).expect("create dir");
+        fs::write(root.join("manifest.json"), MANIFEST).expect("write manifest");
+        fs::write(nested.join("mod.json"), MOD_JSON).expect("write mod.json");
+
+        let packages = dir.join("packages");
+        let mods = find_mods_with(&packages, &FindOptions::new()).expect("find mods");
+        assert_eq!(mods.len(), 1);
+        assert!(mods[0].path.ends_with("extra/mods/RealMod"));
+
+        let shallow = find_mods_with(&packages, &FindOptions::new().max_depth(2));
+        assert!(shallow.expect("find mods").is_empty());
+
+        fs::write(
+            dir.join("enabledmods.json"),
+            r#"{"Yourname.Modname": false}"#,
+        )
+        .expect("write enabledmods");
+        let enabled = find_mods_with(&packages, &FindOptions::new().include_disabled(false));
+        assert!(enabled.expect("find mods").is_empty());
+    }
+
+    #[test]
+    fn discover_mods_by_side() {
+        let dir = TempDir::create("./side_mod_discovery").expect("Temp dir");
+        setup_mods(&dir);
+
+        let client = find_mods_with(&dir, &FindOptions::new().side(Side::Client));
+        assert_eq!(client.expect("find mods").len(), 1);
+        let server = find_mods_with(&dir, &FindOptions::new().side(Side::Server));
+        assert!(server.expect("find mods").is_empty());
+    }
+
+    #[test]
+    fn discover_mods_past_broken_entries() {
+        let dir = TempDir::create("./broken_mod_discovery").expect("Temp dir");
+        setup_mods(&dir);
+        let bad_manifest = dir.join("foo-bad_manifest-1.0.0");
+        fs::create_dir_all(&bad_manifest).expect("create dir");
+        fs::write(bad_manifest.join("manifest.json"), "{").expect("write manifest");
+        let bad_name = dir.join("not a modstring").join("RealMod");
+        fs::create_dir_all(&bad_name).expect("create dir");
+        fs::write(dir.join("not a modstring/manifest.json"), MANIFEST).expect("write manifest");
+        fs::write(bad_name.join("mod.json"), MOD_JSON).expect("write mod.json");
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.join("missing"), dir.join("foo-dangling-1.0.0"))
+            .expect("create symlink");
+
+        let report = super::discover_mods(&dir, &FindOptions::new().follow_symlinks(true))
+            .expect("discover mods");
+        assert_eq!(report.mods.len(), 1);
+        assert_eq!(report.mods[0].mod_json.name, "Yourname.Modname");
+        assert_eq!(report.problems.len(), if cfg!(unix) { 3 } else { 2 });
+        assert!(!report.is_clean());
+        assert!(report
+            .problems
+            .iter()
+            .any(|p| matches!(p.error, ThermiteError::NameError(_))));
+
+        assert_eq!(find_mods(&dir).expect("find mods").len(), 1);
+
+        let json = serde_json::to_value(&report).expect("serialize report");
+        assert_eq!(
+            json["mods"][0]["path"],
+            report.mods[0].path.to_string_lossy().as_ref()
+        );
+        let message = report.problems[0].error.to_string();
+        assert!(json["problems"]
+            .as_array()
+            .expect("problems")
+            .iter()
+            .any(|p| p["error"] == message.as_str()));
+
+        let de: DiscoveryReport = serde_json::from_value(json).expect("deserialize report");
+        assert_eq!(de.mods[0].full_name(), report.mods[0].full_name());
+        assert_eq!(de.mods[0].path, report.mods[0].path);
+        assert!(matches!(&de.problems[0].error, ThermiteError::UnknownError(e) if *e == message));
+    }
+
+    #[test]
+    fn discover_all_mods() {
+        let dir = TempDir::create("./all_mod_discovery").expect("Temp dir");
+        setup_mods(dir.join("packages"));
+        for (folder, name) in [
+            ("Northstar.Client", "Northstar.Client"),
+            ("Manual", "Foo.Manual"),
+        ] {
+            let path = dir.join("mods").join(folder);
+            fs::create_dir_all(&path).expect("create dir");
+            fs::write(
+                path.join("mod.json"),
+                format!(r#"{{"Name": "{name}", "Description": "", "Version": "1.0.0"}}"#),
+            )
+            .expect("write mod.json");
+        }
+        fs::create_dir_all(dir.join("mods/Empty")).expect("create dir");
+
+        let mods = find_all_mods(&dir).expect("find mods");
+        assert_eq!(mods.len(), 3);
+        assert!(matches!(&mods[0], ModEntry::Bare(m) if m.mod_json.name == "Foo.Manual"));
+        assert!(mods[1].is_core());
+        assert!(matches!(&mods[2], ModEntry::Package(m) if m.author == "northstar"));
+        assert_eq!(mods[2].mod_json().name, "Yourname.Modname");
+
+        let json = serde_json::to_value(&mods).expect("serialize mods");
+        assert_eq!(json[1]["kind"], "core");
+        assert_eq!(json[2]["kind"], "package");
+        let de: Vec<ModEntry> = serde_json::from_value(json).expect("deserialize mods");
+        assert!(matches!(&de[2], ModEntry::Package(m) if m.author == "northstar"));
+    }
+
+    #[test]
+    fn discover_mods_with_state() {
+        let dir = TempDir::create("./mod_state_discovery").expect("Temp dir");
+        let packages = dir.join("packages");
+        setup_mods(&packages);
+
+        let mods = find_mods_with_state(&packages).expect("find mods");
+        assert_eq!(mods.len(), 1);
+        assert!(mods[0].1);
+
+        fs::write(
+            dir.join("enabledmods.json"),
+            r#"{"Yourname.Modname": false}"#,
+        )
+        .expect("write enabledmods");
+        let mods = find_mods_with_state(&packages).expect("find mods");
+        assert_eq!(mods[0].0.mod_json.name, "Yourname.Modname");
+        assert!(!mods[0].1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn discover_symlinked_mods() {
+        let dir = TempDir::create("./symlink_mod_discovery").expect("Temp dir");
+        let source = dir.join("source");
+        setup_mods(&source);
+        let packages = dir.join("packages");
+        fs::create_dir_all(&packages).expect("create dir");
+        std::os::unix::fs::symlink(
+            source
+                .join("northstar-mod-1.2.3")
+                .canonicalize()
+                .expect("canonicalize"),
+            packages.join("northstar-mod-1.2.3"),
+        )
+        .expect("create symlink");
+
+        assert!(find_mods(&packages).expect("find mods").is_empty());
+        let mods = find_mods_with(&packages, &FindOptions::new().follow_symlinks(true))
+            .expect("find mods");
+        assert_eq!(mods.len(), 1);
+    }
+
+    #[test]
+    fn installed_mod_accessors() {
+        let dir = TempDir::create("./installed_mod_accessors").expect("Temp dir");
+        setup_mods(&dir);
+        let installed = find_mods(&dir).expect("find mods").remove(0);
+
+        assert_eq!(installed.full_name(), "northstar-Northstar-1.22.0");
+        assert_eq!(installed.manifest_version(), "1.22.0");
+        assert_eq!(installed.version, "1.2.3");
+        assert!(installed.is_core());
+        assert!(installed.mod_dir().ends_with("RealMod"));
+        assert_eq!(
+            installed.package_dir(&dir),
+            installed.mod_dir().parent(),
+            "the package is the folder with the manifest"
+        );
+        assert_eq!(installed.package_dir(dir.join("other")), None);
+
+        // the closest manifest is outside of the package
+        let mut loose = installed.clone();
+        loose.path = dir.join("loose").join("RealMod");
+        fs::create_dir_all(&loose.path).expect("create dir");
+        fs::write(dir.join("manifest.json"), MANIFEST).expect("write manifest");
+        assert_eq!(loose.package_dir(dir.join("loose")), None);
+        assert_eq!(loose.package_dir(&dir), None);
+
+        let mut enabled = EnabledMods::default();
+        enabled.dont_save();
+        assert!(installed.enabled_in(&enabled));
+        enabled.set("Yourname.Modname", false);
+        assert!(!installed.enabled_in(&enabled));
+    }
+
+    #[test]
+    fn find_outdated_packages() {
+        let dir = TempDir::create("./outdated_discovery").expect("Temp dir");
+        setup_mods(&dir);
+        let installed = find_mods(&dir).expect("find mods");
+
+        let mut versions = vec![
+            ModVersion::test("northstar-Northstar-1.10.0", &[]),
+            ModVersion::test("northstar-Northstar-1.2.3", &[]),
+        ];
+        let mut index = dep_index(versions.clone());
+        let outdated = which_outdated(&installed, &index.clone().into());
+        assert_eq!(outdated.len(), 1);
+        assert_eq!(outdated[0].installed, "1.2.3");
+        assert_eq!(outdated[0].latest.version, "1.10.0");
+
+        versions.remove(0);
+        index = dep_index(versions);
+        assert!(which_outdated(&installed, &index.into()).is_empty());
+    }
+
+    #[test]
+    fn read_plugin_version() {
+        let dir = TempDir::create("./plugin_version").expect("Temp dir");
+        let mut raw = b"MZ\0\0".to_vec();
+        raw.extend(
+            "VS_VERSION_INFO\0"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+        raw.extend([0, 0]);
+        for word in [0xFEEF_04BD_u32, 0x0001_0000, 1 << 16 | 2, 3 << 16 | 4] {
+            raw.extend(word.to_le_bytes());
+        }
+        fs::write(dir.join("Versioned.dll"), raw).expect("write plugin");
+        fs::write(dir.join("Plain.DLL"), "MZ").expect("write plugin");
+        fs::write(dir.join("readme.txt"), "").expect("write file");
+
+        let plugins = find_plugins(&dir).expect("find plugins");
+        assert_eq!(plugins.len(), 2);
+        assert_eq!(plugins[0].name, "Plain");
+        assert_eq!(plugins[0].version, None);
+        assert_eq!(plugins[1].name, "Versioned");
+        assert_eq!(plugins[1].version.as_deref(), Some("1.2.3.4"));
+    }
+
+    #[test]
+    fn compare_version_strings() {
+        use std::cmp::Ordering;
+
+        assert_eq!(compare_versions("0.10.0", "0.9.0"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.0", "1.0.0.1"), Ordering::Less);
+        assert_eq!(compare_versions("1.2.0", "1.10.0"), Ordering::Less);
+    }
+
+    fn dep_index(versions: Vec<ModVersion>) -> Vec<Mod> {
+        let mut index: Vec<Mod> = vec![];
+        for v in versions {
+            let (author, name, _) = parse_modstring(&v.full_name).expect("modstring");
+            if let Some(m) = index.iter_mut().find(|m| m.name == name) {
+                m.versions.insert(v.version.clone(), v);
+            } else {
+                index.push(Mod {
+                    name,
+                    author,
+                    latest: v.version.clone(),
+                    versions: BTreeMap::from([(v.version.clone(), v)]),
+                    ..Default::default()
+                });
+            }
+        }
+        index
+    }
+
+    #[test]
+    fn join_index_with_installed() {
+        let dir = TempDir::create("./mod_states").expect("Temp dir");
+        setup_mods(&dir);
+        let installed = find_mods(&dir).expect("find mods");
+        let index = PackageIndex::from(dep_index(vec![
+            ModVersion::test("Northstar-northstar-1.23.0", &[]),
+            ModVersion::test("foo-lib-1.0.0", &[]),
+        ]));
+
+        let states = mod_states(&index, &installed);
+        assert_eq!(states.len(), 2);
+        assert!(states[0].is_installed());
+        assert_eq!(
+            states[0].installed_version().expect("installed version"),
+            "1.22.0"
+        );
+        assert!(states[0].is_upgradable());
+        assert!(!states[1].is_installed());
+        assert!(!states[1].is_upgradable());
+        assert_eq!(Mod::from(states[1].remote.clone()), index[1]);
+    }
+
+    #[test]
+    fn refuse_incompatible_plan() {
+        let dir = TempDir::create("./incompatible_plan").expect("Temp dir");
+        setup_mods(&dir);
+        let mut installed = find_mods(&dir).expect("find mods");
+        installed[0].manifest.incompatibilities = Some(vec!["FOO-lib-0.1.0".into()]);
+        installed[0].mod_json.incompatibilities = vec!["Other.Mod".into()];
+        assert!(installed[0].is_incompatible_with("foo-lib"));
+        assert!(!installed[0].is_incompatible_with("foo-app"));
+        assert!(installed[0].is_incompatible_with_mod("other.mod"));
+        assert!(!installed[0].is_incompatible_with_mod("foo-lib"));
+
+        let index = dep_index(vec![
+            ModVersion::test("foo-lib-1.0.0", &[]),
+            ModVersion::test("foo-app-1.0.0", &["foo-lib-1.0.0"]),
+        ]);
+        let root = ModVersion::test("foo-app-1.0.0", &["foo-lib-1.0.0"]);
+        assert_eq!(
+            find_incompatibilities(std::slice::from_ref(&root), &installed),
+            [],
+            "the plan doesn't include the dependency yet"
+        );
+
+        let err = resolve_deps_checked(std::slice::from_ref(&root), &index, &installed)
+            .expect_err("conflict");
+        assert!(matches!(
+            err,
+            ThermiteError::Incompatible { installed, package }
+                if installed == "northstar-Northstar-1.22.0" && package == "foo-lib-1.0.0"
+        ));
+
+        let replacement = ModVersion::test("northstar-Northstar-1.23.0", &[]);
+        let plan = resolve_deps_checked(&[root, replacement], &index, &installed)
+            .expect("installed package is replaced");
+        assert_eq!(plan.len(), 3);
+    }
+
+    #[test]
+    fn refuse_incompatible_mod_in_plan() {
+        let dir = TempDir::create("./incompatible_mod_plan").expect("Temp dir");
+        setup_mods(&dir);
+        let mut installed = find_mods(&dir).expect("find mods");
+        installed[0].mod_json.incompatibilities = vec!["Other.Mod".into()];
+        let mut other = installed[0].clone();
+        other.author = "foo".into();
+        other.manifest.name = "other".into();
+        other.mod_json.name = "Other.Mod".into();
+        other.mod_json.incompatibilities = vec![];
+        installed.push(other);
+
+        let index = dep_index(vec![
+            ModVersion::test("foo-other-2.0.0", &[]),
+            ModVersion::test("foo-app-1.0.0", &[]),
+        ]);
+        assert!(resolve_deps_checked(
+            &[ModVersion::test("foo-app-1.0.0", &[])],
+            &index,
+            &installed
+        )
+        .is_ok());
+
+        // the update still contains the mod the installed one can't be loaded alongside
+        let update = ModVersion::test("foo-other-2.0.0", &[]);
+        let err = resolve_deps_checked(&[update], &index, &installed).expect_err("conflict");
+        assert!(matches!(
+            err,
+            ThermiteError::Incompatible { installed, package }
+                if installed == "northstar-Northstar-1.22.0" && package == "foo-other-2.0.0"
+        ));
+    }
+
+    #[test]
+    fn resolve_dependency_closure() {
+        let index = dep_index(vec![
+            ModVersion::test("foo-lib-1.0.0", &[]),
+            ModVersion::test("foo-lib-1.10.0", &[]),
+            ModVersion::test(
+                "foo-mid-1.0.0",
+                &["foo-lib-1.0.0", "northstar-Northstar-1.0.0"],
+            ),
+            ModVersion::test("foo-other-1.0.0", &["foo-lib-1.10.0"]),
+        ]);
+        let root = ModVersion::test("foo-app-1.0.0", &["foo-mid-1.0.0", "foo-other-1.0.0"]);
+
+        let plan = resolve_deps_recursive(&[root], &index).expect("resolve deps");
+        let names: Vec<&str> = plan.iter().map(|v| v.full_name.as_str()).collect();
+
+        assert_eq!(names.len(), 4);
+        assert_eq!(names[3], "foo-app-1.0.0");
+        assert!(
+            names.contains(&"foo-lib-1.10.0"),
+            "highest version should win"
+        );
+        let pos = |n: &str| names.iter().position(|v| *v == n).expect("in plan");
+        assert!(pos("foo-lib-1.10.0") < pos("foo-mid-1.0.0"));
+        assert!(pos("foo-lib-1.10.0") < pos("foo-other-1.0.0"));
+    }
+
+    #[test]
+    fn fail_resolve_dependency_cycle() {
+        let index = dep_index(vec![
+            ModVersion::test("foo-a-1.0.0", &["foo-b-1.0.0"]),
+            ModVersion::test("foo-b-1.0.0", &["foo-a-1.0.0"]),
+        ]);
+        let root = ModVersion::test("foo-app-1.0.0", &["foo-a-1.0.0"]);
+
+        let res = resolve_deps_recursive(&[root], &index);
+        assert!(matches!(res, Err(ThermiteError::DepError(msg)) if msg.contains("cycle")));
+    }
+
+    #[test]
+    fn fail_resolve_missing_version() {
+        let index = dep_index(vec![ModVersion::test("foo-lib-1.0.0", &[])]);
+        let root = ModVersion::test("foo-app-1.0.0", &["foo-lib-2.0.0"]);
+
+        let res = resolve_deps_recursive(&[root], &index);
+        assert!(matches!(res, Err(ThermiteError::DepError(dep)) if dep == "foo-lib-2.0.0"));
+    }
+
+    #[test]
+    fn parse_process_names() {
+        assert_eq!(
+            process_name(b"/usr/bin/wineserver\0--foreground\0"),
+            Some("wineserver")
+        );
+        assert_eq!(
+            process_name(b"Z:\\home\\user\\Titanfall2\\NorthstarLauncher.exe\0-dedicated\0"),
+            Some("NorthstarLauncher.exe")
+        );
+        assert_eq!(process_name(b""), None);
+
+        assert_eq!(
+            argv0_path("Z:\\home\\user\\Titanfall2\\Titanfall2.exe"),
+            Some(PathBuf::from("/home/user/Titanfall2/Titanfall2.exe"))
+        );
+        assert_eq!(
+            argv0_path("/opt/game/Titanfall2.exe"),
+            Some(PathBuf::from("/opt/game/Titanfall2.exe"))
+        );
+        assert_eq!(argv0_path("C:\\Games\\Titanfall2.exe"), None);
+        assert_eq!(argv0_path("Titanfall2.exe"), None);
+
+        assert_eq!(
+            tasklist_name(r#""Titanfall2.exe","1234","Console","1","1,024 K""#),
+            Some("Titanfall2.exe")
+        );
+        assert_eq!(tasklist_name("INFO: No tasks are running"), None);
+    }
+
+    #[test]
+    fn check_writable_dir() {
+        let dir = TempDir::create("./write_access").expect("Unable to create temp dir");
+        assert!(check_write_access(&dir).is_ok());
+        assert!(check_write_access(dir.join("missing/mods")).is_ok());
+        assert_eq!(fs::read_dir(&dir).expect("read dir").count(), 0);
+
+        #[cfg(windows)]
+        assert!(extended_path(&dir).to_string_lossy().starts_with(r"\\?\"));
+        #[cfg(not(windows))]
+        assert_eq!(extended_path(&dir), dir.to_path_buf());
+    }
+
+    #[test]
+    fn find_snap_steam() {
+        let home = TempDir::create("./steam_snap_home").expect("Unable to create temp dir");
+        assert_eq!(find_steam_in(&home), None);
+
+        let steam = home.join("snap/steam/common/.local/share/Steam");
+        let library = home.join("Games/SteamLibrary");
+        fs::create_dir_all(steam.join("steamapps")).expect("create dir");
+        fs::write(
+            steam.join("steamapps/libraryfolders.vdf"),
+            format!(
+                "\"libraryfolders\"\n{{\n\t\"0\"\n\t{{\n\t\t\"path\"\t\t\"{}\"\n\t}}\n}}\n",
+                library.display()
+            ),
+        )
+        .expect("write libraryfolders.vdf");
+
+        assert_eq!(find_steam_in(&home), Some(steam.clone()));
+        assert_eq!(library_paths(&steam), [steam, library]);
+    }
 }
@@ -0,0 +1,217 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use crate::model::ModJSON;
+
+/// Every top-level key Northstar reads from a `mod.json`
+pub const MOD_JSON_KEYS: [&str; 11] = [
+    "Name",
+    "Description",
+    "Version",
+    "LoadPriority",
+    "RequiredOnClient",
+    "ConVars",
+    "ConCommands",
+    "Scripts",
+    "Localisation",
+    "Dependencies",
+    "PluginDependencies",
+];
+
+/// Whether an [`Issue`] stops the mod from loading
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem with a `mod.json`, or a `manifest.json` as checked by [`Manifest::validate`](crate::model::Manifest::validate)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// The name is empty
+    MissingName,
+    /// The version isn't a semver version
+    InvalidVersion(String),
+    /// A key Northstar doesn't read, which looks like a typo of one it does
+    UnknownKey { key: String, suggestion: String },
+    /// A script listed in `Scripts` doesn't exist in `mod/scripts/vscripts`
+    MissingScript(PathBuf),
+    /// A field Thunderstore requires in `manifest.json` is missing
+    MissingField(&'static str),
+    /// The package name has characters other than letters, numbers and underscores
+    InvalidName(String),
+    /// A dependency isn't formatted like `author-name-X.Y.Z`
+    InvalidDependency(String),
+    /// The description is longer than Thunderstore allows, in characters
+    DescriptionTooLong(usize),
+}
+
+impl Issue {
+    #[must_use]
+    pub const fn severity(&self) -> Severity {
+        match self {
+            Self::MissingName
+            | Self::MissingScript(_)
+            | Self::MissingField(_)
+            | Self::InvalidName(_)
+            | Self::InvalidDependency(_)
+            | Self::DescriptionTooLong(_) => Severity::Error,
+            Self::InvalidVersion(_) | Self::UnknownKey { .. } => Severity::Warning,
+        }
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingName => write!(f, "Name is empty"),
+            Self::InvalidVersion(version) => write!(f, "Version {version:?} isn't semver"),
+            Self::UnknownKey { key, suggestion } => {
+                write!(f, "Unknown key {key:?}, did you mean {suggestion:?}?")
+            }
+            Self::MissingScript(path) => write!(f, "Script {} doesn't exist", path.display()),
+            Self::MissingField(field) => write!(f, "{field} is missing"),
+            Self::InvalidName(name) => {
+                write!(
+                    f,
+                    "Name {name:?} can only contain letters, numbers and underscores"
+                )
+            }
+            Self::InvalidDependency(dep) => {
+                write!(
+                    f,
+                    "Dependency {dep:?} isn't formatted like author-name-X.Y.Z"
+                )
+            }
+            Self::DescriptionTooLong(len) => write!(f, "Description is {len} characters long"),
+        }
+    }
+}
+
+/// Check a `mod.json` for mistakes that stop it from loading or that Northstar silently ignores
+///
+/// # Params
+/// * `mod_json` - the parsed `mod.json`
+/// * `base` - the mod's folder, i.e. the directory containing `mod.json`. Scripts are only checked if this is provided
+///
+/// # Returns
+/// * every issue found, or an empty list if the `mod.json` looks fine
+#[must_use]
+pub fn validate_mod_json(mod_json: &ModJSON, base: Option<&Path>) -> Vec<Issue> {
+    let mut issues = vec![];
+    if mod_json.name.trim().is_empty() {
+        issues.push(Issue::MissingName);
+    }
+
+    if semver::Version::parse(&mod_json.version).is_err() {
+        issues.push(Issue::InvalidVersion(mod_json.version.clone()));
+    }
+
+    let mut unknown = mod_json._extra.keys().collect::<Vec<_>>();
+    unknown.sort();
+    for key in unknown {
+        if MOD_JSON_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        if let Some(suggestion) = MOD_JSON_KEYS.iter().find(|known| is_typo(key, known)) {
+            issues.push(Issue::UnknownKey {
+                key: key.clone(),
+                suggestion: (*suggestion).into(),
+            });
+        }
+    }
+
+    if let Some(base) = base {
+        let vscripts = base.join("mod").join("scripts").join("vscripts");
+        for script in &mod_json.scripts {
+            let Some(path) = script.get("Path").and_then(Value::as_str) else {
+                continue;
+            };
+            if !vscripts.join(path).is_file() {
+                issues.push(Issue::MissingScript(PathBuf::from(path)));
+            }
+        }
+    }
+
+    issues
+}
+
+/// `true` if `key` differs from `known` only by case or at most two edits
+fn is_typo(key: &str, known: &str) -> bool {
+    let (key, known) = (key.to_lowercase(), known.to_lowercase());
+    key == known || edit_distance(&key, &known) <= 2
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            current.push((prev[j] + cost).min(prev[j + 1] + 1).min(current[j] + 1));
+        }
+        prev = current;
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::{core::utils::TempDir, model::ModJSON};
+
+    use super::{validate_mod_json, Issue, Severity};
+
+    const MOD_JSON: &str = r#"{
+        "Name": "Test.Mod",
+        "Description": "",
+        "Version": "1.0",
+        "LoadPrioirty": 1,
+        "Author": "Someone",
+        "Scripts": [
+            { "Path": "test/exists.nut", "RunOn": "CLIENT" },
+            { "Path": "test/missing.nut", "RunOn": "SERVER" }
+        ]
+    }"#;
+
+    #[test]
+    fn find_mod_json_issues() {
+        let dir = TempDir::create("./validate_mod_json").expect("Unable to create temp dir");
+        let scripts = dir.join("mod/scripts/vscripts/test");
+        fs::create_dir_all(&scripts).expect("create dir");
+        fs::write(scripts.join("exists.nut"), "").expect("write script");
+
+        let mod_json: ModJSON = json5::from_str(MOD_JSON).expect("parse mod.json");
+        let issues = validate_mod_json(&mod_json, Some(&dir));
+
+        assert_eq!(
+            issues,
+            [
+                Issue::InvalidVersion("1.0".into()),
+                Issue::UnknownKey {
+                    key: "LoadPrioirty".into(),
+                    suggestion: "LoadPriority".into()
+                },
+                Issue::MissingScript("test/missing.nut".into()),
+            ]
+        );
+        assert_eq!(issues[2].severity(), Severity::Error);
+        assert_eq!(validate_mod_json(&mod_json, None).len(), 2);
+    }
+
+    #[test]
+    fn fail_empty_name() {
+        let mod_json: ModJSON =
+            json5::from_str(r#"{ "Name": " ", "Description": "", "Version": "1.0.0" }"#)
+                .expect("parse mod.json");
+
+        assert_eq!(validate_mod_json(&mod_json, None), [Issue::MissingName]);
+    }
+}
@@ -0,0 +1,434 @@
+use std::{fs, ops::Range, path::Path};
+
+use crate::{
+    core::utils::write_atomic,
+    error::{Result, ThermiteError},
+};
+
+/// A Valve KeyValues (`.vdf`) text file that can be edited without reformatting it
+///
+/// Used for Steam's `localconfig.vdf` and `config.vdf`. Keys are matched case-insensitively, like
+/// Steam does, and only the values that are set change.
+///
+/// # Example
+/// ```no_run
+/// use thermite::core::vdf::VdfDocument;
+///
+/// # fn main() -> thermite::error::Result<()> {
+/// let mut doc = VdfDocument::load("localconfig.vdf")?;
+/// let path = ["UserLocalConfigStore", "Software", "Valve", "Steam", "apps", "1237970", "LaunchOptions"];
+/// println!("{:?}", doc.get(&path));
+/// doc.set(&path, "-northstar")?;
+/// doc.save("localconfig.vdf")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VdfDocument {
+    text: String,
+}
+
+/// A key and its value, as byte offsets into the text
+#[derive(Debug)]
+struct Entry {
+    key: String,
+    value: Value,
+}
+
+#[derive(Debug)]
+enum Value {
+    /// The quoted or bare token, including its quotes
+    Str(Range<usize>),
+    /// The position of the closing brace
+    Block { entries: Vec<Entry>, close: usize },
+}
+
+#[derive(Debug)]
+enum Token {
+    Str(Range<usize>),
+    Open,
+    Close(usize),
+}
+
+impl VdfDocument {
+    /// Parse the text of a `.vdf` file
+    ///
+    /// # Errors
+    /// - The text has unbalanced braces or an unterminated string
+    pub fn parse(text: impl Into<String>) -> Result<Self> {
+        let doc = Self { text: text.into() };
+        doc.entries()?;
+
+        Ok(doc)
+    }
+
+    /// Read and parse a `.vdf` file
+    ///
+    /// # Errors
+    /// - IO Errors
+    /// - The file can't be parsed
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::parse(fs::read_to_string(path)?)
+    }
+
+    /// Write the document to `path`, replacing the file atomically
+    ///
+    /// # Errors
+    /// - IO Errors
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        write_atomic(path, &self.text)
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// The string value at `path`, or `None` if it's missing or is a block
+    #[must_use]
+    pub fn get(&self, path: &[impl AsRef<str>]) -> Option<String> {
+        let entries = self.entries().ok()?;
+        match &find(&entries, path)?.value {
+            Value::Str(range) => Some(unescape(&self.text[range.clone()])),
+            Value::Block { .. } => None,
+        }
+    }
+
+    /// `true` if there's a value or block at `path`
+    #[must_use]
+    pub fn contains(&self, path: &[impl AsRef<str>]) -> bool {
+        self.entries()
+            .is_ok_and(|entries| find(&entries, path).is_some())
+    }
+
+    /// The keys in the block at `path`, in the order they appear
+    #[must_use]
+    pub fn keys(&self, path: &[impl AsRef<str>]) -> Vec<String> {
+        let Ok(entries) = self.entries() else {
+            return vec![];
+        };
+        match find(&entries, path).map(|e| &e.value) {
+            Some(Value::Block { entries, .. }) => entries.iter().map(|e| e.key.clone()).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Set the string value at `path`, replacing it in place or adding it and any missing blocks
+    /// at the end of the deepest block that exists
+    ///
+    /// # Errors
+    /// - `path` is empty, or leads through or to a string value where a block is expected
+    pub fn set(&mut self, path: &[impl AsRef<str>], value: &str) -> Result<()> {
+        let Some((last, parents)) = path.split_last() else {
+            return Err(ThermiteError::UnknownError("Empty vdf path".into()));
+        };
+
+        let entries = self.entries()?;
+        let mut current = &entries;
+        let mut close = None;
+        let mut depth = 0;
+        for key in parents {
+            let Some(entry) = current
+                .iter()
+                .find(|e| e.key.eq_ignore_ascii_case(key.as_ref()))
+            else {
+                break;
+            };
+            let Value::Block {
+                entries,
+                close: end,
+            } = &entry.value
+            else {
+                return Err(ThermiteError::UnknownError(format!(
+                    "{} is a value, not a block",
+                    entry.key
+                )));
+            };
+            current = entries;
+            close = Some(*end);
+            depth += 1;
+        }
+
+        let mut text = self.text.clone();
+        if depth == parents.len() {
+            if let Some(entry) = current
+                .iter()
+                .find(|e| e.key.eq_ignore_ascii_case(last.as_ref()))
+            {
+                let Value::Str(range) = &entry.value else {
+                    return Err(ThermiteError::UnknownError(format!(
+                        "{} is a block, not a value",
+                        entry.key
+                    )));
+                };
+                text.replace_range(range.clone(), &quote(value));
+                return self.replace(text);
+            }
+        }
+
+        let (pos, indent) = match close {
+            Some(close) => {
+                let line_start = self.text[..close].rfind('\n').map_or(0, |n| n + 1);
+                let before = &self.text[line_start..close];
+                if before.trim().is_empty() {
+                    (line_start, format!("{before}\t"))
+                } else {
+                    text.insert(close, '\n');
+                    (close + 1, "\t".repeat(depth))
+                }
+            }
+            None => {
+                if !text.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                (text.len(), String::new())
+            }
+        };
+        text.insert_str(pos, &nested(&path[depth..], value, &indent));
+
+        self.replace(text)
+    }
+
+    /// Swap in the edited text, refusing edits that would leave the document unparseable
+    fn replace(&mut self, text: String) -> Result<()> {
+        let previous = std::mem::replace(&mut self.text, text);
+        if let Err(e) = self.entries() {
+            self.text = previous;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    fn entries(&self) -> Result<Vec<Entry>> {
+        tokenize(&self.text)
+            .and_then(|tokens| parse_entries(&self.text, &mut tokens.into_iter()))
+            .and_then(|(entries, close)| close.is_none().then_some(entries))
+            .ok_or_else(|| ThermiteError::UnknownError("Unable to parse vdf".into()))
+    }
+}
+
+fn find<'a>(entries: &'a [Entry], path: &[impl AsRef<str>]) -> Option<&'a Entry> {
+    let (first, rest) = path.split_first()?;
+    let entry = entries
+        .iter()
+        .find(|e| e.key.eq_ignore_ascii_case(first.as_ref()))?;
+    match (&entry.value, rest) {
+        (_, []) => Some(entry),
+        (Value::Block { entries, .. }, rest) => find(entries, rest),
+        (Value::Str(_), _) => None,
+    }
+}
+
+/// The text for `path` set to `value`, with a block for every key but the last
+fn nested(path: &[impl AsRef<str>], value: &str, indent: &str) -> String {
+    match path {
+        [] => String::new(),
+        [key] => format!("{indent}{}\t\t{}\n", quote(key.as_ref()), quote(value)),
+        [key, rest @ ..] => format!(
+            "{indent}{}\n{indent}{{\n{}{indent}}}\n",
+            quote(key.as_ref()),
+            nested(rest, value, &format!("{indent}\t"))
+        ),
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn unescape(token: &str) -> String {
+    let Some(inner) = token
+        .strip_prefix('"')
+        .map(|t| t.strip_suffix('"').unwrap_or(t))
+    else {
+        return token.to_owned();
+    };
+
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+
+    out
+}
+
+/// Parse entries up to the next closing brace, returning its position, or to the end of the text
+fn parse_entries<I: Iterator<Item = Token>>(
+    text: &str,
+    tokens: &mut I,
+) -> Option<(Vec<Entry>, Option<usize>)> {
+    let mut entries = vec![];
+    loop {
+        let key = match tokens.next() {
+            Some(Token::Str(range)) => unescape(&text[range]),
+            Some(Token::Close(pos)) => return Some((entries, Some(pos))),
+            None => return Some((entries, None)),
+            Some(Token::Open) => return None,
+        };
+        let value = match tokens.next()? {
+            Token::Str(range) => Value::Str(range),
+            Token::Open => {
+                let (entries, close) = parse_entries(text, tokens)?;
+                Value::Block {
+                    entries,
+                    close: close?,
+                }
+            }
+            Token::Close(_) => return None,
+        };
+        entries.push(Entry { key, value });
+    }
+}
+
+fn tokenize(text: &str) -> Option<Vec<Token>> {
+    let bytes = text.as_bytes();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while let Some(&b) = bytes.get(i) {
+        match b {
+            b if b.is_ascii_whitespace() => i += 1,
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while bytes.get(i).is_some_and(|b| *b != b'\n') {
+                    i += 1;
+                }
+            }
+            b'{' => {
+                tokens.push(Token::Open);
+                i += 1;
+            }
+            b'}' => {
+                tokens.push(Token::Close(i));
+                i += 1;
+            }
+            // platform conditionals like [$WIN32] aren't something we need to understand
+            b'[' => {
+                while bytes.get(i).is_some_and(|b| *b != b']') {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'"' => {
+                let start = i;
+                i += 1;
+                loop {
+                    match *bytes.get(i)? {
+                        b'\\' => i += 2,
+                        b'"' => break,
+                        _ => i += 1,
+                    }
+                }
+                i += 1;
+                tokens.push(Token::Str(start..i));
+            }
+            _ => {
+                let start = i;
+                while bytes
+                    .get(i)
+                    .is_some_and(|b| !b.is_ascii_whitespace() && !matches!(b, b'"' | b'{' | b'}'))
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Str(start..i));
+            }
+        }
+    }
+
+    Some(tokens)
+}
+
+#[cfg(test)]
+mod test {
+    use super::VdfDocument;
+
+    const LOCALCONFIG: &str = "\"UserLocalConfigStore\"
+{
+\t\"Software\"
+\t{
+\t\t\"Valve\"
+\t\t{
+\t\t\t\"Steam\"
+\t\t\t{
+\t\t\t\t\"Apps\"
+\t\t\t\t{
+\t\t\t\t\t\"1237970\"
+\t\t\t\t\t{
+\t\t\t\t\t\t\"LastPlayed\"\t\t\"1700000000\"
+\t\t\t\t\t\t\"LaunchOptions\"\t\t\"PROTON_LOG=1 %command% +name \\\"foo bar\\\"\"
+\t\t\t\t\t}
+\t\t\t\t}
+\t\t\t}
+\t\t}
+\t}
+\t// bare tokens and conditionals show up in older files
+\tfriends { PersonaName foo [$WIN32] }
+}
+";
+
+    const PATH: [&str; 7] = [
+        "UserLocalConfigStore",
+        "Software",
+        "Valve",
+        "Steam",
+        "apps",
+        "1237970",
+        "LaunchOptions",
+    ];
+
+    #[test]
+    fn edit_vdf_in_place() {
+        let mut doc = VdfDocument::parse(LOCALCONFIG).expect("parse vdf");
+        assert_eq!(
+            doc.get(&PATH).as_deref(),
+            Some("PROTON_LOG=1 %command% +name \"foo bar\"")
+        );
+        assert_eq!(
+            doc.get(&["UserLocalConfigStore", "friends", "PersonaName"])
+                .as_deref(),
+            Some("foo")
+        );
+        assert_eq!(doc.get(&PATH[..6]), None);
+        assert!(doc.contains(&PATH[..6]));
+        assert_eq!(doc.keys(&PATH[..6]), ["LastPlayed", "LaunchOptions"]);
+
+        doc.set(&PATH, "-northstar \"C:\\a\"")
+            .expect("set launch options");
+        assert_eq!(
+            doc.as_str(),
+            LOCALCONFIG.replace(
+                "PROTON_LOG=1 %command% +name \\\"foo bar\\\"",
+                "-northstar \\\"C:\\\\a\\\"",
+            )
+        );
+        assert_eq!(doc.get(&PATH).as_deref(), Some("-northstar \"C:\\a\""));
+    }
+
+    #[test]
+    fn add_missing_blocks() {
+        let mut doc = VdfDocument::parse(LOCALCONFIG).expect("parse vdf");
+        let mut path = PATH;
+        path[5] = "1234";
+        doc.set(&path, "-novid").expect("add app");
+        assert!(doc.as_str().contains(
+            "\t\t\t\t\t}\n\t\t\t\t\t\"1234\"\n\t\t\t\t\t{\n\t\t\t\t\t\t\"LaunchOptions\"\t\t\"-novid\"\n\t\t\t\t\t}\n\t\t\t\t}"
+        ));
+        assert_eq!(doc.get(&path).as_deref(), Some("-novid"));
+
+        let mut empty = VdfDocument::parse("").expect("parse empty vdf");
+        empty.set(&["a", "b"], "c").expect("set value");
+        assert_eq!(empty.as_str(), "\"a\"\n{\n\t\"b\"\t\t\"c\"\n}\n");
+
+        assert!(doc.set(&PATH[..6], "block").is_err());
+        assert!(VdfDocument::parse("\"a\" { \"b\" \"c\"").is_err());
+    }
+}
@@ -39,12 +39,65 @@ pub enum ThermiteError {
     NameError(String),
     #[error("Expected string to be UTF8")]
     UTF8Error,
+    #[error("Checksum mismatch, expected {expected} but got {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+    #[error("Operation was cancelled")]
+    Cancelled,
+    #[error("Timed out waiting for the server to respond")]
+    Timeout,
+    #[error(
+        "Expected a zip archive from {} but got {}",
+        .url.as_deref().unwrap_or("file"),
+        .content_type.as_deref().unwrap_or("something else")
+    )]
+    InvalidArchive {
+        url: Option<String>,
+        content_type: Option<String>,
+    },
+    #[error("Refusing to modify core mod {0}")]
+    CoreModError(String),
+    #[error("Archive entry {entry} is unsafe to extract: {reason}")]
+    UnsafeArchive { entry: String, reason: UnsafeReason },
+    #[error("{0:?} is in use, is the game still running?")]
+    GameRunning(PathBuf),
+    #[error("{installed} is incompatible with {package}")]
+    Incompatible { installed: String, package: String },
+    #[error("Steam is running and would overwrite the change when it exits")]
+    SteamRunning,
+    #[error("Writing to {0:?} requires administrator permissions")]
+    NeedsElevation(PathBuf),
+    #[error("{0:?} is managed by the Xbox app and can't be modified")]
+    UnsupportedInstall(PathBuf),
+}
+
+/// Why an archive entry was rejected before extraction
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnsafeReason {
+    #[error("absolute path")]
+    AbsolutePath,
+    #[error("path leaves the install directory")]
+    PathTraversal,
+    #[error("symlink points outside the install directory")]
+    SymlinkEscape,
+    #[error("file name is reserved on Windows")]
+    ReservedName,
+    #[error("suspiciously high compression ratio")]
+    CompressionRatio,
 }
 
 // ureq::Error is ~240 bytes so we store it in a box
 impl From<ureq::Error> for ThermiteError {
     fn from(value: ureq::Error) -> Self {
-        Self::NetworkError(Box::new(value))
+        let timed_out = value
+            .source()
+            .and_then(|e| e.downcast_ref::<io::Error>())
+            .is_some_and(|e| e.kind() == io::ErrorKind::TimedOut);
+
+        if timed_out {
+            Self::Timeout
+        } else {
+            Self::NetworkError(Box::new(value))
+        }
     }
 }
 
@@ -79,4 +132,11 @@ mod test {
         }
     }
 
+    #[test]
+    fn from_ureq_timeout() {
+        let err = ureq::Error::from(std::io::Error::from(std::io::ErrorKind::TimedOut));
+
+        assert!(matches!(ThermiteError::from(err), ThermiteError::Timeout));
+    }
+
 }
@@ -33,14 +33,20 @@ pub const TITANFALL2_ORIGIN_IDS: [&str; 2] = ["Origin.OFR.50.0001452", "Origin.O
 
 // Important functions and structs
 pub mod prelude {
-    pub use crate::api::get_package_index;
+    pub use crate::api::{get_package, get_package_index};
+    pub use crate::core::locate::GameLocator;
     pub use crate::core::manage::{
-        download, download_with_progress, install_mod, install_northstar, install_with_sanity,
+        download, download_file, download_with_progress, install_mod, install_northstar,
+        install_with_sanity,
     };
 
-    pub use crate::core::utils::{find_mods, get_enabled_mods, resolve_deps};
+    pub use crate::core::utils::{
+        find_mods, get_enabled_mods, is_game_running, resolve_deps, resolve_deps_recursive,
+    };
     #[cfg(all(target_os = "linux", feature = "proton"))]
-    pub use crate::core::{download_ns_proton, install_ns_proton, latest_release};
+    pub use crate::core::{download_ns_proton, install_ns_proton, latest_release, set_compat_tool};
+    #[cfg(feature = "ea")]
+    pub use crate::core::{ea_dir, titanfall2_ea_dir, titanfall2_wine_dir};
     #[cfg(feature = "steam")]
     pub use crate::core::{steam_dir, steam_libraries, titanfall};
     pub use crate::error::ThermiteError;
@@ -1,18 +1,35 @@
 use serde::{Deserialize, Serialize};
 use serde_json::{self, Value};
 use std::{
-    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    cmp::{Ordering, Reverse},
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet, HashMap},
+    fmt::{self, Display, Formatter},
     hash::{Hash, Hasher},
+    ops::{Deref, DerefMut},
+    str::FromStr,
 };
 use std::{
     fs,
     path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
 };
+#[cfg(feature = "timestamps")]
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 use tracing::{debug, error};
 
-use crate::{error::ThermiteError, CORE_MODS};
+use crate::{
+    core::{
+        utils::{
+            compare_components, dep_key, is_core_mod, package_key, parse_modstring,
+            split_modstring, write_atomic,
+        },
+        validate::Issue,
+    },
+    error::ThermiteError,
+};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "PascalCase")]
 pub struct ModJSON {
     pub name: String,
@@ -26,189 +43,2294 @@ pub struct ModJSON {
     pub scripts: Vec<Value>,
     #[serde(default)]
     pub localisation: Vec<String>,
+    /// Names of mods this mod can't be loaded alongside
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub incompatibilities: Vec<String>,
     #[serde(flatten)]
     pub _extra: HashMap<String, Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+impl ModJSON {
+    /// Where the mod has to be installed, based on `RequiredOnClient` and what its scripts run on
+    ///
+    /// Mods without scripts are assumed to be client-side asset or localisation mods
+    #[must_use]
+    pub fn side(&self) -> Side {
+        let run_on = self
+            .scripts
+            .iter()
+            .filter_map(|s| s.get("RunOn").and_then(Value::as_str))
+            .map(str::to_uppercase)
+            .collect::<Vec<_>>();
+        let server = run_on.iter().any(|r| r.contains("SERVER"));
+        let client = !self.localisation.is_empty()
+            || run_on
+                .iter()
+                .any(|r| r.contains("CLIENT") || r.contains("UI"));
+
+        match (server, client || self.required_on_client == Some(true)) {
+            (true, true) => Side::Both,
+            (true, false) => Side::Server,
+            (false, _) => Side::Client,
+        }
+    }
+}
+
+/// Where a mod has to be installed for it to work
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum Side {
+    Client,
+    Server,
+    Both,
+}
+
+impl Side {
+    /// `true` if the mod is needed on clients
+    #[must_use]
+    pub const fn client(self) -> bool {
+        matches!(self, Self::Client | Self::Both)
+    }
+
+    /// `true` if the mod is needed on servers, including dedicated servers
+    #[must_use]
+    pub const fn server(self) -> bool {
+        matches!(self, Self::Server | Self::Both)
+    }
+
+    /// `true` if a mod on this side is needed on `other`, e.g. `Both` includes `Server`
+    #[must_use]
+    pub const fn includes(self, other: Self) -> bool {
+        match other {
+            Self::Client => self.client(),
+            Self::Server => self.server(),
+            Self::Both => matches!(self, Self::Both),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Mod {
     pub name: String,
     ///The latest version of the mod
-    pub latest: String,
+    pub latest: Version,
+    #[deprecated(
+        since = "0.9.0",
+        note = "never set from the index, use `ModState::is_installed` instead"
+    )]
     #[serde(default)]
     pub installed: bool,
+    #[deprecated(
+        since = "0.9.0",
+        note = "never set from the index, use `ModState::is_upgradable` instead"
+    )]
     #[serde(default)]
     pub upgradable: bool,
+    #[deprecated(since = "0.9.0", note = "never set from the index")]
     #[serde(default)]
     pub global: bool,
     ///A map of each version of a mod
-    pub versions: BTreeMap<String, ModVersion>,
+    pub versions: BTreeMap<Version, ModVersion>,
     pub author: String,
+    ///The package's rating on Thunderstore
+    #[serde(default)]
+    pub rating_score: i64,
+    #[serde(default)]
+    pub deprecated: bool,
+    ///Thunderstore categories the package is listed under
+    #[serde(default)]
+    pub categories: Vec<String>,
+    ///When the package was first published, as reported by Thunderstore
+    #[serde(default)]
+    pub date_created: String,
+    ///When the package was last updated, as reported by Thunderstore
+    #[serde(default)]
+    pub date_updated: String,
 }
 
 impl Mod {
+    /// The version named by `latest`, or the newest version if `latest` isn't in `versions`
     #[must_use]
     pub fn get_latest(&self) -> Option<&ModVersion> {
-        self.versions.get(&self.latest)
+        self.versions
+            .get(&self.latest)
+            .or_else(|| self.versions.values().next_back())
     }
 
     #[must_use]
     pub fn get_version(&self, version: impl AsRef<str>) -> Option<&ModVersion> {
-        self.versions.get(version.as_ref())
+        self.versions.get(&Version::new(version.as_ref()))
+    }
+
+    /// Every version of the mod, oldest first
+    ///
+    /// Sorted by each version's own `version` rather than its key in `versions`, which can differ if
+    /// the index was built by hand
+    #[must_use]
+    pub fn sorted_versions(&self) -> Vec<&ModVersion> {
+        let mut versions = self.versions.values().collect::<Vec<_>>();
+        versions.sort();
+        versions
+    }
+
+    /// The highest version of the mod, regardless of what `latest` says
+    ///
+    /// Unlike [`get_latest`](Self::get_latest), this doesn't rely on `latest` being spelled exactly
+    /// like one of the keys in `versions`, e.g. `1.0` vs `1.0.0`
+    #[must_use]
+    pub fn latest_version(&self) -> Option<&ModVersion> {
+        self.versions.values().max()
+    }
+
+    /// Total downloads across every version of the mod
+    #[must_use]
+    pub fn total_downloads(&self) -> u64 {
+        self.versions.values().map(|v| v.downloads).sum()
+    }
+
+    /// When the package was first published, if Thunderstore reported a valid timestamp
+    #[cfg(feature = "timestamps")]
+    #[must_use]
+    pub fn created(&self) -> Option<OffsetDateTime> {
+        parse_timestamp(&self.date_created)
+    }
+
+    /// When the package was last updated, if Thunderstore reported a valid timestamp
+    #[cfg(feature = "timestamps")]
+    #[must_use]
+    pub fn updated(&self) -> Option<OffsetDateTime> {
+        parse_timestamp(&self.date_updated)
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
-pub struct ModVersion {
+/// A package as listed in the index, without any local state
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RemoteMod {
     pub name: String,
-    pub full_name: String,
-    pub version: String,
-    pub url: String,
-    pub desc: String,
-    pub deps: Vec<String>,
-    pub installed: bool,
-    pub global: bool,
-    pub file_size: u64,
+    pub author: String,
+    ///The latest version of the mod
+    pub latest: Version,
+    ///A map of each version of a mod
+    pub versions: BTreeMap<Version, ModVersion>,
+    ///The package's rating on Thunderstore
+    #[serde(default)]
+    pub rating_score: i64,
+    #[serde(default)]
+    pub deprecated: bool,
+    ///Thunderstore categories the package is listed under
+    #[serde(default)]
+    pub categories: Vec<String>,
+    ///When the package was first published, as reported by Thunderstore
+    #[serde(default)]
+    pub date_created: String,
+    ///When the package was last updated, as reported by Thunderstore
+    #[serde(default)]
+    pub date_updated: String,
 }
 
-impl ModVersion {
+impl RemoteMod {
+    /// The version named by `latest`, or the newest version if `latest` isn't in `versions`
     #[must_use]
-    pub fn file_size_string(&self) -> String {
-        if self.file_size / 1_000_000 >= 1 {
-            let size = self.file_size / 1_048_576;
+    pub fn get_latest(&self) -> Option<&ModVersion> {
+        self.versions
+            .get(&self.latest)
+            .or_else(|| self.versions.values().next_back())
+    }
 
-            format!("{size:.2} MB")
-        } else {
-            let size = self.file_size / 1024;
-            format!("{size:.2} KB")
+    #[must_use]
+    pub fn get_version(&self, version: impl AsRef<str>) -> Option<&ModVersion> {
+        self.versions.get(&Version::new(version.as_ref()))
+    }
+}
+
+impl From<Mod> for RemoteMod {
+    fn from(value: Mod) -> Self {
+        let Mod {
+            name,
+            latest,
+            versions,
+            author,
+            rating_score,
+            deprecated,
+            categories,
+            date_created,
+            date_updated,
+            ..
+        } = value;
+
+        Self {
+            name,
+            author,
+            latest,
+            versions,
+            rating_score,
+            deprecated,
+            categories,
+            date_created,
+            date_updated,
         }
     }
 }
 
-impl From<&Self> for ModVersion {
-    fn from(value: &Self) -> Self {
-        value.clone()
+impl From<RemoteMod> for Mod {
+    fn from(value: RemoteMod) -> Self {
+        Self {
+            name: value.name,
+            latest: value.latest,
+            versions: value.versions,
+            author: value.author,
+            rating_score: value.rating_score,
+            deprecated: value.deprecated,
+            categories: value.categories,
+            date_created: value.date_created,
+            date_updated: value.date_updated,
+            ..Default::default()
+        }
     }
 }
 
-impl AsRef<Self> for ModVersion {
-    fn as_ref(&self) -> &Self {
-        self
+/// A package from the index together with the mods installed from it, see `mod_states`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModState {
+    pub remote: RemoteMod,
+    /// Mods installed from this package, empty if it isn't installed
+    pub installed: Vec<InstalledMod>,
+}
+
+impl ModState {
+    #[must_use]
+    pub fn is_installed(&self) -> bool {
+        !self.installed.is_empty()
+    }
+
+    /// The highest version of the package that's installed
+    #[must_use]
+    pub fn installed_version(&self) -> Option<Version> {
+        self.installed
+            .iter()
+            .map(InstalledMod::manifest_version)
+            .max()
+    }
+
+    /// Whether the index has a newer version than the one installed
+    #[must_use]
+    pub fn is_upgradable(&self) -> bool {
+        self.installed_version()
+            .zip(self.remote.get_latest())
+            .is_some_and(|(installed, latest)| latest.version > installed)
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-pub struct Manifest {
-    pub name: String,
-    pub version_number: String,
-    pub website_url: String,
-    pub description: String,
-    pub dependencies: Vec<String>,
+#[cfg(feature = "timestamps")]
+fn parse_timestamp(raw: &str) -> Option<OffsetDateTime> {
+    OffsetDateTime::parse(raw, &Rfc3339).ok()
 }
 
-// enabledmods.json
+/// A list of packages from Thunderstore
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(transparent)]
+pub struct PackageIndex(Vec<Mod>);
 
-/// Represents an enabledmods.json file. Core mods will default to `true` if not present when deserializing.
-///
-/// Automatically writes any changes made when dropped (call `dont_save` to disable)
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct EnabledMods {
-    #[serde(rename = "Northstar.Client", default = "default_mod_state")]
-    pub client: bool,
-    #[serde(rename = "Northstar.Custom", default = "default_mod_state")]
-    pub custom: bool,
-    #[serde(rename = "Northstar.CustomServers", default = "default_mod_state")]
-    pub servers: bool,
-    #[serde(flatten)]
-    pub mods: BTreeMap<String, bool>,
-    ///Hash of the file as it was loaded
-    #[serde(skip)]
-    hash: u64,
-    ///Path to the file to read & write
-    #[serde(skip)]
-    path: Option<PathBuf>,
-    #[serde(skip)]
-    do_save: bool,
+impl PackageIndex {
+    #[must_use]
+    pub const fn new(mods: Vec<Mod>) -> Self {
+        Self(mods)
+    }
+
+    #[must_use]
+    pub fn into_inner(self) -> Vec<Mod> {
+        self.0
+    }
+
+    /// Sorts the index by total downloads, most downloaded first.
+    /// Packages with the same number of downloads are ordered by rating.
+    pub fn sort_by_popularity(&mut self) {
+        self.0.sort_by(|a, b| {
+            b.total_downloads()
+                .cmp(&a.total_downloads())
+                .then_with(|| b.rating_score.cmp(&a.rating_score))
+        });
+    }
+
+    /// Sorts the index by when each package was last updated, most recent first.
+    /// Packages without a valid timestamp are placed last.
+    #[cfg(feature = "timestamps")]
+    pub fn sort_by_updated(&mut self) {
+        self.0.sort_by_cached_key(|m| Reverse(m.updated()));
+    }
+
+    /// Add every package from `other` to this index.
+    ///
+    /// Packages that appear in both (by `author-name`) are combined into one entry containing
+    /// the versions from both, using the metadata of whichever has the newest latest version.
+    pub fn merge(&mut self, other: Self) {
+        for incoming in other {
+            let Some(existing) = self.get_mut(&incoming.author, &incoming.name) else {
+                self.0.push(incoming);
+                continue;
+            };
+
+            let (mut newer, older) = if incoming.latest > existing.latest {
+                (incoming, std::mem::take(existing))
+            } else {
+                (std::mem::take(existing), incoming)
+            };
+
+            for (version, v) in older.versions {
+                newer.versions.entry(version).or_insert(v);
+            }
+            *existing = newer;
+        }
+    }
+
+    /// Find a package by author and name, compared like [`ModName`]
+    #[must_use]
+    pub fn get(&self, author: impl AsRef<str>, name: impl AsRef<str>) -> Option<&Mod> {
+        let (author, name) = (ModName::new(author.as_ref()), ModName::new(name.as_ref()));
+        self.0
+            .iter()
+            .find(|m| author == m.author.as_str() && name == m.name.as_str())
+    }
+
+    pub fn get_mut(&mut self, author: impl AsRef<str>, name: impl AsRef<str>) -> Option<&mut Mod> {
+        let (author, name) = (ModName::new(author.as_ref()), ModName::new(name.as_ref()));
+        self.0
+            .iter_mut()
+            .find(|m| author == m.author.as_str() && name == m.name.as_str())
+    }
+
+    /// Search the index for packages matching the query, best matches first
+    ///
+    /// Text is matched case-insensitively against each package's name, author, and the
+    /// description of its latest version. If no substring matches, a fuzzy match on the name
+    /// is attempted, so `"srvutil"` will still find `Server_Utilities`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use thermite::{api::get_package_index, model::SearchQuery};
+    /// let index = get_package_index().unwrap();
+    /// let maps = index.search(SearchQuery::new("map").category("Maps"));
+    /// ```
+    #[must_use]
+    pub fn search(&self, query: impl Into<SearchQuery>) -> Vec<&Mod> {
+        let query = query.into();
+        let mut found: Vec<(u8, &Mod)> = self
+            .0
+            .iter()
+            .filter(|m| query.filter(m))
+            .filter_map(|m| query.score(m).map(|score| (score, m)))
+            .collect();
+
+        found.sort_by_key(|(score, _)| Reverse(*score));
+        found.into_iter().map(|(_, m)| m).collect()
+    }
 }
 
-fn default_mod_state() -> bool {
-    true
+impl Deref for PackageIndex {
+    type Target = Vec<Mod>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
 }
 
-impl Hash for EnabledMods {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.client.hash(state);
-        self.custom.hash(state);
-        self.servers.hash(state);
-        self.mods.hash(state);
+impl DerefMut for PackageIndex {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
     }
 }
 
-impl Default for EnabledMods {
-    fn default() -> Self {
+impl From<Vec<Mod>> for PackageIndex {
+    fn from(value: Vec<Mod>) -> Self {
+        Self(value)
+    }
+}
+
+impl FromIterator<Mod> for PackageIndex {
+    fn from_iter<T: IntoIterator<Item = Mod>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for PackageIndex {
+    type Item = Mod;
+    type IntoIter = std::vec::IntoIter<Mod>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a PackageIndex {
+    type Item = &'a Mod;
+    type IntoIter = std::slice::Iter<'a, Mod>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// Packages that changed when refreshing an index, identified by `author-name`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexDiff {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl IndexDiff {
+    /// Returns `true` if nothing changed
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Parameters for [`PackageIndex::search`]
+///
+/// Deprecated packages are excluded unless `deprecated(true)` is set
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    text: Option<String>,
+    /// The `author-name` keys of the installed packages, if only those should match
+    installed: Option<BTreeSet<String>>,
+    include_deprecated: bool,
+    categories: Vec<String>,
+}
+
+impl SearchQuery {
+    #[must_use]
+    pub fn new(text: impl Into<String>) -> Self {
         Self {
-            client: true,
-            custom: true,
-            servers: true,
-            mods: BTreeMap::new(),
-            hash: 0,
-            path: None,
-            do_save: true,
+            text: Some(text.into()),
+            ..Default::default()
+        }
+    }
+
+    /// A query that matches every package, useful for only applying filters
+    #[must_use]
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Only match packages that one of the `installed` mods came from, going by its author and the
+    /// name in its manifest, e.g. from [`find_mods`](crate::core::find_mods)
+    #[must_use]
+    pub fn installed(mut self, installed: &[InstalledMod]) -> Self {
+        self.installed = Some(
+            installed
+                .iter()
+                .map(|m| dep_key(&m.author, &m.manifest.name))
+                .collect(),
+        );
+        self
+    }
+
+    /// Include deprecated packages in the results
+    #[must_use]
+    pub const fn deprecated(mut self, include_deprecated: bool) -> Self {
+        self.include_deprecated = include_deprecated;
+        self
+    }
+
+    /// Only match packages in this category. Can be called multiple times, in which
+    /// case packages must be in every category.
+    #[must_use]
+    pub fn category(mut self, category: impl Into<String>) -> Self {
+        self.categories.push(category.into());
+        self
+    }
+
+    fn filter(&self, m: &Mod) -> bool {
+        self.installed
+            .as_ref()
+            .map_or(true, |keys| keys.contains(&dep_key(&m.author, &m.name)))
+            && (self.include_deprecated || !m.deprecated)
+            && self
+                .categories
+                .iter()
+                .all(|c| m.categories.iter().any(|mc| mc.eq_ignore_ascii_case(c)))
+    }
+
+    fn score(&self, m: &Mod) -> Option<u8> {
+        let Some(text) = self.text.as_deref().map(str::to_lowercase) else {
+            return Some(0);
+        };
+        if text.is_empty() {
+            return Some(0);
+        }
+
+        let name = m.name.to_lowercase();
+        if name == text {
+            Some(5)
+        } else if name.starts_with(&text) {
+            Some(4)
+        } else if name.contains(&text) {
+            Some(3)
+        } else if m.author.to_lowercase().contains(&text) {
+            Some(2)
+        } else if m
+            .get_latest()
+            .is_some_and(|v| v.desc.to_lowercase().contains(&text))
+        {
+            Some(1)
+        } else if is_subsequence(&text, &name) {
+            Some(0)
+        } else {
+            None
         }
     }
 }
 
-impl Drop for EnabledMods {
-    fn drop(&mut self) {
-        if let Some(path) = self.path.as_ref() {
-            let hash = {
-                let mut hasher = DefaultHasher::new();
-                self.hash(&mut hasher);
-                hasher.finish()
-            };
+impl From<&str> for SearchQuery {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
 
-            if self.do_save && hash != self.hash {
-                if let Err(e) = self.save() {
-                    error!(
-                        "Encountered error while saving enabled_mods.json to {}:\n {}",
-                        path.display(),
-                        e
-                    );
-                } else {
-                    debug!("Wrote file at {}", path.display());
-                }
-            }
+impl From<String> for SearchQuery {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Checks if every character of `needle` appears in `haystack` in order
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
+
+/// A package version, compared as semver so `0.10.0` is newer than `0.9.0` and `1.0.0-rc1` is older
+/// than `1.0.0`
+///
+/// Thunderstore doesn't enforce semver, so any string is accepted. Four-part versions like `1.0.0.1`
+/// sort after `1.0.0`, and versions that can't be parsed are compared by their numeric components.
+/// (De)serializes as a plain string, e.g. `"1.2.3"`
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(from = "String", into = "String")]
+pub struct Version {
+    raw: String,
+    semver: Option<semver::Version>,
+}
+
+impl Version {
+    #[must_use]
+    pub fn new(raw: impl Into<String>) -> Self {
+        let raw = raw.into();
+        let semver = parse_semver(&raw);
+        Self { raw, semver }
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    /// The parsed version, if the string is semver or a four-part version
+    #[must_use]
+    pub fn semver(&self) -> Option<&semver::Version> {
+        self.semver.as_ref()
+    }
+}
+
+/// Parse a version as semver, moving the fourth part of `X.Y.Z.W` versions into the build metadata
+fn parse_semver(raw: &str) -> Option<semver::Version> {
+    if let Ok(version) = semver::Version::parse(raw) {
+        return Some(version);
+    }
+
+    let parts = raw
+        .split('.')
+        .map(|p| p.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let [major, minor, patch, build] = parts[..] else {
+        return None;
+    };
+    let mut version = semver::Version::new(major, minor, patch);
+    version.build = semver::BuildMetadata::new(&build.to_string()).ok()?;
+
+    Some(version)
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (&self.semver, &other.semver) {
+            (Some(a), Some(b)) => a.cmp(b),
+            _ => compare_components(&self.raw, &other.raw),
         }
+        .then_with(|| self.raw.cmp(&other.raw))
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl Eq for Version {}
+
+impl Hash for Version {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.raw.hash(state);
+    }
+}
+
+impl PartialEq<str> for Version {
+    fn eq(&self, other: &str) -> bool {
+        self.raw == other
+    }
+}
+
+impl PartialEq<&str> for Version {
+    fn eq(&self, other: &&str) -> bool {
+        self.raw == *other
+    }
+}
+
+impl Deref for Version {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.raw
+    }
+}
+
+impl AsRef<str> for Version {
+    fn as_ref(&self) -> &str {
+        &self.raw
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl From<String> for Version {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&str> for Version {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<Version> for String {
+    fn from(value: Version) -> Self {
+        value.raw
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for Version {
+    fn schema_name() -> String {
+        "Version".into()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(gen)
+    }
+}
+
+/// A single version of a package
+///
+/// Versions are ordered by their parsed `version`, see [`Version`]. Entries with the same version are
+/// ordered by their other fields so the ordering stays consistent with `==`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ModVersion {
+    pub name: String,
+    pub full_name: String,
+    pub version: Version,
+    pub url: String,
+    pub desc: String,
+    pub deps: Vec<String>,
+    pub installed: bool,
+    pub global: bool,
+    pub file_size: u64,
+    ///Number of times this version has been downloaded
+    #[serde(default)]
+    pub downloads: u64,
+    ///When this version was published, as reported by Thunderstore
+    #[serde(default)]
+    pub date_created: String,
+}
+
+impl ModVersion {
+    /// When this version was published, if Thunderstore reported a valid timestamp
+    #[cfg(feature = "timestamps")]
+    #[must_use]
+    pub fn created(&self) -> Option<OffsetDateTime> {
+        parse_timestamp(&self.date_created)
+    }
+
+    #[must_use]
+    pub const fn size(&self) -> FileSize {
+        FileSize::new(self.file_size)
+    }
+
+    /// The package size formatted like `2.99 MB`, see [`FileSize`]
+    #[must_use]
+    pub fn file_size_string(&self) -> String {
+        self.size().to_string()
+    }
+}
+
+#[cfg(test)]
+impl ModVersion {
+    /// A version for tests, with its name and version taken from `full_name`
+    pub(crate) fn test(full_name: &str, deps: &[&str]) -> Self {
+        let (_, name, version) = split_modstring(full_name).expect("test modstring");
+        Self {
+            name: name.into(),
+            full_name: full_name.into(),
+            version: version.into(),
+            url: "localhost".into(),
+            desc: "Test".into(),
+            deps: deps.iter().map(ToString::to_string).collect(),
+            installed: false,
+            global: false,
+            file_size: 0,
+            downloads: 0,
+            date_created: String::new(),
+        }
+    }
+}
+
+/// A number of bytes, displayed in the largest unit that keeps the value below 1000, e.g. `2.99 MB`
+///
+/// Units are decimal (`1 KB` = 1000 bytes) unless [`binary`](Self::binary) is set, in which case they're
+/// powers of 1024 and labelled `KiB`, `MiB` and `GiB`
+///
+/// # Example
+/// ```
+/// use thermite::model::FileSize;
+///
+/// assert_eq!(FileSize::new(2_990_000).to_string(), "2.99 MB");
+/// assert_eq!(FileSize::new(1536).binary(true).to_string(), "1.50 KiB");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileSize {
+    bytes: u64,
+    binary: bool,
+}
+
+impl FileSize {
+    const DECIMAL_UNITS: [&'static str; 4] = ["B", "KB", "MB", "GB"];
+    const BINARY_UNITS: [&'static str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    #[must_use]
+    pub const fn new(bytes: u64) -> Self {
+        Self {
+            bytes,
+            binary: false,
+        }
+    }
+
+    /// Use powers of 1024 instead of 1000
+    #[must_use]
+    pub const fn binary(mut self, binary: bool) -> Self {
+        self.binary = binary;
+        self
+    }
+
+    #[must_use]
+    pub const fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+impl From<u64> for FileSize {
+    fn from(value: u64) -> Self {
+        Self::new(value)
+    }
+}
+
+impl Display for FileSize {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (base, units) = if self.binary {
+            (1024.0, Self::BINARY_UNITS)
+        } else {
+            (1000.0, Self::DECIMAL_UNITS)
+        };
+
+        if (self.bytes as f64) < base {
+            return write!(f, "{} {}", self.bytes, units[0]);
+        }
+
+        let mut size = self.bytes as f64;
+        let mut unit = 0;
+        // compare after rounding so 999_999 bytes becomes 1.00 MB rather than 1000.00 KB
+        while unit + 1 < units.len() && (size * 100.0).round() / 100.0 >= base {
+            size /= base;
+            unit += 1;
+        }
+
+        write!(f, "{size:.2} {}", units[unit])
+    }
+}
+
+impl From<&Self> for ModVersion {
+    fn from(value: &Self) -> Self {
+        value.clone()
+    }
+}
+
+impl AsRef<Self> for ModVersion {
+    fn as_ref(&self) -> &Self {
+        self
+    }
+}
+
+impl Ord for ModVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.version
+            .cmp(&other.version)
+            .then_with(|| self.full_name.cmp(&other.full_name))
+            .then_with(|| self.name.cmp(&other.name))
+            .then_with(|| self.url.cmp(&other.url))
+            .then_with(|| self.desc.cmp(&other.desc))
+            .then_with(|| self.deps.cmp(&other.deps))
+            .then_with(|| self.installed.cmp(&other.installed))
+            .then_with(|| self.global.cmp(&other.global))
+            .then_with(|| self.file_size.cmp(&other.file_size))
+            .then_with(|| self.downloads.cmp(&other.downloads))
+            .then_with(|| self.date_created.cmp(&other.date_created))
+    }
+}
+
+impl PartialOrd for ModVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A Thunderstore `manifest.json`
+///
+/// Only `name` and `version_number` are required when deserializing, use [`Manifest::validate`] to check
+/// everything Thunderstore requires
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Manifest {
+    pub name: String,
+    pub version_number: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub website_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dependencies: Option<Vec<String>>,
+    /// Packages this package can't be installed alongside, as `author-name` or full modstrings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub incompatibilities: Option<Vec<String>>,
+    /// The package's namespace, added by some mod managers when installing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// The folder the package was installed as, added by some mod managers when installing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed_name: Option<String>,
+    #[serde(flatten)]
+    pub _extra: HashMap<String, Value>,
+}
+
+impl Manifest {
+    /// Longest description Thunderstore accepts
+    pub const MAX_DESCRIPTION_LEN: usize = 250;
+
+    /// Check the manifest against the rules Thunderstore enforces when uploading a package
+    ///
+    /// # Returns
+    /// * every issue found, or an empty list if Thunderstore would accept the manifest
+    #[must_use]
+    pub fn validate(&self) -> Vec<Issue> {
+        let mut issues = vec![];
+        if self.name.is_empty() {
+            issues.push(Issue::MissingName);
+        } else if !self
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            issues.push(Issue::InvalidName(self.name.clone()));
+        }
+
+        let parts = self.version_number.split('.').collect::<Vec<_>>();
+        if parts.len() != 3
+            || parts
+                .iter()
+                .any(|p| p.is_empty() || !p.bytes().all(|b| b.is_ascii_digit()))
+        {
+            issues.push(Issue::InvalidVersion(self.version_number.clone()));
+        }
+
+        for (field, missing) in [
+            ("website_url", self.website_url.is_none()),
+            ("description", self.description.is_none()),
+            ("dependencies", self.dependencies.is_none()),
+        ] {
+            if missing {
+                issues.push(Issue::MissingField(field));
+            }
+        }
+
+        if let Some(len) = self
+            .description
+            .as_ref()
+            .map(|d| d.chars().count())
+            .filter(|len| *len > Self::MAX_DESCRIPTION_LEN)
+        {
+            issues.push(Issue::DescriptionTooLong(len));
+        }
+
+        for dep in self.dependencies.iter().flatten() {
+            if parse_modstring(dep).is_err() {
+                issues.push(Issue::InvalidDependency(dep.clone()));
+            }
+        }
+
+        issues
+    }
+}
+
+// enabledmods.json
+
+/// A mod or package name that compares case-insensitively
+///
+/// Thunderstore names, folder names and `mod.json` names often disagree on case, and Thunderstore replaces
+/// spaces with underscores, so `Server Utilities`, `server_utilities` and `Server_Utilities` are all equal.
+/// The original spelling is kept for display and serialization.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(transparent)]
+pub struct ModName(String);
+
+impl ModName {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The form names are compared in: trimmed, lowercase, with spaces replaced by underscores
+    #[must_use]
+    pub fn normalized(&self) -> String {
+        self.key().collect()
+    }
+
+    fn key(&self) -> impl Iterator<Item = char> + '_ {
+        self.0
+            .trim()
+            .chars()
+            .flat_map(char::to_lowercase)
+            .map(|c| if c == ' ' { '_' } else { c })
+    }
+}
+
+impl PartialEq for ModName {
+    fn eq(&self, other: &Self) -> bool {
+        self.key().eq(other.key())
+    }
+}
+
+impl Eq for ModName {}
+
+impl PartialEq<str> for ModName {
+    fn eq(&self, other: &str) -> bool {
+        *self == Self::new(other)
+    }
+}
+
+impl PartialEq<&str> for ModName {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+impl PartialOrd for ModName {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ModName {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(other.key())
+    }
+}
+
+impl Hash for ModName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for c in self.key() {
+            c.hash(state);
+        }
+    }
+}
+
+impl Deref for ModName {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<str> for ModName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for ModName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<String> for ModName {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for ModName {
+    fn from(value: &str) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<ModName> for String {
+    fn from(value: ModName) -> Self {
+        value.0
+    }
+}
+
+/// Represents an enabledmods.json file. Core mods will default to `true` if not present when deserializing.
+///
+/// Mod names are looked up case-insensitively, see [`ModName`]. The core mods are always present and
+/// written first, so the file looks the same as one written by Northstar. Every other entry is kept
+/// with its original spelling and written in the same order as before.
+///
+/// Automatically writes any changes made when dropped (call `dont_save` to disable)
+#[derive(Clone, Debug)]
+pub struct EnabledMods {
+    /// Every mod's state, including the core mods, by the name as it was written
+    mods: BTreeMap<String, bool>,
+    /// The entry in `mods` each name is looked up as. If entries only differ in case, the first is used
+    index: BTreeMap<ModName, String>,
+    /// Mods that can't be removed, in the order they're written
+    core: Vec<ModName>,
+    ///Hash of the file as it was loaded
+    hash: u64,
+    ///Path to the file to read & write
+    path: Option<PathBuf>,
+    do_save: bool,
+}
+
+impl Hash for EnabledMods {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.mods.hash(state);
+    }
+}
+
+impl Default for EnabledMods {
+    fn default() -> Self {
+        Self::from_mods(BTreeMap::new())
+    }
+}
+
+impl Serialize for EnabledMods {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(Some(self.mods.len()))?;
+        for (name, state) in self.iter() {
+            map.serialize_entry(name, &state)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for EnabledMods {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        BTreeMap::deserialize(deserializer).map(Self::from_mods)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for EnabledMods {
+    fn schema_name() -> String {
+        "EnabledMods".into()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        BTreeMap::<String, bool>::json_schema(gen)
+    }
+}
+
+impl Drop for EnabledMods {
+    fn drop(&mut self) {
+        if let Some(path) = self.path.as_ref() {
+            let hash = {
+                let mut hasher = DefaultHasher::new();
+                self.hash(&mut hasher);
+                hasher.finish()
+            };
+
+            if self.do_save && hash != self.hash {
+                if let Err(e) = self.save() {
+                    error!(
+                        "Encountered error while saving enabled_mods.json to {}:\n {}",
+                        path.display(),
+                        e
+                    );
+                } else {
+                    debug!("Wrote file at {}", path.display());
+                }
+            }
+        }
+    }
+}
+
+impl EnabledMods {
+    /// Attempts to read an `EnabledMods` from the path
+    ///
+    /// # Errors
+    /// - The file doesn't exist
+    /// - The file isn't formatted properly
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ThermiteError> {
+        let raw = fs::read_to_string(path)?;
+
+        json5::from_str(&raw).map_err(Into::into)
+    }
+
+    /// The core mods used by default, in the order Northstar writes them
+    pub const DEFAULT_CORE_MODS: [&'static str; 3] = [
+        "Northstar.Client",
+        "Northstar.Custom",
+        "Northstar.CustomServers",
+    ];
+
+    fn from_mods(mods: BTreeMap<String, bool>) -> Self {
+        let mut enabled = Self {
+            mods,
+            index: BTreeMap::new(),
+            core: vec![],
+            hash: 0,
+            path: None,
+            do_save: true,
+        };
+        enabled.reindex();
+        enabled.set_core_mods(Self::DEFAULT_CORE_MODS);
+        enabled
+    }
+
+    fn reindex(&mut self) {
+        self.index.clear();
+        for name in self.mods.keys() {
+            self.index
+                .entry(ModName::new(name.as_str()))
+                .or_insert_with(|| name.clone());
+        }
+    }
+
+    /// The key of the entry for `name`, if there is one
+    fn key(&self, name: &str) -> Option<&String> {
+        self.index.get(&ModName::new(name))
+    }
+
+    /// The mods that can't be removed, in the order they're written
+    #[must_use]
+    pub fn core_mods(&self) -> &[ModName] {
+        &self.core
+    }
+
+    /// Change which mods can't be removed, e.g. if a Northstar release adds or renames a core mod.
+    /// Core mods without an entry are added as enabled
+    pub fn set_core_mods(&mut self, names: impl IntoIterator<Item = impl Into<ModName>>) {
+        self.core = names.into_iter().map(Into::into).collect();
+        for name in self.core.clone() {
+            if self.key(name.as_str()).is_none() {
+                self.set(name.as_str(), true);
+            }
+        }
+    }
+
+    /// Whether `name` is one of the core mods
+    pub fn is_core(&self, name: impl AsRef<str>) -> bool {
+        let name = ModName::new(name.as_ref());
+        self.core.contains(&name)
+    }
+
+    /// Returns a default `EnabledMods` with the path property set
+    pub fn default_with_path(path: impl AsRef<Path>) -> Self {
+        let mut s = Self::default();
+        s.path = Some(path.as_ref().to_path_buf());
+        s
+    }
+
+    /// Don't attempt to write the file when dropped
+    pub fn dont_save(&mut self) {
+        self.do_save = false;
+    }
+
+    /// Do attempt to write the file when dropped
+    pub fn do_save(&mut self) {
+        self.do_save = true;
+    }
+
+    /// Saves the file using the path it was loaded from
+    ///
+    /// # Errors
+    /// - If the path isn't set
+    /// - If there is an IO error
+    pub fn save(&self) -> Result<(), ThermiteError> {
+        let path = self.path.as_ref().ok_or(ThermiteError::MissingPath)?;
+        write_atomic(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+
+    /// Saves the file using the provided path
+    ///
+    /// # Errors
+    /// - If there is an IO error
+    pub fn save_with_path(&mut self, path: impl AsRef<Path>) -> Result<(), ThermiteError> {
+        self.path = Some(path.as_ref().to_owned());
+        self.save()
+    }
+
+    /// Path the file will be written to
+    #[must_use]
+    pub const fn path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+
+    pub fn set_path(&mut self, path: impl Into<Option<PathBuf>>) {
+        self.path = path.into();
+    }
+
+    /// Returns the current state of a mod
+    ///
+    /// # Warning
+    /// Returns `true` if a mod is missing from the file
+    pub fn is_enabled(&self, name: impl AsRef<str>) -> bool {
+        self.get(name).unwrap_or(true)
+    }
+
+    /// Get the current state of a mod if it exists
+    pub fn get(&self, name: impl AsRef<str>) -> Option<bool> {
+        let key = self.key(name.as_ref())?;
+        self.mods.get(key).copied()
+    }
+
+    /// Updates or inserts a mod's state
+    ///
+    /// An existing entry keeps its spelling, even if `name` differs in case
+    pub fn set(&mut self, name: impl AsRef<str>, val: bool) -> Option<bool> {
+        let name = name.as_ref();
+        if let Some(key) = self.key(name).cloned() {
+            return self.mods.insert(key, val);
+        }
+        self.index.insert(ModName::new(name), name.to_owned());
+        self.mods.insert(name.to_owned(), val)
+    }
+
+    /// Removes a mod's entry, or every entry if there are several that only differ in case, returning
+    /// its state. Core mods can't be removed
+    pub fn remove(&mut self, name: impl AsRef<str>) -> Option<bool> {
+        if self.is_core(&name) {
+            return None;
+        }
+
+        let prev = self.get(&name);
+        let name = ModName::new(name.as_ref());
+        self.mods.retain(|key, _| name != key.as_str());
+        self.index.remove(&name);
+        prev
+    }
+
+    /// Number of entries, including the core mods
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.mods.len()
+    }
+
+    /// `true` if there are no entries, which can only happen if there are no core mods
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.mods.is_empty()
+    }
+
+    /// Every mod in the file and its state, starting with the core mods
+    pub fn iter(&self) -> impl Iterator<Item = (&str, bool)> {
+        let core = self
+            .core
+            .iter()
+            .filter_map(|name| self.key(name.as_str()))
+            .collect::<Vec<_>>();
+        let rest = self
+            .mods
+            .keys()
+            .filter(|key| !core.contains(key))
+            .collect::<Vec<_>>();
+        core.into_iter()
+            .chain(rest)
+            .map(|key| (key.as_str(), self.mods[key]))
+    }
+
+    /// Enable every mod, including the core mods
+    pub fn enable_all(&mut self) {
+        self.mods.values_mut().for_each(|state| *state = true);
+    }
+
+    /// Disable every mod except the core mods, e.g. to check whether a mod is causing a crash
+    ///
+    /// Northstar won't start without the core mods, use `set` to disable them explicitly
+    pub fn disable_all(&mut self) {
+        for (name, state) in &mut self.mods {
+            if !self.core.iter().any(|core| core == name.as_str()) {
+                *state = false;
+            }
+        }
+    }
+
+    /// Set the state of each mod in `names`, inserting any that are missing
+    pub fn set_many(&mut self, names: impl IntoIterator<Item = impl AsRef<str>>, val: bool) {
+        for name in names {
+            self.set(name, val);
+        }
+    }
+
+    /// Remove the entries that `keep` returns `false` for. Core mods are never removed
+    pub fn retain(&mut self, mut keep: impl FnMut(&str, bool) -> bool) {
+        let core = &self.core;
+        self.mods.retain(|name, state| {
+            core.iter().any(|core| core == name.as_str()) || keep(name.as_str(), *state)
+        });
+        self.reindex();
+    }
+
+    /// Make the file match the installed mods
+    ///
+    /// Mods without an entry are added as enabled, and entries for mods that aren't installed are
+    /// removed. Core mods, as found by `is_core_mod` or set with `set_core_mods`, are left alone.
+    pub fn sync(&mut self, installed: &[InstalledMod]) -> SyncReport {
+        let (core, installed): (Vec<_>, Vec<_>) = installed.iter().partition(|m| is_core_mod(m));
+        let names = installed
+            .iter()
+            .map(|m| ModName::new(m.mod_json.name.as_str()))
+            .filter(|name| !self.core.contains(name))
+            .collect::<BTreeSet<_>>();
+
+        let mut report = SyncReport::default();
+        let protected = &self.core;
+        self.mods.retain(|key, _| {
+            let name = ModName::new(key.as_str());
+            let keep = names.contains(&name)
+                || protected.contains(&name)
+                || core.iter().any(|m| name == *m.mod_json.name);
+            if !keep {
+                report.removed.push(key.clone());
+            }
+            keep
+        });
+        self.reindex();
+        for name in names {
+            if self.key(name.as_str()).is_none() {
+                report.added.push(name.to_string());
+                self.set(name.as_str(), true);
+            }
+        }
+
+        report
+    }
+}
+
+/// The changes made by [`EnabledMods::sync`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Installed mods that were added as enabled
+    pub added: Vec<String>,
+    /// Entries that were removed because the mod isn't installed
+    pub removed: Vec<String>,
+}
+
+impl SyncReport {
+    /// `true` if nothing changed
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Represents an installed package
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InstalledMod {
+    pub manifest: Manifest,
+    pub mod_json: ModJSON,
+    pub author: String,
+    /// The package version from the `author-name-X.Y.Z` folder the mod was found in
+    pub version: String,
+    #[serde(serialize_with = "serialize_path")]
+    pub path: PathBuf,
+}
+
+/// Serialize a path as a string, replacing anything that isn't valid UTF-8 instead of failing, so
+/// any path can be sent to a frontend
+pub(crate) fn serialize_path<S: serde::Serializer>(
+    path: &Path,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&path.to_string_lossy())
+}
+
+impl InstalledMod {
+    /// Where the mod has to be installed, see [`ModJSON::side`]
+    #[must_use]
+    pub fn side(&self) -> Side {
+        self.mod_json.side()
+    }
+
+    /// The package's `author-name-X.Y.Z` modstring, using the name and version from its manifest
+    #[must_use]
+    pub fn full_name(&self) -> String {
+        ModString::from(self).to_string()
+    }
+
+    /// The package version from its manifest, which [`version`](Self::version) might not match if the
+    /// package folder was renamed
+    #[must_use]
+    pub fn manifest_version(&self) -> Version {
+        Version::new(self.manifest.version_number.as_str())
+    }
+
+    /// Whether this is one of the Northstar core mods, see `is_core_mod`
+    #[must_use]
+    pub fn is_core(&self) -> bool {
+        is_core_mod(self)
+    }
+
+    /// Whether the mod is enabled in `enabled`. Mods without an entry are enabled
+    #[must_use]
+    pub fn enabled_in(&self, enabled: &EnabledMods) -> bool {
+        enabled.is_enabled(&self.mod_json.name)
+    }
+
+    /// Whether the mod's manifest declares that it can't be installed alongside `package`
+    ///
+    /// `package` is matched against the manifest's `incompatibilities` as `author-name` or a full
+    /// modstring, ignoring the version
+    #[must_use]
+    pub fn is_incompatible_with(&self, package: impl AsRef<str>) -> bool {
+        let key = package_key(package.as_ref());
+        self.manifest
+            .incompatibilities
+            .iter()
+            .flatten()
+            .any(|i| package_key(i) == key)
+    }
+
+    /// Whether the mod's `mod.json` declares that it can't be loaded alongside the mod named `name`
+    #[must_use]
+    pub fn is_incompatible_with_mod(&self, name: impl AsRef<str>) -> bool {
+        let name = ModName::from(name.as_ref());
+        self.mod_json
+            .incompatibilities
+            .iter()
+            .any(|i| name == i.as_str())
+    }
+
+    /// The folder containing the mod's `mod.json`
+    #[must_use]
+    pub fn mod_dir(&self) -> &Path {
+        &self.path
+    }
+
+    /// The folder of the package the mod was installed from, i.e. the closest folder above the mod
+    /// with a `manifest.json`, which has to be directly inside `packages_dir`
+    ///
+    /// # Returns
+    /// * `None` if there's no such folder, e.g. the mod doesn't have a manifest of its own and the
+    ///   closest one belongs to a profile or the game
+    #[must_use]
+    pub fn package_dir(&self, packages_dir: impl AsRef<Path>) -> Option<&Path> {
+        let packages_dir = packages_dir.as_ref();
+        let canonical = packages_dir.canonicalize().ok();
+        let is_packages_dir = |dir: &Path| {
+            dir == packages_dir || canonical.is_some() && dir.canonicalize().ok() == canonical
+        };
+
+        self.path
+            .ancestors()
+            .take_while(|p| !is_packages_dir(p))
+            .find(|p| p.join("manifest.json").is_file())
+            .filter(|p| p.parent().is_some_and(is_packages_dir))
+    }
+}
+
+/// A package's identity, written as `author-name-X.Y.Z`
+///
+/// Parse one from a string with [`str::parse`], which accepts the same strings as `parse_modstring`
+///
+/// # Example
+/// ```
+/// use thermite::model::ModString;
+///
+/// let parsed: ModString = "Fifty-Server_Utilities-1.2.3".parse().unwrap();
+/// assert_eq!(parsed.name, "Server_Utilities");
+/// assert_eq!(parsed.to_string(), "Fifty-Server_Utilities-1.2.3");
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(try_from = "String", into = "String")]
+pub struct ModString {
+    pub author: String,
+    pub name: String,
+    pub version: String,
+}
+
+impl ModString {
+    pub fn new(
+        author: impl Into<String>,
+        name: impl Into<String>,
+        version: impl Into<String>,
+    ) -> Self {
+        Self {
+            author: author.into(),
+            name: name.into(),
+            version: version.into(),
+        }
+    }
+
+    /// `author-name`, without the version
+    #[must_use]
+    pub fn package(&self) -> String {
+        format!("{}-{}", self.author, self.name)
+    }
+}
+
+impl Display for ModString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}-{}", self.author, self.name, self.version)
+    }
+}
+
+impl FromStr for ModString {
+    type Err = ThermiteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        split_modstring(s)
+            .map(|(author, name, version)| Self::new(author, name, version))
+            .ok_or_else(|| ThermiteError::NameError(s.into()))
+    }
+}
+
+impl TryFrom<String> for ModString {
+    type Error = ThermiteError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl TryFrom<&ModVersion> for ModString {
+    type Error = ThermiteError;
+
+    /// Parses the version's `full_name`, which is the only place Thunderstore puts the author
+    fn try_from(value: &ModVersion) -> Result<Self, Self::Error> {
+        value.full_name.parse()
+    }
+}
+
+impl From<&InstalledMod> for ModString {
+    fn from(value: &InstalledMod) -> Self {
+        Self::new(
+            value.author.as_str(),
+            value.manifest.name.as_str(),
+            value.manifest.version_number.as_str(),
+        )
+    }
+}
+
+impl From<ModString> for String {
+    fn from(value: ModString) -> Self {
+        value.to_string()
+    }
+}
+
+impl From<ModString> for (String, String, String) {
+    fn from(value: ModString) -> Self {
+        (value.author, value.name, value.version)
+    }
+}
+
+/// A mod folder directly inside a profile's `mods` directory, without a Thunderstore package around it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BareMod {
+    pub mod_json: ModJSON,
+    #[serde(serialize_with = "serialize_path")]
+    pub path: PathBuf,
+}
+
+/// Any mod in a profile, as returned by `find_all_mods`
+///
+/// Serializes as the mod with a `kind` of `package`, `core` or `bare` added
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ModEntry {
+    /// A mod from a Thunderstore package in `packages`, boxed since it's much larger than the others
+    Package(Box<InstalledMod>),
+    /// One of the Northstar core mods in `mods`
+    Core(BareMod),
+    /// Any other mod in `mods`, e.g. one that was installed by hand
+    Bare(BareMod),
+}
+
+impl ModEntry {
+    #[must_use]
+    pub const fn mod_json(&self) -> &ModJSON {
+        match self {
+            Self::Package(m) => &m.mod_json,
+            Self::Core(m) | Self::Bare(m) => &m.mod_json,
+        }
+    }
+
+    /// The folder containing the mod's `mod.json`
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Package(m) => &m.path,
+            Self::Core(m) | Self::Bare(m) => &m.path,
+        }
+    }
+
+    #[must_use]
+    pub const fn is_core(&self) -> bool {
+        matches!(self, Self::Core(_))
+    }
+}
+
+/// A Northstar plugin DLL, as returned by `find_plugins`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plugin {
+    /// File name without the `.dll` extension
+    pub name: String,
+    pub path: PathBuf,
+    /// Size of the file in bytes
+    pub size: u64,
+    /// File version from the DLL's version resource, if it has one
+    pub version: Option<String>,
+}
+
+/// An installed package with a newer version available, returned by `which_outdated`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outdated {
+    pub author: String,
+    pub name: String,
+    /// The installed version
+    pub installed: String,
+    /// The latest version in the index
+    pub latest: ModVersion,
+}
+
+/// A package recorded in a [`LocalIndex`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct LocalPackage {
+    /// The package's `author-name-X.Y.Z` modstring
+    pub mod_string: String,
+    /// SHA-256 of the archive it was installed from, if known
+    pub hash: Option<String>,
+    /// When it was installed, in seconds since the Unix epoch
+    pub installed_at: u64,
+    /// `true` if the user asked for the package, `false` if it was only installed as a dependency
+    pub explicit: bool,
+    /// Thunderstore dependency strings of the installed version
+    #[serde(default)]
+    pub deps: Vec<String>,
+    /// Names of the Northstar mods in the package, from their `mod.json`
+    #[serde(default)]
+    pub mods: Vec<String>,
+}
+
+/// Record of every package thermite installed into a packages directory, stored as JSON in
+/// [`LOCAL_INDEX_FILE`](crate::core::utils::LOCAL_INDEX_FILE)
+///
+/// Packages are keyed by `author-name`, so lookups accept either that or a full modstring
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct LocalIndex {
+    packages: BTreeMap<String, LocalPackage>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl LocalIndex {
+    /// Attempts to read a `LocalIndex` from the path
+    ///
+    /// # Errors
+    /// - The file doesn't exist
+    /// - The file isn't formatted properly
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ThermiteError> {
+        let raw = fs::read_to_string(path.as_ref())?;
+        let mut index: Self = serde_json::from_str(&raw)?;
+        index.path = Some(path.as_ref().to_path_buf());
+
+        Ok(index)
+    }
+
+    /// Returns an empty `LocalIndex` with the path property set
+    pub fn default_with_path(path: impl AsRef<Path>) -> Self {
+        Self {
+            packages: BTreeMap::new(),
+            path: Some(path.as_ref().to_path_buf()),
+        }
+    }
+
+    #[must_use]
+    pub const fn path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+
+    pub fn set_path(&mut self, path: impl Into<Option<PathBuf>>) {
+        self.path = path.into();
+    }
+
+    #[must_use]
+    pub fn get(&self, package: impl AsRef<str>) -> Option<&LocalPackage> {
+        self.packages.get(&package_key(package.as_ref()))
+    }
+
+    #[must_use]
+    pub fn contains(&self, package: impl AsRef<str>) -> bool {
+        self.get(package).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &LocalPackage> {
+        self.packages.values()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.packages.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+
+    /// Add a package, replacing any other version of it and returning the old entry
+    ///
+    /// # Errors
+    /// - The package's modstring isn't formatted like `author-name-X.Y.Z`
+    pub fn insert(&mut self, package: LocalPackage) -> Result<Option<LocalPackage>, ThermiteError> {
+        let (author, name, _) = parse_modstring(&package.mod_string)?;
+        Ok(self.packages.insert(dep_key(&author, &name), package))
+    }
+
+    /// Record that a version of a package was installed
+    ///
+    /// A package that was already installed explicitly stays explicit, so updating or reinstalling it
+    /// as a dependency doesn't make it eligible for removal
+    ///
+    /// # Params
+    /// * `version` - the version that was installed
+    /// * `mods` - the mods that were in the package, e.g. as returned by `install_mod_verbose`
+    /// * `hash` - SHA-256 of the archive, if known
+    /// * `explicit` - whether the user asked for this package, rather than it being a dependency
+    ///
+    /// # Errors
+    /// - The version's `full_name` isn't formatted like `author-name-X.Y.Z`
+    pub fn record(
+        &mut self,
+        version: &ModVersion,
+        mods: &[InstalledMod],
+        hash: Option<String>,
+        explicit: bool,
+    ) -> Result<(), ThermiteError> {
+        let explicit = explicit || self.get(&version.full_name).is_some_and(|p| p.explicit);
+        let installed_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        self.insert(LocalPackage {
+            mod_string: version.full_name.clone(),
+            hash,
+            installed_at,
+            explicit,
+            deps: version.deps.clone(),
+            mods: mods.iter().map(|m| m.mod_json.name.clone()).collect(),
+        })?;
+
+        Ok(())
+    }
+
+    /// Remove a package, returning its entry
+    pub fn remove(&mut self, package: impl AsRef<str>) -> Option<LocalPackage> {
+        self.packages.remove(&package_key(package.as_ref()))
+    }
+
+    /// Mark a package as explicitly installed or not, returning `false` if it isn't in the index
+    pub fn set_explicit(&mut self, package: impl AsRef<str>, explicit: bool) -> bool {
+        self.packages
+            .get_mut(&package_key(package.as_ref()))
+            .map(|p| p.explicit = explicit)
+            .is_some()
+    }
+
+    /// Saves the file using the path it was loaded from
+    ///
+    /// # Errors
+    /// - If the path isn't set
+    /// - If there is an IO error
+    pub fn save(&self) -> Result<(), ThermiteError> {
+        let path = self.path.as_ref().ok_or(ThermiteError::MissingPath)?;
+        write_atomic(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+
+    /// Saves the file using the provided path
+    ///
+    /// # Errors
+    /// - If there is an IO error
+    pub fn save_with_path(&mut self, path: impl AsRef<Path>) -> Result<(), ThermiteError> {
+        self.path = Some(path.as_ref().to_path_buf());
+        self.save()
+    }
+}
+
+/// The arguments in `ns_startup_args.txt` or `ns_startup_args_dedi.txt`
+///
+/// Arguments keep the order and style they were parsed with, so saving only changes what was edited
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StartupArgs {
+    args: Vec<StartupArg>,
+    path: Option<PathBuf>,
+}
+
+/// A single startup argument, e.g. `-multiple`, `-profile=dev`, or `+setplaylist private_match`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StartupArg {
+    pub name: String,
+    pub value: Option<String>,
+    /// `true` if the value is joined to the name with `=` instead of a space
+    pub joined: bool,
+}
+
+impl StartupArgs {
+    /// Parse an argument string. Values can be quoted to include spaces, with `\"` for a literal quote
+    #[must_use]
+    pub fn parse(raw: &str) -> Self {
+        let mut args: Vec<StartupArg> = vec![];
+        for token in split_args(raw) {
+            if let Some(prev) = args
+                .last_mut()
+                .filter(|a| a.value.is_none() && !token.starts_with(['-', '+']))
+            {
+                prev.value = Some(token);
+            } else if let Some((name, value)) =
+                token.split_once('=').filter(|_| token.starts_with('-'))
+            {
+                args.push(StartupArg {
+                    name: name.into(),
+                    value: Some(value.into()),
+                    joined: true,
+                });
+            } else {
+                args.push(StartupArg {
+                    name: token,
+                    value: None,
+                    joined: false,
+                });
+            }
+        }
+
+        Self { args, path: None }
+    }
+
+    /// Attempts to read `StartupArgs` from the path
+    ///
+    /// # Errors
+    /// - The file doesn't exist
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ThermiteError> {
+        let raw = fs::read_to_string(path.as_ref())?;
+        let mut args = Self::parse(&raw);
+        args.path = Some(path.as_ref().to_path_buf());
+
+        Ok(args)
+    }
+
+    /// Returns an empty `StartupArgs` with the path property set
+    pub fn default_with_path(path: impl AsRef<Path>) -> Self {
+        Self {
+            args: vec![],
+            path: Some(path.as_ref().to_path_buf()),
+        }
+    }
+
+    #[must_use]
+    pub const fn path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+
+    pub fn set_path(&mut self, path: impl Into<Option<PathBuf>>) {
+        self.path = path.into();
+    }
+
+    #[must_use]
+    pub fn get(&self, name: impl AsRef<str>) -> Option<&StartupArg> {
+        self.args.iter().find(|a| a.name == name.as_ref())
+    }
+
+    #[must_use]
+    pub fn contains(&self, name: impl AsRef<str>) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StartupArg> {
+        self.args.iter()
+    }
+
+    /// Updates or appends an argument, returning the previous value
+    ///
+    /// New `-` arguments are written as `-name=value`, and `+` arguments as `+name value`
+    pub fn set_arg(
+        &mut self,
+        name: impl AsRef<str>,
+        value: Option<impl Into<String>>,
+    ) -> Option<StartupArg> {
+        let name = name.as_ref();
+        let value = value.map(Into::into);
+        if let Some(arg) = self.args.iter_mut().find(|a| a.name == name) {
+            let prev = arg.clone();
+            arg.value = value;
+            Some(prev)
+        } else {
+            self.args.push(StartupArg {
+                name: name.into(),
+                value,
+                joined: name.starts_with('-'),
+            });
+            None
+        }
+    }
+
+    /// Removes every occurence of an argument, returning the first
+    pub fn remove_arg(&mut self, name: impl AsRef<str>) -> Option<StartupArg> {
+        let first = self.get(&name).cloned();
+        self.args.retain(|a| a.name != name.as_ref());
+        first
+    }
+
+    /// Saves the file using the path it was loaded from
+    ///
+    /// # Errors
+    /// - If the path isn't set
+    /// - If there is an IO error
+    pub fn save(&self) -> Result<(), ThermiteError> {
+        let path = self.path.as_ref().ok_or(ThermiteError::MissingPath)?;
+        write_atomic(path, self.to_string())?;
+
+        Ok(())
+    }
+
+    /// Saves the file using the provided path
+    ///
+    /// # Errors
+    /// - If there is an IO error
+    pub fn save_with_path(&mut self, path: impl AsRef<Path>) -> Result<(), ThermiteError> {
+        self.path = Some(path.as_ref().to_path_buf());
+        self.save()
+    }
+}
+
+impl Display for StartupArgs {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, arg) in self.args.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", arg.name)?;
+            if let Some(value) = &arg.value {
+                write!(f, "{}", if arg.joined { '=' } else { ' ' })?;
+                write_arg_value(f, value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Write a value so that [`split_args`] reads it back unchanged, quoting it if it's empty or
+/// contains whitespace or quotes
+fn write_arg_value(f: &mut Formatter<'_>, value: &str) -> fmt::Result {
+    if !value.is_empty() && !value.contains(|c: char| c.is_whitespace() || c == '"') {
+        return write!(f, "{value}");
+    }
+
+    write!(f, "\"")?;
+    let mut backslashes = 0;
+    for c in value.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            // backslashes are only special right before a quote, where they have to be doubled
+            '"' => {
+                write!(f, "{}\\\"", "\\".repeat(backslashes * 2))?;
+                backslashes = 0;
+            }
+            c => {
+                write!(f, "{}{c}", "\\".repeat(backslashes))?;
+                backslashes = 0;
+            }
+        }
+    }
+    write!(f, "{}\"", "\\".repeat(backslashes * 2))
+}
+
+/// Split on whitespace, treating anything between double quotes as part of the same token
+///
+/// Quotes can be escaped like on the Windows command line: `\"` is a literal quote, and a run of
+/// backslashes before a quote is halved
+fn split_args(raw: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut started = false;
+    let mut backslashes = 0;
+    for c in raw.chars() {
+        if c == '\\' {
+            backslashes += 1;
+            started = true;
+            continue;
+        }
+        if c == '"' {
+            current.extend(std::iter::repeat('\\').take(backslashes / 2));
+            if backslashes % 2 == 1 {
+                current.push('"');
+            } else {
+                quoted = !quoted;
+            }
+            backslashes = 0;
+            started = true;
+            continue;
+        }
+        current.extend(std::iter::repeat('\\').take(backslashes));
+        backslashes = 0;
+
+        if c.is_whitespace() && !quoted {
+            if started {
+                tokens.push(std::mem::take(&mut current));
+                started = false;
+            }
+        } else {
+            current.push(c);
+            started = true;
+        }
+    }
+    current.extend(std::iter::repeat('\\').take(backslashes));
+    if started {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
+/// An `autoexec_ns_server.cfg` file
+///
+/// Comments, blank lines, and formatting are kept as they were read, so saving only changes the values that were set
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerConfig {
+    lines: Vec<CfgLine>,
+    path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CfgLine {
+    /// Blank lines, comments, and anything else that isn't a convar
+    Other(String),
+    ConVar {
+        indent: String,
+        name: String,
+        sep: String,
+        value: String,
+        quoted: bool,
+        /// Everything after the value, including any comment
+        rest: String,
+    },
+}
+
+impl ServerConfig {
+    #[must_use]
+    pub fn parse(raw: &str) -> Self {
+        let lines = raw.split('\n').map(parse_cfg_line).collect();
+        Self { lines, path: None }
+    }
+
+    /// Attempts to read a `ServerConfig` from the path
+    ///
+    /// # Errors
+    /// - The file doesn't exist
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ThermiteError> {
+        let raw = fs::read_to_string(path.as_ref())?;
+        let mut config = Self::parse(&raw);
+        config.path = Some(path.as_ref().to_path_buf());
+
+        Ok(config)
+    }
+
+    #[must_use]
+    pub const fn path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+
+    pub fn set_path(&mut self, path: impl Into<Option<PathBuf>>) {
+        self.path = path.into();
+    }
+
+    /// Get the value of a convar, without quotes
+    #[must_use]
+    pub fn get(&self, name: impl AsRef<str>) -> Option<&str> {
+        self.lines.iter().rev().find_map(|l| match l {
+            CfgLine::ConVar { name: n, value, .. } if n == name.as_ref() => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Updates a convar in place or appends it to the end of the file, returning the previous value
+    ///
+    /// Appended lines use the same line endings as the rest of the file
+    pub fn set(&mut self, name: impl AsRef<str>, value: impl Into<String>) -> Option<String> {
+        let name = name.as_ref();
+        let value = value.into();
+        let needs_quotes =
+            value.is_empty() || value.contains(char::is_whitespace) || value.contains('"');
+        let existing = self.lines.iter_mut().rev().find_map(|l| match l {
+            CfgLine::ConVar {
+                name: n,
+                value,
+                quoted,
+                ..
+            } if n == name => Some((value, quoted)),
+            _ => None,
+        });
+
+        if let Some((old, quoted)) = existing {
+            *quoted |= needs_quotes;
+            Some(std::mem::replace(old, value))
+        } else {
+            let crlf = self.is_crlf();
+            // keep the trailing newline at the end of the file
+            let at = if self
+                .lines
+                .last()
+                .is_some_and(|l| *l == CfgLine::Other(String::new()))
+            {
+                self.lines.len() - 1
+            } else {
+                // the last line is getting a line ending
+                if crlf {
+                    match self.lines.last_mut() {
+                        Some(CfgLine::Other(raw)) => raw.push('\r'),
+                        Some(CfgLine::ConVar { rest, .. }) => rest.push('\r'),
+                        None => {}
+                    }
+                }
+                self.lines.len()
+            };
+            let rest = if crlf && at < self.lines.len() {
+                "\r"
+            } else {
+                ""
+            };
+            self.lines.insert(
+                at,
+                CfgLine::ConVar {
+                    indent: String::new(),
+                    name: name.into(),
+                    sep: " ".into(),
+                    quoted: needs_quotes || value.parse::<f64>().is_err(),
+                    value,
+                    rest: rest.into(),
+                },
+            );
+            None
+        }
+    }
+
+    /// `true` if the file's lines end with `\r\n`
+    fn is_crlf(&self) -> bool {
+        let len = self.lines.len();
+        self.lines
+            .iter()
+            .take(len.saturating_sub(1))
+            .any(|l| match l {
+                CfgLine::Other(raw) => raw.ends_with('\r'),
+                CfgLine::ConVar { rest, .. } => rest.ends_with('\r'),
+            })
     }
-}
 
-impl EnabledMods {
-    /// Attempts to read an `EnabledMods` from the path
-    ///
-    /// # Errors
-    /// - The file doesn't exist
-    /// - The file isn't formatted properly
-    pub fn load(path: impl AsRef<Path>) -> Result<Self, ThermiteError> {
-        let raw = fs::read_to_string(path)?;
+    /// Removes every line setting a convar, returning the last value
+    pub fn remove(&mut self, name: impl AsRef<str>) -> Option<String> {
+        let prev = self.get(&name).map(ToOwned::to_owned);
+        self.lines
+            .retain(|l| !matches!(l, CfgLine::ConVar { name: n, .. } if n == name.as_ref()));
+        prev
+    }
 
-        json5::from_str(&raw).map_err(Into::into)
+    #[must_use]
+    pub fn server_name(&self) -> Option<&str> {
+        self.get("ns_server_name")
     }
 
-    /// Returns a default `EnabledMods` with the path property set
-    pub fn default_with_path(path: impl AsRef<Path>) -> Self {
-        let mut s = Self::default();
-        s.path = Some(path.as_ref().to_path_buf());
-        s
+    pub fn set_server_name(&mut self, name: impl Into<String>) -> Option<String> {
+        self.set("ns_server_name", name)
     }
 
-    /// Don't attempt to write the file when dropped
-    pub fn dont_save(&mut self) {
-        self.do_save = false;
+    #[must_use]
+    pub fn description(&self) -> Option<&str> {
+        self.get("ns_server_desc")
     }
 
-    /// Do attempt to write the file when dropped
-    pub fn do_save(&mut self) {
-        self.do_save = true;
+    pub fn set_description(&mut self, desc: impl Into<String>) -> Option<String> {
+        self.set("ns_server_desc", desc)
+    }
+
+    /// The server password, or `None` if the server is public
+    #[must_use]
+    pub fn password(&self) -> Option<&str> {
+        self.get("ns_server_password").filter(|p| !p.is_empty())
+    }
+
+    /// Set the server password. An empty password makes the server public
+    pub fn set_password(&mut self, password: impl Into<String>) -> Option<String> {
+        self.set("ns_server_password", password)
+    }
+
+    #[must_use]
+    pub fn playlist(&self) -> Option<&str> {
+        self.get("setplaylist")
+    }
+
+    pub fn set_playlist(&mut self, playlist: impl Into<String>) -> Option<String> {
+        self.set("setplaylist", playlist)
     }
 
     /// Saves the file using the path it was loaded from
@@ -217,17 +2339,10 @@ impl EnabledMods {
     /// - If the path isn't set
     /// - If there is an IO error
     pub fn save(&self) -> Result<(), ThermiteError> {
-        let parsed = serde_json::to_string_pretty(self)?;
-        if let Some(path) = &self.path {
-            if let Some(p) = path.parent() {
-                fs::create_dir_all(p)?;
-            }
+        let path = self.path.as_ref().ok_or(ThermiteError::MissingPath)?;
+        write_atomic(path, self.to_string())?;
 
-            fs::write(path, parsed)?;
-            Ok(())
-        } else {
-            Err(ThermiteError::MissingPath)
-        }
+        Ok(())
     }
 
     /// Saves the file using the provided path
@@ -235,75 +2350,207 @@ impl EnabledMods {
     /// # Errors
     /// - If there is an IO error
     pub fn save_with_path(&mut self, path: impl AsRef<Path>) -> Result<(), ThermiteError> {
-        self.path = Some(path.as_ref().to_owned());
+        self.path = Some(path.as_ref().to_path_buf());
         self.save()
     }
+}
 
-    /// Path the file will be written to
-    #[must_use]
-    pub const fn path(&self) -> Option<&PathBuf> {
-        self.path.as_ref()
+impl Display for ServerConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            match line {
+                CfgLine::Other(raw) => write!(f, "{raw}")?,
+                CfgLine::ConVar {
+                    indent,
+                    name,
+                    sep,
+                    value,
+                    quoted,
+                    rest,
+                } => {
+                    if *quoted {
+                        let value = value.replace('"', "\\\"");
+                        write!(f, "{indent}{name}{sep}\"{value}\"{rest}")?;
+                    } else {
+                        write!(f, "{indent}{name}{sep}{value}{rest}")?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
     }
+}
 
-    pub fn set_path(&mut self, path: impl Into<Option<PathBuf>>) {
-        self.path = path.into();
+fn parse_cfg_line(line: &str) -> CfgLine {
+    let body = line.trim_start();
+    let indent = &line[..line.len() - body.len()];
+    let name_end = body.find(char::is_whitespace).unwrap_or(body.len());
+    let (name, after) = body.split_at(name_end);
+    let value_start = after.trim_start();
+    if name.is_empty()
+        || name.starts_with("//")
+        || value_start.is_empty()
+        || value_start.starts_with("//")
+    {
+        return CfgLine::Other(line.into());
     }
 
-    /// Returns the current state of a mod
-    ///
-    /// # Warning
-    /// Returns `true` if a mod is missing from the file
-    pub fn is_enabled(&self, name: impl AsRef<str>) -> bool {
-        self.mods.get(name.as_ref()).copied().unwrap_or(true)
+    let sep = &after[..after.len() - value_start.len()];
+    let (value, quoted, rest) = if let Some(quoted) = value_start.strip_prefix('"') {
+        // quotes in the value are escaped as `\"`
+        let Some(end) = quoted
+            .match_indices('"')
+            .map(|(i, _)| i)
+            .find(|&i| !quoted[..i].ends_with('\\'))
+        else {
+            return CfgLine::Other(line.into());
+        };
+        (
+            quoted[..end].replace("\\\"", "\""),
+            true,
+            &quoted[end + 1..],
+        )
+    } else {
+        let end = value_start
+            .find(char::is_whitespace)
+            .unwrap_or(value_start.len());
+        (value_start[..end].to_owned(), false, &value_start[end..])
+    };
+
+    CfgLine::ConVar {
+        indent: indent.into(),
+        name: name.into(),
+        sep: sep.into(),
+        value,
+        quoted,
+        rest: rest.into(),
     }
+}
 
-    /// Get the current state of a mod if it exists
-    pub fn get(&self, name: impl AsRef<str>) -> Option<bool> {
-        if CORE_MODS.contains(&name.as_ref()) {
-            Some(match name.as_ref() {
-                "Northstar.Client" => self.client,
-                "Northstar.Custom" => self.custom,
-                "Northstar.CustomServers" => self.servers,
-                _ => unimplemented!(),
-            })
-        } else {
-            self.mods.get(name.as_ref()).copied()
+/// Where to get Northstar releases from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum NorthstarChannel {
+    /// The `northstar-Northstar` package on Thunderstore
+    #[default]
+    Thunderstore,
+    /// The latest stable release on GitHub
+    GitHub,
+    /// The newest release on GitHub, including release candidates
+    GitHubPrerelease,
+}
+
+/// Whether the game starts with or without Northstar
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum LaunchMode {
+    Vanilla,
+    Northstar,
+}
+
+impl LaunchMode {
+    /// The argument that selects this mode when the game is started through `Titanfall2.exe`
+    #[must_use]
+    pub const fn launch_arg(self) -> &'static str {
+        match self {
+            Self::Vanilla => "-vanilla",
+            Self::Northstar => "-northstar",
         }
     }
+}
 
-    /// Updates or inserts a mod's state
-    pub fn set(&mut self, name: impl AsRef<str>, val: bool) -> Option<bool> {
-        if CORE_MODS.contains(&name.as_ref().to_lowercase().as_str()) {
-            let prev = self.get(&name);
-            match name.as_ref().to_lowercase().as_str() {
-                "northstar.client" => self.client = val,
-                "northstar.custom" => self.custom = val,
-                "northstar.customservers" => self.servers = val,
-                _ => unimplemented!(),
-            }
-            prev
-        } else {
-            self.mods.insert(name.as_ref().to_string(), val)
-        }
+/// A Northstar release published on GitHub
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NorthstarRelease {
+    pub tag_name: String,
+    pub name: Option<String>,
+    /// Release notes, as markdown
+    #[serde(default)]
+    pub body: Option<String>,
+    /// `true` for release candidates
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub published_at: Option<String>,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+impl NorthstarRelease {
+    /// The release's version, e.g. `1.24.0` for the tag `v1.24.0`
+    #[must_use]
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    /// The zip containing Northstar itself, ready to pass to `install_northstar`
+    #[must_use]
+    pub fn zip(&self) -> Option<&ReleaseAsset> {
+        self.assets.iter().find(|a| {
+            a.name.starts_with("Northstar.")
+                && a.name.ends_with(".zip")
+                && !a.name.to_lowercase().contains("debug")
+        })
     }
 }
 
-/// Represents an installed package
-#[derive(Debug, Clone)]
-pub struct InstalledMod {
-    pub manifest: Manifest,
-    pub mod_json: ModJSON,
-    pub author: String,
-    pub path: PathBuf,
+/// A file attached to a GitHub release
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+    #[serde(default)]
+    pub size: u64,
+    /// The hash GitHub computed for the file, like `sha256:<hex>`. Missing for older releases
+    #[serde(default)]
+    pub digest: Option<String>,
+}
+
+impl ReleaseAsset {
+    /// The hex encoded SHA-256 hash from [`digest`](Self::digest), if GitHub provided one
+    #[must_use]
+    pub fn sha256(&self) -> Option<&str> {
+        self.digest.as_deref()?.strip_prefix("sha256:")
+    }
+}
+
+/// Record of the files extracted by `install_northstar`, stored in the game directory at
+/// [`NORTHSTAR_RECORD`](crate::core::manage::NORTHSTAR_RECORD)
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct NorthstarFiles {
+    /// The installed release, if it could be determined
+    pub version: Option<String>,
+    pub files: Vec<NorthstarFile>,
+}
+
+/// A single file extracted by `install_northstar`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NorthstarFile {
+    /// Path relative to the game directory, always using `/` as the separator
+    pub path: String,
+    /// Hex encoded SHA-256 hash of the file's contents
+    pub sha256: String,
 }
 
 #[cfg(test)]
 mod test {
-    use std::collections::HashMap;
+    use std::{
+        cmp::Ordering,
+        collections::{BTreeMap, HashMap},
+        path::PathBuf,
+    };
 
-    use crate::core::utils::TempDir;
+    use crate::core::{utils::TempDir, validate::Issue};
 
-    use super::{EnabledMods, Manifest, ModJSON};
+    use super::{
+        EnabledMods, FileSize, InstalledMod, LocalIndex, Manifest, Mod, ModJSON, ModName,
+        ModString, ModVersion, NorthstarChannel, NorthstarRelease, PackageIndex, ReleaseAsset,
+        SearchQuery, ServerConfig, Side, StartupArgs, Version,
+    };
 
     const TEST_MOD_JSON: &str = r#"{
         "Name": "Test",
@@ -327,6 +2574,7 @@ mod test {
             con_vars: vec![],
             scripts: vec![],
             localisation: vec![],
+            incompatibilities: vec![],
             _extra: HashMap::new(),
         };
 
@@ -346,6 +2594,7 @@ mod test {
             con_vars: vec![],
             scripts: vec![],
             localisation: vec![],
+            incompatibilities: vec![],
             _extra: HashMap::new(),
         };
 
@@ -368,9 +2617,13 @@ mod test {
         let expected = Manifest {
             name: "Test".into(),
             version_number: "0.1.0".into(),
-            website_url: "https://example.com".into(),
-            description: "Test".into(),
-            dependencies: vec![],
+            website_url: Some("https://example.com".into()),
+            description: Some("Test".into()),
+            dependencies: Some(vec![]),
+            author: None,
+            installed_name: None,
+            incompatibilities: None,
+            _extra: HashMap::new(),
         };
 
         let de = json5::from_str(TEST_MANIFEST);
@@ -379,6 +2632,36 @@ mod test {
         assert_eq!(expected, de.unwrap());
     }
 
+    #[test]
+    fn validate_manifest() {
+        let manifest: Manifest = serde_json::from_str(
+            r#"{
+                "name": "Test Mod",
+                "version_number": "0.1",
+                "dependencies": ["foo-bar-1.0.0", "bad"],
+                "author": "foo",
+                "installed_name": "foo-Test_Mod",
+                "extra": true
+            }"#,
+        )
+        .expect("parse manifest");
+        assert_eq!(manifest.author.as_deref(), Some("foo"));
+        assert_eq!(manifest._extra["extra"], serde_json::Value::Bool(true));
+
+        assert_eq!(
+            manifest.validate(),
+            [
+                Issue::InvalidName("Test Mod".into()),
+                Issue::InvalidVersion("0.1".into()),
+                Issue::MissingField("website_url"),
+                Issue::MissingField("description"),
+                Issue::InvalidDependency("bad".into()),
+            ]
+        );
+        let valid: Manifest = json5::from_str(TEST_MANIFEST).expect("parse manifest");
+        assert!(valid.validate().is_empty());
+    }
+
     #[test]
     fn save_enabled_mods_on_drop() {
         let dir =
@@ -440,4 +2723,723 @@ mod test {
         assert!(test_mod.is_some());
         assert!(!test_mod.unwrap());
     }
+
+    #[test]
+    fn enabled_mods_file_format() {
+        let raw = r#"{
+  "Northstar.Client": true,
+  "Northstar.Custom": false,
+  "Northstar.CustomServers": true,
+  "Alpha": true,
+  "beta": false
+}"#;
+        let mut enabled: EnabledMods =
+            json5::from_str(r#"{"beta": false, "Northstar.Custom": false, "Alpha": true}"#)
+                .expect("parse enabledmods");
+        enabled.dont_save();
+        assert_eq!(
+            serde_json::to_string_pretty(&enabled).expect("serialize"),
+            raw
+        );
+
+        assert_eq!(enabled.remove("Northstar.Client"), None);
+        enabled.set_core_mods(["Northstar.Client", "Northstar.Coop"]);
+        assert!(enabled.is_core("northstar.coop"));
+        assert_eq!(enabled.get("Northstar.Coop"), Some(true));
+        assert_eq!(enabled.remove("Northstar.Custom"), Some(false));
+        assert_eq!(
+            enabled.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            [
+                "Northstar.Client",
+                "Northstar.Coop",
+                "Alpha",
+                "Northstar.CustomServers",
+                "beta"
+            ]
+        );
+    }
+
+    #[test]
+    fn enabled_mods_keep_original_names() {
+        let raw = r#"{
+  "Northstar.Client": true,
+  "Northstar.Custom": true,
+  "Northstar.CustomServers": true,
+  "Beta": true,
+  "Server Utilities": true,
+  "Server_Utilities": false,
+  "alpha": false
+}"#;
+        let mut enabled: EnabledMods = json5::from_str(raw).expect("parse enabledmods");
+        enabled.dont_save();
+        assert_eq!(enabled.len(), 7);
+        assert_eq!(
+            serde_json::to_string_pretty(&enabled).expect("serialize"),
+            raw
+        );
+
+        assert_eq!(enabled.get("ALPHA"), Some(false));
+        assert_eq!(enabled.get("server_utilities"), Some(true));
+        assert_eq!(enabled.set("beta", false), Some(true));
+        assert_eq!(enabled.get("Beta"), Some(false));
+        assert_eq!(enabled.remove("server utilities"), Some(true));
+        assert_eq!(enabled.get("Server_Utilities"), None);
+        assert_eq!(
+            enabled.iter().skip(3).collect::<Vec<_>>(),
+            [("Beta", false), ("alpha", false)]
+        );
+    }
+
+    #[test]
+    fn bulk_enabled_mods() {
+        let mut enabled = EnabledMods::default();
+        enabled.dont_save();
+        enabled.set_many(["Foo.A", "Foo.B", "Bar.C"], true);
+        enabled.set("Northstar.CustomServers", false);
+
+        enabled.disable_all();
+        assert_eq!(
+            enabled.iter().filter(|(_, state)| *state).count(),
+            2,
+            "only the enabled core mods are left"
+        );
+        enabled.enable_all();
+        assert!(enabled.iter().all(|(_, state)| state));
+        assert_eq!(enabled.iter().count(), 6);
+
+        enabled.retain(|name, _| !name.starts_with("Foo."));
+        assert_eq!(
+            enabled.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            [
+                "Northstar.Client",
+                "Northstar.Custom",
+                "Northstar.CustomServers",
+                "Bar.C"
+            ]
+        );
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn generate_schemas() {
+        let mod_json =
+            serde_json::to_value(schemars::schema_for!(ModJSON)).expect("mod.json schema");
+        assert!(mod_json["properties"]["Name"].is_object());
+        assert!(mod_json["required"]
+            .as_array()
+            .expect("required fields")
+            .contains(&"Version".into()));
+
+        let manifest =
+            serde_json::to_value(schemars::schema_for!(Manifest)).expect("manifest schema");
+        assert!(manifest["properties"]["version_number"].is_object());
+
+        let enabled =
+            serde_json::to_value(schemars::schema_for!(EnabledMods)).expect("enabledmods schema");
+        assert_eq!(enabled["type"], "object");
+        assert_eq!(enabled["additionalProperties"]["type"], "boolean");
+
+        let index =
+            serde_json::to_value(schemars::schema_for!(PackageIndex)).expect("index schema");
+        assert_eq!(index["type"], "array");
+        assert_eq!(index["definitions"]["Version"]["type"], "string");
+    }
+
+    #[test]
+    fn order_mod_versions() {
+        let base = ModVersion::test("Foo-Lib-0.1.0", &[]);
+        let version = |v: &str| ModVersion {
+            full_name: format!("Foo-Lib-{v}"),
+            version: v.into(),
+            ..base.clone()
+        };
+        assert!(version("1.9.0") < version("1.10.0"));
+        assert!(version("1.0.0.2") > version("1.0.0.1"));
+        assert_eq!(version("1.2.3").cmp(&version("1.2.3")), Ordering::Equal);
+
+        let m = Mod {
+            latest: "1.10".into(),
+            versions: BTreeMap::from([
+                ("1.10".into(), version("1.10.0")),
+                ("1.9.0".into(), version("1.9.0")),
+                ("0.1.0".into(), version("0.1.0")),
+            ]),
+            ..popularity_mod("Lib", 0, 0)
+        };
+        assert_eq!(
+            m.sorted_versions()
+                .iter()
+                .map(|v| v.version.as_str())
+                .collect::<Vec<_>>(),
+            ["0.1.0", "1.9.0", "1.10.0"]
+        );
+        assert_eq!(
+            m.latest_version().expect("latest version").version,
+            "1.10.0"
+        );
+        assert_eq!(Mod::default().latest_version(), None);
+    }
+
+    #[test]
+    fn compare_mod_names() {
+        let name = ModName::from("Server Utilities");
+        assert_eq!(name, ModName::from(" server_utilities"));
+        assert_eq!(name, "SERVER_UTILITIES");
+        assert_ne!(name, "ServerUtilities");
+        assert_eq!(name.normalized(), "server_utilities");
+        assert_eq!(name.to_string(), "Server Utilities");
+
+        let mut enabled = EnabledMods::default();
+        enabled.dont_save();
+        enabled.set("Foo.Bar", false);
+        assert_eq!(enabled.set("foo.bar", true), Some(false));
+        assert_eq!(enabled.len(), 4);
+        assert_eq!(enabled.iter().last(), Some(("Foo.Bar", true)));
+        assert_eq!(enabled.get("FOO.BAR"), Some(true));
+        assert_eq!(enabled.set("northstar.client", false), Some(true));
+        assert_eq!(enabled.get("Northstar.Client"), Some(false));
+        assert_eq!(enabled.remove("foo.BAR"), Some(true));
+        assert_eq!(enabled.len(), 3);
+
+        let index = PackageIndex::new(vec![Mod {
+            name: "Server_Utilities".into(),
+            author: "Fifty".into(),
+            ..Default::default()
+        }]);
+        assert!(index.get("fifty", "server utilities").is_some());
+        assert!(index.get("someone", "server utilities").is_none());
+    }
+
+    #[test]
+    fn classify_mod_side() {
+        let side = |extra: &str| {
+            json5::from_str::<ModJSON>(&format!(
+                r#"{{ "Name": "Test", "Description": "", "Version": "1.0.0", {extra} }}"#
+            ))
+            .expect("parse mod.json")
+            .side()
+        };
+
+        assert_eq!(side(r#""Scripts": []"#), Side::Client);
+        assert_eq!(side(r#""Localisation": ["a.txt"]"#), Side::Client);
+        assert_eq!(
+            side(r#""Scripts": [{ "Path": "a.nut", "RunOn": "SERVER && MP" }]"#),
+            Side::Server
+        );
+        assert_eq!(
+            side(
+                r#""RequiredOnClient": true, "Scripts": [{ "Path": "a.nut", "RunOn": "SERVER" }]"#
+            ),
+            Side::Both
+        );
+        assert_eq!(
+            side(r#""Scripts": [{ "Path": "a.nut", "RunOn": "( CLIENT || SERVER ) && MP" }]"#),
+            Side::Both
+        );
+        assert!(Side::Both.includes(Side::Server));
+        assert!(!Side::Client.includes(Side::Server));
+    }
+
+    #[test]
+    fn format_file_sizes() {
+        let cases = [
+            (0, "0 B"),
+            (999, "999 B"),
+            (1000, "1.00 KB"),
+            (2_990_000, "2.99 MB"),
+            (999_999, "1.00 MB"),
+            (1_250_000_000, "1.25 GB"),
+            (5_000_000_000_000, "5000.00 GB"),
+        ];
+        for (bytes, expected) in cases {
+            assert_eq!(FileSize::new(bytes).to_string(), expected);
+        }
+
+        assert_eq!(FileSize::new(1023).binary(true).to_string(), "1023 B");
+        assert_eq!(FileSize::new(1536).binary(true).to_string(), "1.50 KiB");
+        assert_eq!(
+            FileSize::new(3 * 1024 * 1024).binary(true).to_string(),
+            "3.00 MiB"
+        );
+
+        let version = ModVersion {
+            file_size: 2_990_000,
+            ..ModVersion::test("Foo-Lib-0.1.0", &[])
+        };
+        assert_eq!(version.file_size_string(), "2.99 MB");
+    }
+
+    #[test]
+    fn convert_mod_strings() {
+        let parsed: ModString = "Foo-My-Mod-1.0.0.1".parse().expect("parse modstring");
+        assert_eq!(parsed, ModString::new("Foo", "My-Mod", "1.0.0.1"));
+        assert_eq!(parsed.package(), "Foo-My-Mod");
+        assert_eq!(parsed.to_string(), "Foo-My-Mod-1.0.0.1");
+        assert!("Foo-Mod".parse::<ModString>().is_err());
+
+        let json = serde_json::to_string(&parsed).expect("serialize modstring");
+        assert_eq!(json, r#""Foo-My-Mod-1.0.0.1""#);
+        assert_eq!(
+            serde_json::from_str::<ModString>(&json).expect("deserialize modstring"),
+            parsed
+        );
+        assert!(serde_json::from_str::<ModString>(r#""not a modstring""#).is_err());
+
+        let manifest: Manifest = json5::from_str(TEST_MANIFEST).expect("parse manifest");
+        let installed = InstalledMod {
+            mod_json: json5::from_str(TEST_MOD_JSON).expect("parse mod.json"),
+            author: "Foo".into(),
+            version: manifest.version_number.clone(),
+            manifest,
+            path: PathBuf::new(),
+        };
+        let from_installed = ModString::from(&installed);
+        assert_eq!(from_installed.to_string(), installed.full_name());
+
+        let version = ModVersion::test("Foo-Lib-0.1.0", &[]);
+        let from_version = ModString::try_from(&version).expect("convert version");
+        assert_eq!(from_version.to_string(), version.full_name);
+    }
+
+    #[test]
+    fn sync_enabled_mods() {
+        let manifest: Manifest = json5::from_str(TEST_MANIFEST).expect("parse manifest");
+        let installed = ["Foo.New", "Foo.Kept", "Northstar.Client"].map(|name| InstalledMod {
+            manifest: manifest.clone(),
+            mod_json: ModJSON {
+                name: name.into(),
+                description: String::new(),
+                version: "1.0.0".into(),
+                load_priority: None,
+                required_on_client: None,
+                con_vars: vec![],
+                scripts: vec![],
+                localisation: vec![],
+                incompatibilities: vec![],
+                _extra: HashMap::new(),
+            },
+            author: "Foo".into(),
+            version: "1.0.0".into(),
+            path: PathBuf::new(),
+        });
+
+        let mut mods = EnabledMods::default();
+        mods.set("Foo.Kept", false);
+        mods.set("Foo.Gone", true);
+        mods.set("Northstar.Custom", false);
+
+        let report = mods.sync(&installed);
+        assert_eq!(report.added, ["Foo.New"]);
+        assert_eq!(report.removed, ["Foo.Gone"]);
+        assert_eq!(mods.get("Foo.Kept"), Some(false));
+        assert_eq!(mods.get("Foo.New"), Some(true));
+        assert_eq!(mods.get("Northstar.Custom"), Some(false));
+        assert!(mods.sync(&installed).is_empty());
+    }
+
+    fn popularity_mod(name: &str, downloads: u64, rating_score: i64) -> Mod {
+        Mod {
+            name: name.into(),
+            latest: "0.1.0".into(),
+            versions: BTreeMap::from([(
+                "0.1.0".into(),
+                ModVersion {
+                    downloads,
+                    ..ModVersion::test(&format!("Foo-{name}-0.1.0"), &[])
+                },
+            )]),
+            author: "Foo".into(),
+            rating_score,
+            deprecated: false,
+            categories: vec![],
+            date_created: String::new(),
+            date_updated: String::new(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sort_index_by_popularity() {
+        let mut index = PackageIndex::from(vec![
+            popularity_mod("Least", 1, 10),
+            popularity_mod("Most", 100, 0),
+            popularity_mod("TieLow", 50, 1),
+            popularity_mod("TieHigh", 50, 5),
+        ]);
+
+        index.sort_by_popularity();
+
+        let names: Vec<&str> = index.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["Most", "TieHigh", "TieLow", "Least"]);
+    }
+
+    #[allow(deprecated)]
+    fn search_index() -> PackageIndex {
+        let mut deprecated = popularity_mod("Old_Utilities", 0, 0);
+        deprecated.deprecated = true;
+        let mut installed = popularity_mod("Server_Utilities", 0, 0);
+        installed.categories = vec!["Server-side".into()];
+
+        PackageIndex::from(vec![
+            popularity_mod("Utilities", 0, 0),
+            installed,
+            deprecated,
+            popularity_mod("Maps", 0, 0),
+        ])
+    }
+
+    #[test]
+    fn search_index_by_name() {
+        let index = search_index();
+        let names: Vec<&str> = index
+            .search("utilities")
+            .iter()
+            .map(|m| m.name.as_str())
+            .collect();
+
+        assert_eq!(names, ["Utilities", "Server_Utilities"]);
+    }
+
+    #[test]
+    fn search_index_fuzzy() {
+        let index = search_index();
+        let res = index.search("srvutil");
+
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].name, "Server_Utilities");
+    }
+
+    #[test]
+    fn search_index_filters() {
+        let index = search_index();
+
+        let manifest = Manifest {
+            name: "server_utilities".into(),
+            ..json5::from_str(TEST_MANIFEST).expect("parse manifest")
+        };
+        let installed = InstalledMod {
+            mod_json: json5::from_str(TEST_MOD_JSON).expect("parse mod.json"),
+            author: "foo".into(),
+            version: manifest.version_number.clone(),
+            manifest,
+            path: PathBuf::new(),
+        };
+        let res = index.search(SearchQuery::all().installed(std::slice::from_ref(&installed)));
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].name, "Server_Utilities");
+        assert!(index.search(SearchQuery::all().installed(&[])).is_empty());
+
+        let res = index.search(SearchQuery::new("old").deprecated(true));
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].name, "Old_Utilities");
+
+        let res = index.search(SearchQuery::all().category("server-side"));
+        assert_eq!(res.len(), 1);
+
+        assert_eq!(index.search(SearchQuery::all()).len(), 3);
+    }
+
+    #[test]
+    fn merge_indexes() {
+        let mut a = popularity_mod("Shared", 10, 0);
+        a.categories = vec!["Old".into()];
+        let mut b = popularity_mod("Shared", 5, 0);
+        b.latest = "0.10.0".into();
+        b.categories = vec!["New".into()];
+        b.versions.insert(
+            "0.10.0".into(),
+            ModVersion {
+                version: "0.10.0".into(),
+                downloads: 1,
+                ..b.versions[&"0.1.0".into()].clone()
+            },
+        );
+        b.versions.remove(&"0.1.0".into());
+
+        let mut index = PackageIndex::from(vec![a, popularity_mod("OnlyA", 0, 0)]);
+        index.merge(PackageIndex::from(vec![b, popularity_mod("OnlyB", 0, 0)]));
+
+        assert_eq!(index.len(), 3);
+        let shared = index
+            .iter()
+            .find(|m| m.name == "Shared")
+            .expect("merged mod");
+        assert_eq!(shared.latest, "0.10.0");
+        assert_eq!(shared.categories, ["New"]);
+        assert_eq!(shared.versions.len(), 2);
+        assert_eq!(shared.total_downloads(), 11);
+    }
+
+    #[test]
+    fn order_versions() {
+        let mut versions = [
+            "1.0.0",
+            "0.10.0",
+            "1.0.0-rc1",
+            "0.9.0",
+            "1.0.0.1",
+            "1.0.0.10",
+        ]
+        .map(Version::from)
+        .to_vec();
+        versions.sort();
+
+        assert_eq!(
+            versions,
+            [
+                "0.9.0",
+                "0.10.0",
+                "1.0.0-rc1",
+                "1.0.0",
+                "1.0.0.1",
+                "1.0.0.10"
+            ]
+            .map(Version::from)
+        );
+        assert!(Version::from("1.0.0.1").semver().is_some());
+        assert!(Version::from("1.0").semver().is_none());
+        assert_eq!(
+            Version::new("1.10").cmp(&Version::new("1.9")),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn latest_version_is_semver() {
+        let mut m = popularity_mod("Sorted", 0, 0);
+        let v = m.versions[&"0.1.0".into()].clone();
+        for version in ["0.9.0", "0.10.0"] {
+            m.versions.insert(
+                version.into(),
+                ModVersion {
+                    version: version.into(),
+                    ..v.clone()
+                },
+            );
+        }
+        m.latest = "2.0.0".into();
+
+        assert_eq!(m.get_latest().map(|v| v.version.as_str()), Some("0.10.0"));
+        assert_eq!(
+            serde_json::to_string(&m.versions.keys().collect::<Vec<_>>()).expect("serialize"),
+            r#"["0.1.0","0.9.0","0.10.0"]"#
+        );
+
+        let raw = serde_json::to_string(&m).expect("serialize mod");
+        let parsed: Mod = serde_json::from_str(&raw).expect("deserialize mod");
+        assert_eq!(parsed, m);
+    }
+
+    #[test]
+    fn record_local_packages() {
+        let dir = TempDir::create("./local_index").expect("Unable to create temp dir");
+        let path = dir.join("thermite_index.json");
+        let mut index = LocalIndex::default_with_path(&path);
+        let lib = ModVersion::test("Foo-Lib-0.1.0", &[]);
+        let app = ModVersion {
+            full_name: "Foo-App-0.1.0".into(),
+            deps: vec!["Foo-Lib-0.1.0".into()],
+            ..lib.clone()
+        };
+
+        index.record(&lib, &[], None, true).expect("record lib");
+        index
+            .record(&app, &[], Some("abc".into()), true)
+            .expect("record app");
+        index.record(&lib, &[], None, false).expect("reinstall lib");
+        assert!(index.get("foo-lib").expect("lib in index").explicit);
+
+        assert!(index.set_explicit("Foo-Lib-0.1.0", false));
+        assert!(!index.set_explicit("Foo-Missing", false));
+        index.save().expect("save index");
+
+        let loaded = LocalIndex::load(&path).expect("load index");
+        assert_eq!(loaded, index);
+        let app = loaded.get("Foo-App").expect("app in index");
+        assert_eq!(app.hash.as_deref(), Some("abc"));
+        assert_eq!(app.deps, ["Foo-Lib-0.1.0"]);
+        assert!(!loaded.get("Foo-Lib").expect("lib in index").explicit);
+
+        index.remove("Foo-App-0.1.0");
+        assert_eq!(index.len(), 1);
+    }
+
+    #[test]
+    fn parse_startup_args() {
+        let raw = r#"-multiple -profile=dev +setplaylist private_match -port 37015 +ns_server_name "My Server""#;
+        let args = StartupArgs::parse(raw);
+
+        assert!(args.contains("-multiple"));
+        assert_eq!(
+            args.get("-profile").and_then(|a| a.value.as_deref()),
+            Some("dev")
+        );
+        assert_eq!(
+            args.get("+setplaylist").and_then(|a| a.value.as_deref()),
+            Some("private_match")
+        );
+        assert_eq!(
+            args.get("-port").and_then(|a| a.value.as_deref()),
+            Some("37015")
+        );
+        assert_eq!(
+            args.get("+ns_server_name").and_then(|a| a.value.as_deref()),
+            Some("My Server")
+        );
+        assert_eq!(args.to_string(), raw);
+    }
+
+    #[test]
+    fn edit_startup_args() {
+        let mut args = StartupArgs::parse("-multiple -profile=dev");
+        let prev = args.set_arg("-profile", Some("test"));
+        assert_eq!(prev.and_then(|a| a.value), Some("dev".into()));
+        args.set_arg("-novid", None::<String>);
+        args.set_arg("+mp_gamemode", Some("ps"));
+        assert!(args.remove_arg("-multiple").is_some());
+        assert!(args.remove_arg("-multiple").is_none());
+
+        assert_eq!(args.to_string(), "-profile=test -novid +mp_gamemode ps");
+    }
+
+    #[test]
+    fn round_trip_startup_args_with_quotes() {
+        let mut args = StartupArgs::default();
+        args.set_arg("+ns_server_name", Some(r#"The "Best" Server"#));
+        args.set_arg("-motd", Some(r#"say"hi""#));
+        args.set_arg("-dir", Some(r"C:\Games\Titanfall2\"));
+        args.set_arg("-quoted", Some(r#"C:\My Games\"#));
+        args.set_arg("-empty", Some(""));
+
+        let raw = args.to_string();
+        assert_eq!(
+            raw,
+            r#"+ns_server_name "The \"Best\" Server" -motd="say\"hi\"" -dir=C:\Games\Titanfall2\ -quoted="C:\My Games\\" -empty="""#
+        );
+        assert_eq!(StartupArgs::parse(&raw), args);
+    }
+
+    #[test]
+    fn save_startup_args() {
+        let dir = TempDir::create("./save_startup_args").expect("Unable to create temp dir");
+        let path = dir.join("ns_startup_args.txt");
+        let mut args = StartupArgs::default_with_path(&path);
+        args.set_arg("-profile", Some("dev"));
+        args.save().expect("save args");
+
+        let loaded = StartupArgs::load(&path).expect("load args");
+        assert_eq!(loaded, args);
+    }
+
+    const TEST_SERVER_CFG: &str = "ns_server_name \"Unnamed Northstar Server\" // server name\r\nns_server_password \"\" // server password\r\n\r\n// gameserver settings\r\n  base_tickinterval_mp 0.016666667 // default tickrate: 60 tick\r\nannouncement #PROGRESSION_ANNOUNCEMENT_BODY\r\n";
+
+    #[test]
+    fn round_trip_server_config() {
+        let config = ServerConfig::parse(TEST_SERVER_CFG);
+
+        assert_eq!(config.server_name(), Some("Unnamed Northstar Server"));
+        assert_eq!(config.password(), None);
+        assert_eq!(config.get("base_tickinterval_mp"), Some("0.016666667"));
+        assert_eq!(
+            config.get("announcement"),
+            Some("#PROGRESSION_ANNOUNCEMENT_BODY")
+        );
+        assert_eq!(config.to_string(), TEST_SERVER_CFG);
+    }
+
+    #[test]
+    fn edit_server_config() {
+        let mut config = ServerConfig::parse(TEST_SERVER_CFG);
+        let prev = config.set_server_name("My Server");
+        assert_eq!(prev.as_deref(), Some("Unnamed Northstar Server"));
+        config.set_password("hunter2");
+        config.set_playlist("private_match");
+        config.set("ns_erase_auth_info", "1");
+        assert_eq!(
+            config.remove("announcement").as_deref(),
+            Some("#PROGRESSION_ANNOUNCEMENT_BODY")
+        );
+
+        assert_eq!(
+            config.to_string(),
+            "ns_server_name \"My Server\" // server name\r\nns_server_password \"hunter2\" // server password\r\n\r\n// gameserver settings\r\n  base_tickinterval_mp 0.016666667 // default tickrate: 60 tick\r\nsetplaylist \"private_match\"\r\nns_erase_auth_info 1\r\n"
+        );
+        assert_eq!(config.password(), Some("hunter2"));
+
+        let mut config =
+            ServerConfig::parse("ns_server_name \"Old\"\r\nns_should_return_to_lobby 0");
+        config.set_server_name("The \"Best\" Server");
+        config.set("ns_erase_auth_info", "1");
+        assert_eq!(
+            config.to_string(),
+            "ns_server_name \"The \\\"Best\\\" Server\"\r\nns_should_return_to_lobby 0\r\nns_erase_auth_info 1"
+        );
+        let reparsed = ServerConfig::parse(&config.to_string());
+        assert_eq!(reparsed.server_name(), Some("The \"Best\" Server"));
+        assert_eq!(reparsed, config);
+
+        let mut config = ServerConfig::parse("ns_server_name \"Old\"\n");
+        config.set("ns_erase_auth_info", "1");
+        assert_eq!(
+            config.to_string(),
+            "ns_server_name \"Old\"\nns_erase_auth_info 1\n"
+        );
+    }
+
+    #[test]
+    fn northstar_channel_serde() {
+        let channel: NorthstarChannel =
+            serde_json::from_str("\"gitHubPrerelease\"").expect("parse channel");
+        assert_eq!(channel, NorthstarChannel::GitHubPrerelease);
+        assert_eq!(NorthstarChannel::default(), NorthstarChannel::Thunderstore);
+        assert_eq!(
+            serde_json::to_string(&NorthstarChannel::GitHub).expect("serialize channel"),
+            "\"gitHub\""
+        );
+    }
+
+    #[test]
+    fn parse_github_release() {
+        let release: NorthstarRelease = serde_json::from_str(
+            r#"{
+                "tag_name": "v1.24.0-rc1",
+                "name": "v1.24.0-rc1",
+                "prerelease": true,
+                "draft": false,
+                "assets": [
+                    {"name": "northstar-debug-symbols.zip", "browser_download_url": "https://example.com/debug.zip", "size": 1},
+                    {"name": "Northstar.release.v1.24.0-rc1.zip", "browser_download_url": "https://example.com/ns.zip", "size": 2, "digest": "sha256:abcd"}
+                ]
+            }"#,
+        )
+        .expect("parse release");
+
+        assert!(release.prerelease);
+        assert_eq!(release.version(), "1.24.0-rc1");
+        assert_eq!(
+            release.zip().map(|a| a.browser_download_url.as_str()),
+            Some("https://example.com/ns.zip")
+        );
+        assert_eq!(release.zip().and_then(ReleaseAsset::sha256), Some("abcd"));
+        assert_eq!(release.assets[0].sha256(), None);
+    }
+
+    #[cfg(feature = "timestamps")]
+    #[test]
+    fn parse_timestamps() {
+        let mut old = popularity_mod("Old", 0, 0);
+        old.date_updated = "2023-01-01T00:00:00Z".into();
+        let mut new = popularity_mod("New", 0, 0);
+        new.date_updated = "2023-06-01T12:30:00.123456Z".into();
+        let invalid = popularity_mod("Invalid", 0, 0);
+
+        assert!(new.updated().is_some());
+        assert!(invalid.updated().is_none());
+        assert!(new.updated() > old.updated());
+
+        let mut index = PackageIndex::from(vec![invalid, old, new]);
+        index.sort_by_updated();
+        let names: Vec<&str> = index.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["New", "Old", "Invalid"]);
+    }
 }